@@ -1,3 +1,4 @@
+use crate::width::is_zero_width;
 use std::iter::FromIterator;
 
 #[derive(Clone, Default)]
@@ -59,6 +60,105 @@ impl Buffer {
         }
     }
 
+    /// Move over the current char and the combining chars drawn over it
+    pub fn move_forward_cluster(&mut self) {
+        if self.is_at_end() {
+            return;
+        }
+        self.move_forward();
+        while self.current_char().copied().map_or(false, is_zero_width) {
+            self.move_forward();
+        }
+    }
+
+    /// Move before the previous char and the combining chars drawn over it
+    pub fn move_backward_cluster(&mut self) {
+        self.move_backward();
+        while self.current_char().copied().map_or(false, is_zero_width) && !self.is_at_start() {
+            self.move_backward();
+        }
+    }
+
+    /// Remove the current char and the combining chars drawn over it
+    pub fn remove_current_cluster(&mut self) {
+        if self.remove_current_char().is_some() {
+            while self.current_char().copied().map_or(false, is_zero_width) {
+                self.remove_current_char();
+            }
+        }
+    }
+
+    /// Move backward over the rest of the word the cursor is on, exp: `fo|o` -> `|foo`
+    ///
+    /// A word is a run of alphanumeric chars, of spaces or of symbols
+    pub fn move_to_word_start(&mut self) {
+        if let Some(current_char) = self.current_char() {
+            match *current_char {
+                ' ' => {
+                    while self.previous_char() == Some(&' ') {
+                        self.move_backward()
+                    }
+                }
+                c if c.is_alphanumeric() => {
+                    while let Some(previous_char) = self.previous_char() {
+                        if previous_char.is_alphanumeric() {
+                            self.move_backward()
+                        } else {
+                            break;
+                        }
+                    }
+                }
+
+                _ => {
+                    while let Some(previous_char) = self.previous_char() {
+                        if !previous_char.is_alphanumeric() && *previous_char != ' ' {
+                            self.move_backward()
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move forward over the rest of the word the cursor is on, spaces are skipped to the next word
+    pub fn move_to_word_end(&mut self) {
+        if let Some(current_char) = self.current_char() {
+            match *current_char {
+                ' ' => {
+                    while self.next_char() == Some(&' ') {
+                        self.move_forward();
+                    }
+                    self.move_forward();
+                }
+                c if c.is_alphanumeric() => {
+                    while let Some(character) = self.current_char() {
+                        if !character.is_alphanumeric() {
+                            break;
+                        }
+                        self.move_forward();
+                    }
+                }
+
+                _ => {
+                    while let Some(character) = self.current_char() {
+                        if character.is_alphanumeric() || *character == ' ' {
+                            break;
+                        }
+                        self.move_forward();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove the chars in `start..end` and move the cursor to `start`
+    pub fn remove_range(&mut self, start: usize, end: usize) {
+        self.buffer.drain(start..end);
+        self.buffer_pos = start;
+    }
+
     pub fn clear(&mut self) {
         self.buffer.clear();
         self.buffer_pos = 0;
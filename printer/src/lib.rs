@@ -1,4 +1,5 @@
 pub mod buffer;
 pub mod printer;
+pub mod width;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
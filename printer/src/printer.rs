@@ -4,7 +4,9 @@ use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 use crate::{buffer::Buffer, Result};
 
 mod cursor;
+mod frame;
 mod writer;
+use frame::Frame;
 
 #[cfg(test)]
 mod tests;
@@ -15,24 +17,69 @@ pub struct Printer<W: std::io::Write> {
     pub writer: writer::Writer<W>,
     pub cursor: cursor::Cursor<W>,
     pub prompt: String,
+    pub prompt_color: Color,
+    /// Drawn on the last terminal row, which is excluded from the scroll region
+    status_line: Option<String>,
+    pub status_line_color: Color,
+    /// Ranges of input chars (buffer positions) drawn underlined, exp: compiler errors
+    pub underlined: Vec<std::ops::Range<usize>>,
+    pub underline_color: Color,
+    /// Drawn on the first terminal rows, which are excluded from the scroll region
+    top_pane: Option<Pane>,
+    pub top_pane_color: Color,
+    /// Last drawn input, `None` if the screen changed since
+    frame: Option<Frame>,
+    alternate_screen: bool,
+}
+
+/// Maximum number of lines kept by the top pane
+const TOP_PANE_MAX_LINES: usize = 1000;
+
+/// Output area drawn above the input, exp: stderr of the evaluated code
+#[derive(Debug, Clone)]
+struct Pane {
+    rows: u16,
+    /// Lines without a color use `top_pane_color`
+    lines: Vec<(String, Option<Color>)>,
+    /// Number of lines under the view, 0 means following the new output
+    scroll: usize,
 }
 
 impl<W: std::io::Write> Printer<W> {
     pub fn new(raw: W, prompt: String) -> Printer<W> {
         crossterm::terminal::enable_raw_mode().expect("failed to enable raw_mode");
         let raw = Rc::new(RefCell::new(raw));
-        let prompt_len = prompt.chars().count();
+        let prompt_len = crate::width::str_width(&prompt);
         Self {
             printer: PrintQueue::default(),
             writer: writer::Writer::new(raw.clone()),
             cursor: cursor::Cursor::new(raw, prompt_len),
             prompt,
+            prompt_color: Color::Yellow,
+            status_line: None,
+            status_line_color: Color::DarkGrey,
+            underlined: Vec::new(),
+            underline_color: Color::Red,
+            top_pane: None,
+            top_pane_color: Color::DarkYellow,
+            frame: None,
+            alternate_screen: false,
         }
     }
 }
 
 impl<W: std::io::Write> Drop for Printer<W> {
     fn drop(&mut self) {
+        // give back the last row
+        let _ = self.set_status_line(None);
+        if self.top_pane.take().is_some() {
+            let _ = self.set_scroll_region(None);
+        }
+        let _ = self.leave_alternate_screen();
+        // the drop can come from a panic in the middle of a draw
+        let _ = self.writer.raw.reset_color();
+        let _ = self.cursor.raw.show();
+        let _ = std::io::Write::flush(&mut self.writer.raw);
         let _ = crossterm::terminal::disable_raw_mode();
     }
 }
@@ -99,97 +146,131 @@ impl<W: std::io::Write> Printer<W> {
         if self.check_for_offscreen_render_hack(buffer)? {
             return Ok(());
         }
-
+        self.print_input_from_queue(process_function(buffer), buffer)
+    }
+    /// FIXME: This function takes the buffer just to calculate if it needs scrolling
+    pub fn print_input_from_queue(&mut self, queue: PrintQueue, buffer: &Buffer) -> Result<()> {
         self.cursor.hide();
         // scroll if needed before writing the input
         self.scroll_if_needed_for_input(&buffer);
         self.cursor.save_position();
-        self.cursor.goto_start();
-        self.writer.raw.clear(ClearType::FromCursorDown)?;
 
-        self.print_prompt_if_set()?;
-
-        self.print_input_inner(process_function(&buffer))?;
-        //bound last row to last position
-        self.cursor.bound_current_row_at_current_col();
+        let frame = self.layout_input(queue);
+        self.draw_frame(frame)?;
 
         self.cursor.restore_position();
+        // the clear under the input ignores the scroll region
+        self.draw_status_line()?;
         self.cursor.show();
 
         Ok(())
     }
-    /// FIXME: This function takes the buffer just to calculate if it needs scrolling
-    pub fn print_input_from_queue(&mut self, queue: PrintQueue, buffer: &Buffer) -> Result<()> {
-        self.cursor.hide();
-        // scroll if needed before writing the input
-        self.scroll_if_needed_for_input(&buffer);
-        self.cursor.save_position();
+
+    /// Compute the cells of the input and the bounds of its rows, nothing is drawn
+    fn layout_input(&mut self, queue: PrintQueue) -> Frame {
+        let mut frame = Frame::new(self.cursor.starting_pos().1);
+        self.cursor.set_detached(true);
         self.cursor.goto_start();
-        self.writer.raw.clear(ClearType::FromCursorDown)?;
 
-        self.print_prompt_if_set()?;
+        let prompt = self.prompt.clone();
+        self.cursor.goto(0, self.cursor.current_pos().1);
+        self.put_str(&mut frame, &prompt, self.prompt_color);
 
-        self.print_input_inner(queue)?;
+        self.layout_input_inner(&mut frame, queue);
         //bound last row to last position
         self.cursor.bound_current_row_at_current_col();
+        self.cursor.set_detached(false);
+        frame
+    }
 
-        self.cursor.restore_position();
-        self.cursor.show();
-
+    /// Draw the cells that changed since the last frame in one write, the cursor movements included
+    fn draw_frame(&mut self, frame: Frame) -> Result<()> {
+        let mut out = vec![];
+        let color = frame.draw(self.frame.as_ref(), self.cursor.height(), &mut out)?;
+        std::io::Write::write_all(&mut self.writer.raw, &out)?;
+        self.writer.set_last_color(color);
+        self.frame = Some(frame);
         Ok(())
     }
 
-    fn print_input_inner(&mut self, printer: PrintQueue) -> Result<()> {
+    /// The next input is drawn entirely, needed when the screen changes under it
+    fn forget_frame(&mut self) {
+        self.frame = None;
+    }
+
+    fn layout_input_inner(&mut self, frame: &mut Frame, printer: PrintQueue) {
+        // index of the printed char in the buffer, used to find the underlined ones
+        let mut idx = 0;
         for item in printer {
             match item {
                 PrinterItem::String(string, color) => {
-                    self.print_input_str(&string, color)?;
+                    self.layout_input_str(frame, &string, color, &mut idx);
                 }
                 PrinterItem::Str(string, color) => {
-                    self.print_input_str(&string, color)?;
+                    self.layout_input_str(frame, &string, color, &mut idx);
                 }
                 PrinterItem::Char(c, color) => {
-                    self.print_input_char(c, color)?;
+                    self.layout_input_str(frame, &c.to_string(), color, &mut idx);
                 }
                 PrinterItem::NewLine => {
                     self.cursor.bound_current_row_at_current_col();
                     self.cursor.goto_next_row_terminal_start();
-                    self.print_extra_lines_indicator_if_needed(false)?;
+                    self.put_extra_lines_indicator_if_needed(frame, false);
+                    idx += 1;
                 }
             }
         }
-
-        Ok(())
     }
 
-    fn print_input_str(&mut self, string: &str, color: Color) -> Result<()> {
+    fn layout_input_str(&mut self, frame: &mut Frame, string: &str, color: Color, idx: &mut usize) {
         for c in string.chars() {
-            self.print_input_char(c, color)?;
+            if self.underlined.iter().any(|range| range.contains(idx)) {
+                self.layout_input_char(frame, c, self.underline_color, true);
+            } else {
+                self.layout_input_char(frame, c, color, false);
+            }
+            *idx += 1;
         }
-        Ok(())
     }
 
-    fn print_input_char(&mut self, c: char, color: Color) -> Result<()> {
+    fn layout_input_char(&mut self, frame: &mut Frame, c: char, color: Color, underlined: bool) {
         if c == '\n' {
             // this can happen if the user uses a multiline string
             self.cursor.bound_current_row_at_current_col();
             self.cursor.goto_next_row_terminal_start();
-            self.print_extra_lines_indicator_if_needed(false)?;
-            return Ok(());
+            self.put_extra_lines_indicator_if_needed(frame, false);
+            return;
         }
-        self.writer
-            .write_char_with_color(c, color, &mut self.cursor)?;
+        if self.cursor.wraps_before(c) {
+            self.cursor.bound_current_row_at_current_col();
+            self.cursor.move_right_unbounded();
+            self.put_extra_lines_indicator_if_needed(frame, true);
+        }
+        self.put_char(frame, c, color, underlined);
         if self.cursor.is_at_last_terminal_col() {
             self.cursor.bound_current_row_at_current_col();
         }
 
         if self.cursor.is_at_col(self.prompt_len()) {
-            self.print_extra_lines_indicator_if_needed(true)?;
+            self.put_extra_lines_indicator_if_needed(frame, true);
+        }
+    }
+
+    /// Put the char at the cursor position in the frame and move over it
+    fn put_char(&mut self, frame: &mut Frame, c: char, color: Color, underlined: bool) {
+        let (x, y) = self.cursor.current_pos();
+        frame.put(x, y, c, color, underlined);
+        self.cursor.move_right_over(c);
+    }
+
+    fn put_str(&mut self, frame: &mut Frame, string: &str, color: Color) {
+        for c in string.chars() {
+            self.put_char(frame, c, color, false);
         }
-        Ok(())
     }
 
     pub fn print_output(&mut self, printer: PrintQueue) -> Result<()> {
+        self.forget_frame();
         for item in printer {
             match item {
                 PrinterItem::Char(c, color) => {
@@ -223,7 +304,7 @@ impl<W: std::io::Write> Printer<W> {
 
         let height_overflow = input_last_row.saturating_sub(self.cursor.height() - 1);
         if height_overflow > 0 {
-            self.writer.scroll_up(height_overflow, &mut self.cursor);
+            self.scroll_up(height_overflow);
         }
     }
 
@@ -253,8 +334,15 @@ impl<W: std::io::Write> Printer<W> {
         }
     }
 
-    fn adjust(&mut self) {
-        self.cursor.move_right_unbounded();
+    fn adjust(&mut self, c: char) {
+        if self.cursor.wraps_before(c) {
+            self.cursor.bound_current_row_at_current_col();
+            self.cursor.move_right_unbounded();
+            for _ in 0..4 {
+                self.cursor.move_right_unbounded();
+            }
+        }
+        self.cursor.move_right_over(c);
         if self.cursor.is_at_last_terminal_col() {
             self.cursor.bound_current_row_at_current_col();
         }
@@ -274,17 +362,17 @@ impl<W: std::io::Write> Printer<W> {
         for item in printer {
             match item {
                 PrinterItem::String(string, _) => {
-                    for _ in string.chars() {
-                        self.adjust();
+                    for c in string.chars() {
+                        self.adjust(c);
                     }
                 }
                 PrinterItem::Str(string, _) => {
-                    for _ in string.chars() {
-                        self.adjust();
+                    for c in string.chars() {
+                        self.adjust(c);
                     }
                 }
-                PrinterItem::Char(_, _) => {
-                    self.adjust();
+                PrinterItem::Char(c, _) => {
+                    self.adjust(c);
                 }
                 PrinterItem::NewLine => {
                     self.cursor.bound_current_row_at_current_col();
@@ -305,69 +393,378 @@ impl<W: std::io::Write> Printer<W> {
 
     pub fn print_prompt_if_set(&mut self) -> Result<()> {
         let prompt = &self.prompt.clone();
-        self.write_from_terminal_start(&prompt, Color::Yellow)?;
+        self.write_from_terminal_start(&prompt, self.prompt_color)?;
         Ok(())
     }
 
     pub fn prompt_len(&self) -> usize {
-        self.prompt.chars().count()
+        crate::width::str_width(&self.prompt)
     }
 
     pub fn set_prompt(&mut self, prompt: String) {
         self.prompt = prompt;
         self.cursor.prompt_len = self.prompt_len();
     }
+
+    /// Write `rows` on the terminal rows under the input, the cursor position is kept
+    ///
+    /// The caller needs to scroll first if there is no room left, see `screen_height_overflow_by_new_lines`
+    /// Each row is made of (text, foreground, optional background) segments, long rows are truncated
+    pub fn print_below_input(
+        &mut self,
+        rows: Vec<Vec<(String, Color, Option<Color>)>>,
+        buffer: &Buffer,
+    ) -> Result<()> {
+        let max_width = self.cursor.width() - 1;
+        let input_last_row = self.cursor.input_last_pos(buffer).1;
+
+        self.cursor.hide();
+        self.cursor.save_position();
+        for (idx, row) in rows.into_iter().enumerate() {
+            self.cursor.goto(0, input_last_row + 1 + idx);
+            self.writer.raw.clear(ClearType::CurrentLine)?;
+
+            let row_width: usize = row.iter().map(|(text, ..)| text.chars().count()).sum();
+            let mut width_left = if row_width > max_width {
+                max_width - 3
+            } else {
+                max_width
+            };
+            for (text, fg, bg) in row {
+                let text: String = text.chars().take(width_left).collect();
+                width_left -= text.chars().count();
+                self.writer.raw.set_fg(fg)?;
+                if let Some(bg) = bg {
+                    self.writer.raw.set_bg(bg)?;
+                }
+                self.writer.raw.write(&text)?;
+                self.writer.raw.set_bg(Color::Reset)?;
+            }
+            if row_width > max_width {
+                self.writer.raw.write("...")?;
+            }
+        }
+        self.writer.raw.reset_color()?;
+        self.cursor.restore_position();
+        self.cursor.goto_internal_pos();
+        self.cursor.show();
+
+        Ok(())
+    }
+
+    /// Give the terminal back to another interactive program, `resume` takes it again
+    pub fn suspend(&mut self) -> Result<()> {
+        self.forget_frame();
+        self.writer.raw.write("\x1b[r")?;
+        self.writer.raw.clear(ClearType::All)?;
+        self.cursor.raw.goto(0, 0)?;
+        std::io::Write::flush(&mut self.writer.raw)?;
+        crossterm::terminal::disable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Restore raw mode and the scroll region after `suspend`, the program output is kept on screen
+    pub fn resume(&mut self) -> Result<()> {
+        crossterm::terminal::enable_raw_mode()?;
+        let (width, height) = crossterm::terminal::size()?;
+        self.update_dimensions(width, height)
+    }
+
+    /// Draw on the terminal alternate screen, the previous screen is shown untouched when leaving it
+    ///
+    /// It's also used to come back after a program that left it, exp: an editor
+    pub fn enter_alternate_screen(&mut self) -> Result<()> {
+        self.writer.raw.enter_alternate_screen()?;
+        self.alternate_screen = true;
+        // the scroll region is set again for the status line and the top pane
+        let (width, height) = crossterm::terminal::size()?;
+        self.update_dimensions(width, height)?;
+        self.clear()
+    }
+
+    /// The status line and the top pane are kept for the next `enter_alternate_screen`
+    pub fn leave_alternate_screen(&mut self) -> Result<()> {
+        if !self.alternate_screen {
+            return Ok(());
+        }
+        // the scroll region is shared by both screens
+        self.writer.raw.write("\x1b[r")?;
+        self.writer.raw.leave_alternate_screen()?;
+        self.alternate_screen = false;
+        self.forget_frame();
+        Ok(())
+    }
+
+    pub fn is_in_alternate_screen(&self) -> bool {
+        self.alternate_screen
+    }
+
+    /// Show a status line on the last terminal row, `None` removes it
+    pub fn set_status_line(&mut self, status: Option<String>) -> Result<()> {
+        self.forget_frame();
+        let was_shown = self.status_line.is_some();
+        self.status_line = status;
+        let (width, height) = (self.cursor.width() as u16, self.cursor.height() as u16);
+
+        match (was_shown, self.status_line.is_some()) {
+            (false, true) => {
+                // make room if the cursor is on the last row
+                if self.cursor.is_at_last_terminal_row() {
+                    self.scroll_up(1);
+                }
+                self.cursor.update_dimensions(width, height - 1);
+                self.set_scroll_region(Some(height - 1))?;
+            }
+            (true, false) => {
+                self.set_scroll_region(None)?;
+                self.cursor.raw.save_position()?;
+                self.cursor.raw.goto(0, height)?;
+                self.writer.raw.clear(ClearType::CurrentLine)?;
+                self.cursor.raw.restore_position()?;
+                self.cursor.update_dimensions(width, height + 1);
+            }
+            _ => (),
+        }
+        self.draw_status_line()
+    }
+
+    pub fn draw_status_line(&mut self) -> Result<()> {
+        let status = match &self.status_line {
+            Some(status) => status.chars().take(self.cursor.width()).collect::<String>(),
+            None => return Ok(()),
+        };
+        // the status line is right after the scroll region
+        let row = self.cursor.height() as u16;
+        self.cursor.raw.save_position()?;
+        self.cursor.raw.goto(0, row)?;
+        self.writer.raw.clear(ClearType::CurrentLine)?;
+        self.writer
+            .raw
+            .write_with_color(status, self.status_line_color)?;
+        self.cursor.raw.restore_position()?;
+        Ok(())
+    }
+
+    /// Needs to be called on terminal resize
+    pub fn update_dimensions(&mut self, width: u16, height: u16) -> Result<()> {
+        self.forget_frame();
+        if self.status_line.is_some() {
+            self.cursor.update_dimensions(width, height - 1);
+            self.set_scroll_region(Some(height - 1))?;
+            self.draw_status_line()?;
+        } else {
+            self.cursor.update_dimensions(width, height);
+            if self.top_pane.is_some() {
+                self.set_scroll_region(None)?;
+            }
+        }
+        self.draw_top_pane()
+    }
+
+    /// Limit scrolling to the rows between the top pane and `bottom`, None extends it to the last terminal row
+    fn set_scroll_region(&mut self, bottom: Option<u16>) -> Result<()> {
+        let top = self.top_pane_height() + 1;
+        // setting the scroll region moves the cursor to the top
+        self.cursor.raw.save_position()?;
+        match bottom {
+            Some(bottom) => self.writer.raw.write(format!("\x1b[{};{}r", top, bottom))?,
+            None if top > 1 => self.writer.raw.write(format!("\x1b[{};r", top))?,
+            None => self.writer.raw.write("\x1b[r")?,
+        }
+        self.cursor.raw.restore_position()?;
+        Ok(())
+    }
+
+    /// Reserve the first `rows` terminal rows for a pane, `None` removes it
+    ///
+    /// The screen is cleared since the input area moves, the pane content is kept
+    pub fn set_top_pane(&mut self, rows: Option<u16>) -> Result<()> {
+        // leave a few rows for the input
+        let max_rows = (self.cursor.height() as u16).saturating_sub(4);
+        self.top_pane = match rows {
+            Some(rows) if max_rows > 0 => Some(Pane {
+                rows: rows.min(max_rows),
+                lines: self
+                    .top_pane
+                    .take()
+                    .map(|pane| pane.lines)
+                    .unwrap_or_default(),
+                scroll: 0,
+            }),
+            _ => None,
+        };
+        let bottom = self
+            .status_line
+            .as_ref()
+            .map(|_| self.cursor.height() as u16);
+        self.set_scroll_region(bottom)?;
+        self.clear()
+    }
+
+    /// Number of rows taken by the top pane and its separator
+    pub fn top_pane_height(&self) -> usize {
+        self.top_pane
+            .as_ref()
+            .map(|pane| pane.rows as usize + 1)
+            .unwrap_or(0)
+    }
+
+    /// Append `output` to the top pane, the view doesn't move if it is scrolled
+    pub fn push_to_top_pane(&mut self, output: &str) -> Result<()> {
+        self.push_colored_to_top_pane(output, None)
+    }
+
+    /// Like `push_to_top_pane` but the lines keep their color, exp: log levels
+    pub fn push_colored_to_top_pane(&mut self, output: &str, color: Option<Color>) -> Result<()> {
+        let pane = match self.top_pane.as_mut() {
+            Some(pane) => pane,
+            None => return Ok(()),
+        };
+        let lines: Vec<(String, Option<Color>)> = output
+            .lines()
+            .map(|l| (l.replace('\t', "    "), color))
+            .collect();
+        if pane.scroll != 0 {
+            pane.scroll += lines.len();
+        }
+        pane.lines.extend(lines);
+
+        let overflow = pane.lines.len().saturating_sub(TOP_PANE_MAX_LINES);
+        pane.lines.drain(..overflow);
+        pane.scroll = pane
+            .scroll
+            .min(pane.lines.len().saturating_sub(pane.rows as usize));
+        self.draw_top_pane()
+    }
+
+    /// Scroll the top pane by a page, returns false if there is no pane
+    pub fn scroll_top_pane(&mut self, up: bool) -> Result<bool> {
+        let pane = match self.top_pane.as_mut() {
+            Some(pane) => pane,
+            None => return Ok(false),
+        };
+        let page = pane.rows.max(1) as usize;
+        pane.scroll = if up {
+            std::cmp::min(
+                pane.scroll + page,
+                pane.lines.len().saturating_sub(pane.rows as usize),
+            )
+        } else {
+            pane.scroll.saturating_sub(page)
+        };
+        self.draw_top_pane()?;
+        Ok(true)
+    }
+
+    pub fn draw_top_pane(&mut self) -> Result<()> {
+        let pane = match &self.top_pane {
+            Some(pane) => pane,
+            None => return Ok(()),
+        };
+        let width = self.cursor.width();
+        let end = pane.lines.len() - pane.scroll;
+        let visible: Vec<(String, Color)> = pane.lines[end.saturating_sub(pane.rows as usize)..end]
+            .iter()
+            .map(|(line, color)| {
+                (
+                    line.chars().take(width).collect(),
+                    color.unwrap_or(self.top_pane_color),
+                )
+            })
+            .collect();
+        let title = if pane.scroll == 0 {
+            " stderr ".to_string()
+        } else {
+            format!(" stderr ({} more lines below) ", pane.scroll)
+        };
+        let separator = format!(
+            "──{}{}",
+            title,
+            "─".repeat(width.saturating_sub(title.chars().count() + 2))
+        );
+        let rows = pane.rows;
+
+        self.cursor.raw.save_position()?;
+        for row in 0..rows {
+            self.cursor.raw.goto(0, row)?;
+            self.writer.raw.clear(ClearType::CurrentLine)?;
+            if let Some((line, color)) = visible.get(row as usize) {
+                self.writer.raw.write_with_color(line, *color)?;
+            }
+        }
+        self.cursor.raw.goto(0, rows)?;
+        self.writer.raw.clear(ClearType::CurrentLine)?;
+        self.writer
+            .raw
+            .write_with_color(separator, self.status_line_color)?;
+        self.cursor.raw.restore_position()?;
+        Ok(())
+    }
 }
 
 // Methods that combine writer and cursor are exported by the printer
 impl<W: std::io::Write> Printer<W> {
     pub fn write_from_terminal_start(&mut self, out: &str, color: Color) -> Result<()> {
+        self.forget_frame();
         self.writer
             .write_from_terminal_start(out, color, &mut self.cursor)
     }
     pub fn clear(&mut self) -> Result<()> {
-        self.writer.clear(&mut self.cursor)
+        self.forget_frame();
+        self.writer.clear(&mut self.cursor)?;
+        // the input area starts under the top pane
+        let top = self.top_pane_height();
+        if top != 0 {
+            self.cursor.set_starting_pos(0, top);
+            self.cursor.goto_start();
+        }
+        self.draw_top_pane()?;
+        self.draw_status_line()
+    }
+    /// Clear from the cursor to the end of the screen, the status line is kept
+    pub fn clear_from_cursor_down(&mut self) -> Result<()> {
+        self.forget_frame();
+        self.writer.raw.clear(ClearType::FromCursorDown)?;
+        self.draw_status_line()
     }
     pub fn clear_last_line(&mut self) -> Result<()> {
+        self.forget_frame();
         self.writer.clear_last_line(&mut self.cursor)
     }
 
     pub fn write_newline(&mut self, buffer: &Buffer) {
+        self.forget_frame();
         self.writer.write_newline(&mut self.cursor, buffer);
     }
 
     pub fn write(&mut self, out: &str, color: Color) -> Result<()> {
+        self.forget_frame();
         self.writer.write(out, color, &mut self.cursor)
     }
 
     pub fn write_at(&mut self, s: &str, x: usize, y: usize) -> Result<()> {
+        self.forget_frame();
         self.writer.write_at(s, x, y, &mut self.cursor)
     }
     pub fn write_at_no_cursor(&mut self, s: &str, color: Color, x: usize, y: usize) -> Result<()> {
+        self.forget_frame();
         self.writer
             .write_at_no_cursor(s, color, x, y, &mut self.cursor)
     }
     pub fn scroll_up(&mut self, n: usize) {
+        self.forget_frame();
         self.writer.scroll_up(n, &mut self.cursor)
     }
-    pub fn print_extra_lines_indicator_if_needed(&mut self, from_start: bool) -> Result<()> {
-        let prompt_len = self.prompt_len();
-
-        let mut write = |indicator| {
-            if from_start {
-                self.writer
-                    .write_from_terminal_start(indicator, Color::Yellow, &mut self.cursor)
-            } else {
-                self.writer
-                    .write(indicator, Color::Yellow, &mut self.cursor)
-            }
-        };
-        match prompt_len {
-            0 => Ok(()),
-            1 => write(" "),
+    fn put_extra_lines_indicator_if_needed(&mut self, frame: &mut Frame, from_start: bool) {
+        if from_start {
+            self.cursor.goto(0, self.cursor.current_pos().1);
+        }
+        match self.prompt_len() {
+            0 => (),
+            1 => self.put_str(frame, " ", Color::Yellow),
             n => {
                 let indicator = ".".repeat(n - 2) + ": ";
-                write(&indicator)
+                self.put_str(frame, &indicator, Color::Yellow)
             }
         }
     }
@@ -5,6 +5,7 @@ mod raw;
 use raw::Raw;
 
 use crate::buffer::Buffer;
+use crate::width::char_width;
 /// input is shown with x in this example
 /// |In: x
 /// |    x
@@ -33,6 +34,8 @@ pub struct Cursor<W: std::io::Write> {
     pub raw: Raw<W>,
 
     copy: CursorPosition,
+    /// The terminal cursor doesn't follow the moves, exp: while the input is laid out before drawing it
+    detached: bool,
 }
 
 impl<W: std::io::Write> Cursor<W> {
@@ -51,6 +54,7 @@ impl<W: std::io::Write> Cursor<W> {
             bound: Bound::new(width as usize, height as usize),
             raw,
             prompt_len,
+            detached: false,
         }
     }
 
@@ -178,7 +182,14 @@ impl<W: std::io::Write> Cursor<W> {
             .expect("failed to restore cursor position");
     }
 
+    pub fn set_detached(&mut self, detached: bool) {
+        self.detached = detached;
+    }
+
     pub fn goto_internal_pos(&mut self) {
+        if self.detached {
+            return;
+        }
         self.raw
             .goto(self.pos.current_pos.0 as u16, self.pos.current_pos.1 as u16)
             .expect("failed to move cursor");
@@ -232,21 +243,28 @@ impl<W: std::io::Write> Cursor<W> {
     }
 
     pub fn buffer_pos_to_cursor_pos(&self, buffer: &Buffer) -> (usize, usize) {
-        let last_buffer_pos = buffer.len();
+        self.relative_pos_of(buffer, buffer.len())
+    }
+
+    /// Position of the buffer char at `buffer_pos` relative to the input start
+    ///
+    /// Wide chars take two cells, and go to the next row if only one cell is left
+    fn relative_pos_of(&self, buffer: &Buffer, buffer_pos: usize) -> (usize, usize) {
         let max_line_chars = self.bound.width - self.prompt_len;
 
-        let mut y = buffer
-            .iter()
-            .take(last_buffer_pos)
-            .filter(|c| **c == '\n')
-            .count();
-
-        let mut x = 0;
-        for i in 0..last_buffer_pos {
-            match buffer.get(i) {
-                Some('\n') => x = 0,
-                _ => x += 1,
-            };
+        let (mut x, mut y) = (0, 0);
+        for c in buffer.iter().take(buffer_pos) {
+            if *c == '\n' {
+                x = 0;
+                y += 1;
+                continue;
+            }
+            let width = char_width(*c);
+            if x + width > max_line_chars {
+                x = 0;
+                y += 1;
+            }
+            x += width;
             if x == max_line_chars {
                 x = 0;
                 y += 1;
@@ -278,13 +296,41 @@ impl<W: std::io::Write> Cursor<W> {
         self.pos.current_pos.1 == self.input_last_pos(buffer).1
     }
 
-    pub fn cursor_pos_to_buffer_pos(&self) -> usize {
-        self.pos.current_pos.0 - self.prompt_len
-            + self.bound.bounds_sum(
-                self.pos.starting_pos.1,
-                self.pos.current_pos.1,
-                self.prompt_len,
-            )
+    /// The buffer position of the first char at or after the cursor
+    pub fn cursor_pos_to_buffer_pos(&self, buffer: &Buffer) -> usize {
+        let x = self.pos.current_pos.0.saturating_sub(self.prompt_len);
+        let y = self
+            .pos
+            .current_pos
+            .1
+            .saturating_sub(self.pos.starting_pos.1);
+        (0..=buffer.len())
+            .find(|pos| {
+                let (char_x, char_y) = self.relative_pos_of(buffer, *pos);
+                char_y > y || char_y == y && char_x >= x
+            })
+            .unwrap_or_else(|| buffer.len())
+    }
+
+    /// Move the cursor to the buffer position
+    pub fn goto_buffer_pos(&mut self, buffer: &Buffer) {
+        let (x, y) = self.relative_pos_of(buffer, buffer.buffer_pos);
+        self.goto(x + self.prompt_len, y + self.pos.starting_pos.1);
+    }
+
+    /// A wide char doesn't fit in the last column, the terminal draws it on the next row
+    pub fn wraps_before(&self, c: char) -> bool {
+        char_width(c) > 1 && self.is_at_last_terminal_col()
+    }
+
+    /// Move over a char written at the cursor position
+    pub fn move_right_over(&mut self, c: char) {
+        for _ in 0..char_width(c) {
+            if self.is_at_last_terminal_col() {
+                self.bound_current_row_at_current_col();
+            }
+            self.move_right_unbounded();
+        }
     }
 
     pub fn goto_next_row_terminal_start(&mut self) {
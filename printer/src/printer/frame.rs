@@ -0,0 +1,187 @@
+use crate::width::char_width;
+use crate::Result;
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Attribute, Color, Print, SetAttribute, SetForegroundColor},
+    terminal::{Clear, ClearType},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Blank,
+    /// A char with the zero width chars drawn over it
+    Char {
+        text: String,
+        color: Color,
+        underlined: bool,
+    },
+    /// Second cell of a wide char
+    WideTail,
+}
+
+/// The input as drawn on the terminal (prompt included), cell by cell
+///
+/// A new frame is compared with the last drawn one so only the cells that changed are drawn again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Terminal row of the first input row
+    start_row: usize,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Frame {
+    pub fn new(start_row: usize) -> Self {
+        Self {
+            start_row,
+            rows: vec![],
+        }
+    }
+
+    /// Put a char at the terminal position `(x, y)`, y can't be before the start row
+    pub fn put(&mut self, x: usize, y: usize, c: char, color: Color, underlined: bool) {
+        let row_idx = y - self.start_row;
+        if self.rows.len() <= row_idx {
+            self.rows.resize(row_idx + 1, vec![]);
+        }
+        let row = &mut self.rows[row_idx];
+
+        let width = char_width(c);
+        if width == 0 {
+            // drawn over the previous char
+            let previous = match row.get(x.wrapping_sub(1)) {
+                Some(Cell::WideTail) => x.checked_sub(2),
+                _ => x.checked_sub(1),
+            };
+            if let Some(Cell::Char { text, .. }) = previous.and_then(|x| row.get_mut(x)) {
+                text.push(c);
+            }
+            return;
+        }
+
+        if row.len() < x + width {
+            row.resize(x + width, Cell::Blank);
+        }
+        row[x] = Cell::Char {
+            text: c.to_string(),
+            color,
+            underlined,
+        };
+        if width == 2 {
+            row[x + 1] = Cell::WideTail;
+        }
+    }
+
+    /// Queue the commands that turn `previous` into this frame, `None` means the screen content is unknown
+    ///
+    /// The rows under the input are cleared, they can have the content of a longer input or a panel
+    ///
+    /// Returns the last foreground color set
+    pub fn draw(
+        &self,
+        previous: Option<&Frame>,
+        height: usize,
+        out: &mut impl std::io::Write,
+    ) -> Result<Option<Color>> {
+        let previous = previous.filter(|previous| previous.start_row == self.start_row);
+        if previous.is_none() {
+            queue!(
+                out,
+                MoveTo(0, self.start_row as u16),
+                Clear(ClearType::FromCursorDown)
+            )?;
+        }
+
+        let mut pen = Pen::default();
+        for (idx, row) in self.rows.iter().enumerate() {
+            let y = (self.start_row + idx) as u16;
+            let (start, old_len) = match previous {
+                Some(previous) => {
+                    let old = previous.rows.get(idx).map(Vec::as_slice).unwrap_or(&[]);
+                    match first_difference(old, row) {
+                        Some(start) => (start, old.len()),
+                        None => continue,
+                    }
+                }
+                None => (0, 0),
+            };
+
+            queue!(out, MoveTo(start as u16, y))?;
+            for cell in row.iter().skip(start) {
+                pen.draw(cell, out)?;
+            }
+            // the rest of the old row, a full row doesn't need it
+            if old_len > row.len() {
+                queue!(out, Clear(ClearType::UntilNewLine))?;
+            }
+        }
+        if pen.underlined {
+            queue!(out, SetAttribute(Attribute::NoUnderline))?;
+        }
+
+        let below = self.start_row + self.rows.len();
+        if previous.is_some() && below < height {
+            queue!(
+                out,
+                MoveTo(0, below as u16),
+                Clear(ClearType::FromCursorDown)
+            )?;
+        }
+        Ok(pen.color)
+    }
+}
+
+/// The first column of `new` that differs from `old`, a wide char is drawn from its first cell
+fn first_difference(old: &[Cell], new: &[Cell]) -> Option<usize> {
+    let mut x = old
+        .iter()
+        .zip(new)
+        .position(|(old, new)| old != new)
+        .unwrap_or_else(|| old.len().min(new.len()));
+    if x == old.len() && x == new.len() {
+        return None;
+    }
+    while x > 0 && (old.get(x) == Some(&Cell::WideTail) || new.get(x) == Some(&Cell::WideTail)) {
+        x -= 1;
+    }
+    Some(x)
+}
+
+/// Current terminal style, it's only changed when a cell needs it
+#[derive(Default)]
+struct Pen {
+    color: Option<Color>,
+    underlined: bool,
+}
+
+impl Pen {
+    fn draw(&mut self, cell: &Cell, out: &mut impl std::io::Write) -> Result<()> {
+        let (text, color, underlined) = match cell {
+            Cell::Blank => (" ", None, false),
+            Cell::Char {
+                text,
+                color,
+                underlined,
+            } => (text.as_str(), Some(*color), *underlined),
+            // the terminal moved over it with the wide char
+            Cell::WideTail => return Ok(()),
+        };
+        if let Some(color) = color {
+            if self.color != Some(color) {
+                queue!(out, SetForegroundColor(color))?;
+                self.color = Some(color);
+            }
+        }
+        if self.underlined != underlined {
+            let attribute = if underlined {
+                Attribute::Underlined
+            } else {
+                Attribute::NoUnderline
+            };
+            queue!(out, SetAttribute(attribute))?;
+            self.underlined = underlined;
+        }
+        queue!(out, Print(text))?;
+        Ok(())
+    }
+}
@@ -146,6 +146,82 @@ pub fn calculate_bounds_correctly2() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn wide_and_combining_chars_cursor_pos() {
+    let mut p = Printer::new(std::io::sink(), "".to_owned());
+    p.cursor.update_dimensions(10, 10);
+    move_to_and_modify_start(&mut p, 0, 0);
+
+    assert_eq!(p.cursor.buffer_pos_to_cursor_pos(&"a中b".into()), (4, 0));
+    assert_eq!(
+        p.cursor.buffer_pos_to_cursor_pos(&"e\u{301}x".into()),
+        (2, 0)
+    );
+    // only one cell is left on the first row
+    let mut b: crate::buffer::Buffer = "aaaaaaaaa中".into();
+    assert_eq!(p.cursor.buffer_pos_to_cursor_pos(&b), (2, 1));
+
+    b.set_buffer_pos(9);
+    p.cursor.goto_buffer_pos(&b);
+    assert_eq!(p.cursor.pos.current_pos, (9, 0));
+    assert_eq!(p.cursor.cursor_pos_to_buffer_pos(&b), 9);
+    p.cursor.goto(1, 1);
+    assert_eq!(p.cursor.cursor_pos_to_buffer_pos(&b), 10);
+}
+
+#[test]
+fn char_width() {
+    use crate::width::char_width;
+    assert_eq!(char_width('a'), 1);
+    assert_eq!(char_width('é'), 1);
+    assert_eq!(char_width('中'), 2);
+    assert_eq!(char_width('🦀'), 2);
+    assert_eq!(char_width('\u{301}'), 0);
+}
+
+#[test]
+fn word_motions() {
+    let mut b: crate::buffer::Buffer = "let foo = bar".into();
+    b.set_buffer_pos(5);
+    b.move_to_word_start();
+    assert_eq!(b.buffer_pos, 4);
+    b.move_to_word_end();
+    assert_eq!(b.buffer_pos, 7);
+    b.move_to_word_end();
+    assert_eq!(b.buffer_pos, 8);
+
+    b.remove_range(4, 8);
+    assert_eq!(b.to_string(), "let = bar");
+    assert_eq!(b.buffer_pos, 4);
+}
+
+#[test]
+fn input_redraw_starts_at_the_first_change() -> Result<()> {
+    let mut p = Printer::new(Vec::new(), "In: ".to_owned());
+    move_to_and_modify_start(&mut p, 0, 0);
+    let drawn = |p: &mut Printer<Vec<u8>>| {
+        String::from_utf8(std::mem::take(&mut *p.writer.raw.raw.borrow_mut())).unwrap()
+    };
+
+    p.print_input(&default_process_fn, &"let x".into())?;
+    assert!(drawn(&mut p).contains("let x"));
+    assert_eq!(p.cursor.bound.bound[0], 9);
+
+    p.print_input(&default_process_fn, &"let xy".into())?;
+    let output = drawn(&mut p);
+    assert!(output.contains('y'));
+    assert!(!output.contains("let"));
+
+    p.print_input(&default_process_fn, &"let xy".into())?;
+    assert!(!drawn(&mut p).contains('x'));
+
+    // the screen changed under the input
+    p.clear()?;
+    p.print_input(&default_process_fn, &"let xy".into())?;
+    assert!(drawn(&mut p).contains("let xy"));
+    Ok(())
+}
+
 // helper
 fn move_to_and_modify_start(printer: &mut Printer<impl Write>, x: usize, y: usize) {
     printer.cursor.pos.starting_pos.0 = x;
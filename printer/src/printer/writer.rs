@@ -21,6 +21,11 @@ impl<W: std::io::Write> Writer<W> {
         }
     }
 
+    /// Needed when the color is set without the writer, exp: a drawn frame
+    pub(super) fn set_last_color(&mut self, color: Option<Color>) {
+        self.last_color = color;
+    }
+
     pub(super) fn write(
         &mut self,
         out: &str,
@@ -40,21 +45,6 @@ impl<W: std::io::Write> Writer<W> {
         Ok(())
     }
 
-    pub(super) fn write_char_with_color(
-        &mut self,
-        c: char,
-        color: Color,
-        cursor: &mut super::cursor::Cursor<W>,
-    ) -> Result<()> {
-        // Performance: set_fg only when needed
-        if self.last_color != Some(color) {
-            self.raw.set_fg(color)?;
-        }
-        self.write_char(c, cursor)?;
-        self.last_color = Some(color);
-        Ok(())
-    }
-
     pub(super) fn write_char(
         &mut self,
         c: char,
@@ -63,7 +53,7 @@ impl<W: std::io::Write> Writer<W> {
         self.raw.write(c)?;
         // Performance: Make sure to not move the cursor if cursor_pos = last_cursor_pos+1 because it moves automatically
         // This optimization is currently disabled for simplicity
-        cursor.move_right_unbounded();
+        cursor.move_right_over(c);
         Ok(())
     }
 
@@ -56,6 +56,26 @@ impl<W: std::io::Write> Raw<W> {
         Ok(())
     }
 
+    pub fn set_underline(&mut self, underline: bool) -> Result<()> {
+        let attribute = if underline {
+            Attribute::Underlined
+        } else {
+            Attribute::NoUnderline
+        };
+        queue!(self, SetAttribute(attribute))?;
+        Ok(())
+    }
+
+    pub fn enter_alternate_screen(&mut self) -> Result<()> {
+        queue!(self, EnterAlternateScreen)?;
+        Ok(())
+    }
+
+    pub fn leave_alternate_screen(&mut self) -> Result<()> {
+        queue!(self, LeaveAlternateScreen)?;
+        Ok(())
+    }
+
     pub fn set_title(&mut self, title: &str) -> Result<()> {
         queue!(self, SetTitle(title))?;
         Ok(())
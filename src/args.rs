@@ -7,15 +7,22 @@ const VERSION: &str = "1.6.2";
 pub fn handle_args(options: &mut Options) -> bool {
     let args: Vec<String> = env::args().skip(1).collect();
 
-    if !args.is_empty() {
-        match args[0].as_str() {
+    if args.first().map(String::as_str) == Some("eval") {
+        std::process::exit(crate::irust::oneshot::eval(&args[1..], options));
+    }
+
+    for arg in &args {
+        match arg.as_str() {
             "-h" | "--help" => {
                 println!(
                     "IRust: Cross Platform Rust REPL
         version: {}\n
         config file is in {}\n
         --help => shows this message
-        --reset-config => reset IRust configuration to default",
+        --reset-config => reset IRust configuration to default
+        --no-banner => start without the welcome message
+        eval [--dep name=version].. [--output text|json] <expression> => print the expression value and exit
+        [file.rs].. => load the files in order and run their code before the first prompt",
                     VERSION,
                     Options::config_path()
                         .map(|p| p.to_string_lossy().to_string())
@@ -30,12 +37,25 @@ pub fn handle_args(options: &mut Options) -> bool {
             }
 
             "--reset-config" => {
+                // keep the arguments given before it
+                let no_banner = options.no_banner;
+                let startup_files = std::mem::take(&mut options.startup_files);
                 options.reset();
+                options.no_banner = no_banner;
+                options.startup_files = startup_files;
+            }
+
+            "--no-banner" => {
+                options.no_banner = true;
             }
 
-            x => {
+            x if x.starts_with('-') => {
                 eprintln!("Unknown argument: {}", x);
             }
+
+            file => {
+                options.startup_files.push(file.into());
+            }
         }
     }
 
@@ -29,7 +29,7 @@ impl Dep {
 pub fn check_required_deps() -> bool {
     const REQUIRED_DEPS: &[&str] = &["cargo"];
     for dep in REQUIRED_DEPS {
-        if !dep_installed(&dep) {
+        if !dep_installed(dep) {
             eprintln!(
                 "{0} is not insalled!\n{0} is required for IRust to work.",
                 dep
@@ -174,6 +174,17 @@ pub fn warn_about_opt_deps(options: &mut Options) {
     println!("{}", "Everthing is set!".green());
 }
 
+/// `sccache` is only needed if it's enabled, without it nothing can be compiled
+pub fn check_sccache(options: &mut Options) {
+    if options.sccache && !dep_installed("sccache") {
+        eprintln!(
+            "{}",
+            "sccache is enabled but not installed, compiling without it".yellow()
+        );
+        options.sccache = false;
+    }
+}
+
 fn dep_installed(d: &str) -> bool {
     if let Err(e) = std::process::Command::new(d)
         .arg("-h")
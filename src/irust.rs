@@ -1,29 +1,58 @@
 mod art;
+mod backtrace;
 mod cargo_cmds;
+mod completion_panel;
+mod crates;
+mod debugger;
+mod diagnostics;
+mod doctest;
+mod env;
 mod events;
 mod format;
 mod global_variables;
 mod help;
 pub mod highlight;
 mod history;
+mod indent;
+mod input;
+mod logs;
+mod meta;
+pub mod oneshot;
 pub mod options;
+mod pager;
 mod parser;
+mod prompt;
 mod racer;
+mod ranking;
+pub mod recovery;
 mod repl;
 mod script;
+mod shell;
+mod signature_help;
+mod snippets;
+mod target;
+mod terminal;
+mod transcript;
+mod watch;
 use crossterm::event::KeyModifiers;
 use crossterm::event::{Event, KeyCode, KeyEvent};
-use global_variables::GlobalVariables;
+use global_variables::{GlobalVariables, LastEval};
 use highlight::theme::Theme;
 use history::History;
 use once_cell::sync::Lazy;
 use options::Options;
 use printer::{buffer::Buffer, printer::Printer};
 use racer::Racer;
-use repl::Repl;
+use repl::{Checkpoint, Repl};
 use script::ScriptManager;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+/// A row drawn under the input, made of (text, foreground, optional background) segments
+type BelowInputRow = Vec<(
+    String,
+    crossterm::style::Color,
+    Option<crossterm::style::Color>,
+)>;
 static SOUT: Lazy<std::io::Stdout> = Lazy::new(std::io::stdout);
 
 pub struct IRust {
@@ -33,16 +62,38 @@ pub struct IRust {
     options: Options,
     racer: Option<Racer>,
     global_variables: GlobalVariables,
+    last_eval: LastEval,
     theme: Theme,
     history: History,
     script_mg: Option<ScriptManager>,
+    checkpoints: Vec<Checkpoint>,
+    /// History entry suffix currently shown as ghost text after the input
+    history_suggestion: Option<String>,
+    /// File reloaded and re-evaluated on every save, set with `:watch`
+    watcher: Option<watch::Watcher>,
+    /// (function name, definition) of the call the cursor is in
+    signature_help: Option<(String, String)>,
+    /// The input changed since it was last checked, see `check_as_you_type`
+    input_check_pending: bool,
+    /// Events read ahead (while looking for the rest of a composed char or for a terminal answer)
+    pending_events: std::collections::VecDeque<Event>,
+    /// The last key was ctrl-c on an empty input
+    ctrl_c_on_empty_input: bool,
+    transcript: transcript::Transcript,
+    /// Last output of each evaluated input, for `diff_reruns`
+    previous_outputs: std::collections::HashMap<String, String>,
+    /// The terminal background, detected at startup if `Options::background` is `Auto`
+    background: terminal::Background,
 }
 
 impl IRust {
-    pub fn new(options: Options) -> Self {
+    pub fn new(mut options: Options) -> Self {
         let out = SOUT.lock();
+        options.apply_build_cache();
+        options.apply_default_deps();
         // Make sure to call Repl::new at the start so it can set `irust-repl` dir, which might be used by others (ScriptManager)
-        let repl = Repl::new();
+        let mut repl = Repl::new();
+        repl.set_prelude(options.prelude.clone());
 
         let global_variables = GlobalVariables::new();
 
@@ -58,12 +109,35 @@ impl IRust {
                 if let Some(prompt) = script_mg.input_prompt(&global_variables) {
                     prompt
                 } else {
-                    options.input_prompt.clone()
+                    prompt::render(
+                        &options.input_prompt,
+                        &global_variables,
+                        &LastEval::default(),
+                        options.toolchain,
+                    )
                 }
             })
-            .unwrap_or_else(|| options.input_prompt.clone());
+            .unwrap_or_else(|| {
+                prompt::render(
+                    &options.input_prompt,
+                    &global_variables,
+                    &LastEval::default(),
+                    options.toolchain,
+                )
+            });
 
-        let printer = Printer::new(out, prompt);
+        let mut printer = Printer::new(out, prompt);
+        printer.underline_color = options.diagnostic_color;
+        printer.top_pane_color = options.split_view_color;
+
+        // needs raw mode, so after creating the printer
+        let (background, pending_events) = match options.background {
+            terminal::Background::Auto => terminal::detect_background(&mut printer.writer.raw),
+            background => (background, Vec::new()),
+        };
+        options.adapt_colors_to(background);
+        printer.prompt_color = options.prompt_color;
+        options.apply_eval_limits();
 
         let racer = if options.enable_racer {
             Racer::start()
@@ -72,8 +146,11 @@ impl IRust {
         };
 
         let buffer = Buffer::new();
-        let theme = highlight::theme::theme().unwrap_or_default();
-        let history = History::new().unwrap_or_default();
+        let mut theme = highlight::theme::theme().unwrap_or_default();
+        theme.engine = options.highlight_engine;
+        theme.adapt_to(background);
+        let history =
+            History::new(options.history_max_entries, options.share_history).unwrap_or_default();
 
         IRust {
             repl,
@@ -82,9 +159,20 @@ impl IRust {
             racer,
             buffer,
             global_variables,
+            last_eval: LastEval::default(),
             theme,
             history,
             script_mg,
+            checkpoints: Vec::new(),
+            history_suggestion: None,
+            watcher: None,
+            signature_help: None,
+            input_check_pending: false,
+            pending_events: pending_events.into(),
+            ctrl_c_on_empty_input: false,
+            transcript: transcript::Transcript::default(),
+            previous_outputs: std::collections::HashMap::new(),
+            background,
         }
     }
 
@@ -94,8 +182,15 @@ impl IRust {
             "IRust: {}",
             self.global_variables.get_cwd().display()
         ))?;
-        self.repl.prepare_ground(self.options.toolchain)?;
+        self.repl
+            .prepare_ground_in_background(self.options.toolchain)?;
+        if self.options.alternate_screen {
+            self.printer.enter_alternate_screen()?;
+        }
+        self.update_split_view()?;
         self.welcome()?;
+        self.load_startup_files()?;
+        self.update_status_line()?;
         self.printer.print_prompt_if_set()?;
 
         Ok(())
@@ -103,21 +198,135 @@ impl IRust {
 
     /// Wrapper over printer.print_input that highlights rust code using current theme
     pub fn print_input(&mut self) -> Result<()> {
+        self.print_input_only()?;
+        self.print_below_input()
+    }
+
+    /// Draw the signature help and the completions panel under the input, scrolling the input up if needed
+    fn print_below_input(&mut self) -> Result<()> {
+        let mut rows: Vec<BelowInputRow> = self.signature_help_row().into_iter().collect();
+        rows.extend(self.completion_panel_rows());
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let height_overflow = self
+            .printer
+            .cursor
+            .screen_height_overflow_by_new_lines(&self.buffer, rows.len() + 1);
+        if height_overflow != 0 {
+            self.printer.scroll_up(height_overflow);
+            // the input moved, reprint it to recalculate its bounds
+            self.print_input_only()?;
+        }
+
+        self.printer.print_below_input(rows, &self.buffer)
+    }
+
+    fn print_input_only(&mut self) -> Result<()> {
+        self.history_suggestion = self.find_history_suggestion();
+        match self.history_suggestion.clone() {
+            Some(suggestion) => self.print_input_with_suggestion(&suggestion),
+            None => self.print_input_inner(),
+        }
+    }
+
+    /// Reprint the input without the history ghost text
+    pub fn remove_history_suggestion_and_reprint(&mut self) -> Result<()> {
+        if self.history_suggestion.take().is_some() {
+            self.print_input_inner()?;
+        }
+        Ok(())
+    }
+
+    fn print_input_inner(&mut self) -> Result<()> {
         let theme = &self.theme;
-        self.printer
-            .print_input(&|buffer| highlight::highlight(buffer, theme), &self.buffer)?;
+        let options = &self.options;
+        let repl = &self.repl;
+        self.printer.print_input(
+            &|buffer| Self::highlight_input(buffer, theme, options, repl),
+            &self.buffer,
+        )?;
+        Ok(())
+    }
+
+    fn print_input_with_suggestion(&mut self, suggestion: &str) -> Result<()> {
+        let mut queue = Self::highlight_input(&self.buffer, &self.theme, &self.options, &self.repl);
+        queue.push(printer::printer::PrinterItem::String(
+            suggestion.to_string(),
+            self.options.history_suggestion_color,
+        ));
+
+        // the cursor stays in place, the buffer with the suggestion is only used to check if scrolling is needed
+        let mut buffer = self.buffer.clone();
+        buffer.insert_str(suggestion);
+        buffer.buffer_pos = self.buffer.buffer_pos;
+        self.printer.print_input_from_queue(queue, &buffer)?;
         Ok(())
     }
 
+    fn highlight_input(
+        buffer: &Buffer,
+        theme: &Theme,
+        options: &Options,
+        repl: &Repl,
+    ) -> printer::printer::PrintQueue {
+        let mut queue = highlight::highlight(buffer, theme);
+        if options.semantic_highlighting {
+            queue = highlight::highlight_session_items(queue, theme, |name| repl.item_kind(name));
+        }
+        if options.highlight_matching_bracket {
+            queue = highlight::highlight_matching_bracket(
+                queue,
+                buffer,
+                options.matching_bracket_color,
+            );
+        }
+        queue
+    }
+
+    fn find_history_suggestion(&self) -> Option<String> {
+        // racer inline suggestions take priority
+        let racer_suggestion_active = self
+            .racer
+            .as_ref()
+            .map(|r| r.active_suggestion.is_some())
+            .unwrap_or(false);
+        if !self.options.history_autosuggest || racer_suggestion_active || !self.buffer.is_at_end()
+        {
+            return None;
+        }
+        self.history
+            .suggest(&self.buffer.to_string())
+            .map(ToOwned::to_owned)
+    }
+
     pub fn run(&mut self) -> Result<()> {
         self.prepare()?;
 
         loop {
+            if recovery::termination_requested() {
+                break Ok(());
+            }
             // flush queued output after each key
             // some events that have an inner input loop like ctrl-r/ ctrl-d require flushing inside their respective handler function
             std::io::Write::flush(&mut self.printer.writer.raw)?;
 
-            match crossterm::event::read() {
+            // poll so the watched file and the input can be checked while waiting for input
+            // and so a termination signal is noticed without a key press
+            let idle_timeout = self.idle_timeout();
+            let timeout = idle_timeout.unwrap_or(recovery::SIGNAL_CHECK_INTERVAL);
+            if !self.has_pending_event() && !crossterm::event::poll(timeout)? {
+                if idle_timeout.is_some() {
+                    self.check_watched_file()?;
+                    if self.input_check_pending {
+                        self.check_input()?;
+                    }
+                }
+                continue;
+            }
+
+            match self.read_input_event() {
                 Ok(ev) => {
                     let exit = self.handle_input_event(ev)?;
                     if exit {
@@ -129,14 +338,41 @@ impl IRust {
         }
     }
 
+    /// How long to wait for input before doing background work, `None` if there is nothing to do
+    fn idle_timeout(&self) -> Option<std::time::Duration> {
+        let check_delay = if self.input_check_pending {
+            Some(std::time::Duration::from_millis(
+                self.options.check_as_you_type_delay,
+            ))
+        } else {
+            None
+        };
+        let watch_interval = self.watcher.as_ref().map(|_| watch::WATCH_INTERVAL);
+
+        match (check_delay, watch_interval) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
     fn handle_input_event(&mut self, ev: crossterm::event::Event) -> Result<bool> {
+        let is_ctrl_c = matches!(
+            ev,
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            })
+        );
+        if !is_ctrl_c {
+            self.ctrl_c_on_empty_input = false;
+        }
         // handle input event
         match ev {
             Event::Mouse(_) => (),
             Event::Resize(width, height) => {
-                self.printer.cursor.update_dimensions(width, height);
+                self.printer.update_dimensions(width, height)?;
                 //Hack
-                self.handle_ctrl_c()?;
+                self.reset_input()?;
             }
             Event::Key(key_event) => match key_event {
                 KeyEvent {
@@ -201,12 +437,24 @@ impl IRust {
                 } => {
                     self.handle_down()?;
                 }
+                KeyEvent {
+                    code: KeyCode::Backspace,
+                    modifiers: KeyModifiers::ALT,
+                } => {
+                    self.handle_alt_backspace()?;
+                }
                 KeyEvent {
                     code: KeyCode::Backspace,
                     ..
                 } => {
                     self.handle_backspace()?;
                 }
+                KeyEvent {
+                    code: KeyCode::Char('d'),
+                    modifiers: KeyModifiers::ALT,
+                } => {
+                    self.handle_alt_d()?;
+                }
                 KeyEvent {
                     code: KeyCode::Char('c'),
                     modifiers: KeyModifiers::CONTROL,
@@ -251,12 +499,20 @@ impl IRust {
                 KeyEvent {
                     code: KeyCode::Left,
                     modifiers: KeyModifiers::CONTROL,
+                }
+                | KeyEvent {
+                    code: KeyCode::Char('b'),
+                    modifiers: KeyModifiers::ALT,
                 } => {
                     self.handle_ctrl_left()?;
                 }
                 KeyEvent {
                     code: KeyCode::Right,
                     modifiers: KeyModifiers::CONTROL,
+                }
+                | KeyEvent {
+                    code: KeyCode::Char('f'),
+                    modifiers: KeyModifiers::ALT,
                 } => {
                     self.handle_ctrl_right()?;
                 }
@@ -266,17 +522,20 @@ impl IRust {
                 } => {
                     self.handle_del()?;
                 }
-                keyevent => {
-                    // Handle AltGr on windows
-                    if keyevent
-                        .modifiers
-                        .contains(KeyModifiers::CONTROL | KeyModifiers::ALT)
-                    {
-                        if let KeyCode::Char(c) = keyevent.code {
-                            self.handle_character(c)?;
-                        }
-                    }
+                KeyEvent {
+                    code: KeyCode::PageUp,
+                    ..
+                } => {
+                    self.printer.scroll_top_pane(true)?;
                 }
+                KeyEvent {
+                    code: KeyCode::PageDown,
+                    ..
+                } => {
+                    self.printer.scroll_top_pane(false)?;
+                }
+                // AltGr and composed chars are already normalized by `read_input_event`
+                _ => (),
             },
         }
         Ok(false)
@@ -285,12 +544,72 @@ impl IRust {
 // Scripts
 impl IRust {
     pub fn update_input_prompt(&mut self) {
+        self.printer.prompt_color = match self.last_eval.success {
+            Some(false) => self.options.prompt_error_color,
+            _ => self.options.prompt_color,
+        };
         if let Some(ref script_mg) = self.script_mg {
             if let Some(prompt) = script_mg.input_prompt(&self.global_variables) {
                 self.printer.set_prompt(prompt);
+                return;
             }
         }
+        self.printer.set_prompt(prompt::render(
+            &self.options.input_prompt,
+            &self.global_variables,
+            &self.last_eval,
+            self.options.toolchain,
+        ));
     }
+    /// Show or hide the stderr pane according to `split_view`
+    pub fn update_split_view(&mut self) -> Result<()> {
+        let rows = if self.options.split_view {
+            Some(self.options.split_view_rows)
+        } else {
+            None
+        };
+        if rows.is_none() && self.printer.top_pane_height() == 0 {
+            return Ok(());
+        }
+        self.printer.set_top_pane(rows)
+    }
+
+    pub fn update_status_line(&mut self) -> Result<()> {
+        if !self.options.status_line {
+            return self.printer.set_status_line(None);
+        }
+
+        let last_eval = match self.last_eval.duration {
+            Some(duration) => format!("{:.2?}", duration),
+            None => "-".to_string(),
+        };
+        let racer = match &self.racer {
+            Some(racer) if racer.is_running() => "on",
+            Some(_) => "not found",
+            None => "off",
+        };
+        let target = match cargo_cmds::target() {
+            Some(target) => format!(" {}", target.triple),
+            None => String::new(),
+        };
+        let compile_mode = if self.options.eval_release {
+            "release"
+        } else {
+            "debug"
+        };
+        let status = format!(
+            " {}{} | {} | {} deps | last eval: {} | racer: {}",
+            self.options.toolchain.name(),
+            target,
+            compile_mode,
+            cargo_cmds::dependencies_count(),
+            last_eval,
+            racer
+        );
+        self.printer.status_line_color = self.options.status_line_color;
+        self.printer.set_status_line(Some(status))
+    }
+
     pub fn get_output_prompt(&mut self) -> String {
         if let Some(ref script_mg) = self.script_mg {
             if let Some(prompt) = script_mg.get_output_prompt(&self.global_variables) {
@@ -298,16 +617,23 @@ impl IRust {
             }
         }
         //Default
-        self.options.output_prompt.clone()
+        prompt::render(
+            &self.options.output_prompt,
+            &self.global_variables,
+            &self.last_eval,
+            self.options.toolchain,
+        )
     }
 }
 
 impl Drop for IRust {
     fn drop(&mut self) {
         // ignore errors on drop with let _
-        let _ = self.exit();
         if std::thread::panicking() {
-            let _ = self.printer.writer.raw.write("IRust panicked, to log the error you can redirect stderror to a file, example irust 2>log");
+            // the printer state can't be trusted, it restores the terminal when it's dropped
+            let _ = self.save_state();
+        } else {
+            let _ = self.exit();
         }
     }
 }
@@ -83,7 +83,11 @@ impl IRust {
                 }
                 Ok(Some(_)) => return Ok(()),
             }
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            if crate::utils::ctrl_c_pressed(std::time::Duration::from_millis(100)) {
+                add_cmd.kill()?;
+                add_cmd.wait()?;
+                return Err(crate::utils::INTERRUPTED.into());
+            }
         }
     }
 
@@ -96,23 +100,63 @@ impl IRust {
     }
 
     pub fn welcome(&mut self) -> Result<()> {
-        let default_msg = "Welcome to IRust".to_string();
-        let msg = if !self.options.welcome_msg.is_empty() {
-            self.fit_msg(&self.options.welcome_msg.clone())
-        } else {
-            self.fit_msg(&default_msg)
-        };
+        if self.options.no_banner {
+            return Ok(());
+        }
+
+        // a script welcome takes precedence over the options
+        let mut msg = self
+            .script_mg
+            .as_ref()
+            .and_then(|script_mg| script_mg.welcome(&self.global_variables))
+            .unwrap_or_default();
+        if msg.is_empty() && !self.options.welcome_file.is_empty() {
+            match self.read_welcome_file() {
+                Ok(file) => msg = file,
+                Err(e) => {
+                    self.printer.write(
+                        &format!("Failed to read welcome_file: {}", e),
+                        self.options.irust_warn_color,
+                    )?;
+                    self.printer.write_newline(&self.buffer);
+                }
+            }
+        }
+        if msg.is_empty() {
+            msg = self.options.welcome_msg.clone();
+        }
+        if msg.is_empty() {
+            msg = "Welcome to IRust".to_string();
+        }
 
         self.printer.writer.raw.set_fg(self.options.welcome_color)?;
-        self.printer.writer.raw.write(&msg)?;
+        if msg.contains('\n') {
+            // multi-line messages are arts, they are shown as is
+            for line in msg.trim_end().lines() {
+                self.printer.writer.raw.write(line)?;
+                self.printer.write_newline(&self.buffer);
+            }
+        } else {
+            let msg = self.fit_msg(&msg);
+            self.printer.writer.raw.write(&msg)?;
+            self.printer.write_newline(&self.buffer);
+        }
         self.printer.writer.raw.reset_color()?;
 
-        self.printer.write_newline(&self.buffer);
         self.printer.write_newline(&self.buffer);
 
         Ok(())
     }
 
+    fn read_welcome_file(&self) -> Result<String> {
+        let path = &self.options.welcome_file;
+        let path = match path.strip_prefix("~/") {
+            Some(path) => dirs_next::home_dir().unwrap_or_default().join(path),
+            None => std::path::PathBuf::from(path),
+        };
+        Ok(std::fs::read_to_string(path)?)
+    }
+
     pub fn ferris(&mut self) -> String {
         r#"
      _~^~^~_
@@ -127,10 +171,8 @@ impl IRust {
     }
 
     fn fit_msg(&mut self, msg: &str) -> String {
-        let slash_num = self.printer.cursor.width() - msg.len();
-        let slash = std::iter::repeat('-')
-            .take(slash_num / 2)
-            .collect::<String>();
+        let slash_num = self.printer.cursor.width().saturating_sub(msg.len());
+        let slash = "-".repeat(slash_num / 2);
 
         format!("{0}{1}{0}", slash, msg)
     }
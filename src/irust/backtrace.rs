@@ -0,0 +1,223 @@
+//! Render the panics of evaluated code: the panic message and the frames of the session code
+use crossterm::style::{Colorize, Styler};
+
+/// Frames of the std runtime and of the async runtimes used for top-level `.await`
+const HIDDEN_FRAMES: &[&str] = &[
+    "std::",
+    "core::",
+    "alloc::",
+    "<std::",
+    "<core::",
+    "<alloc::",
+    "__rustc::",
+    "rust_begin_unwind",
+    "__rust",
+    "__libc",
+    "_start",
+    "tokio::",
+    "<tokio::",
+    "async_std::",
+    "<async_std::",
+    "async_global_executor::",
+    "async_io::",
+    "futures_lite::",
+    "pollster::",
+];
+
+#[derive(Debug, PartialEq)]
+struct Panic<'a> {
+    message: String,
+    location: &'a str,
+    /// (function, location)
+    frames: Vec<(&'a str, Option<&'a str>)>,
+}
+
+fn parse_panic(stderr: &str) -> Option<(&str, Panic<'_>)> {
+    let start = stderr
+        .match_indices("thread '")
+        .map(|(idx, _)| idx)
+        .find(|idx| {
+            let line = stderr[*idx..].lines().next().unwrap_or_default();
+            line.contains(" panicked at ")
+        })?;
+    let (before, panic) = stderr.split_at(start);
+    let mut lines = panic.lines();
+    let header = lines.next()?;
+    let header = &header[header.find(" panicked at ")? + " panicked at ".len()..];
+
+    let (message, location) = match header.strip_prefix('\'') {
+        // before rust 1.73: thread 'main' panicked at 'message', src/main.rs:2:5
+        Some(rest) => {
+            let (message, location) = rest.rsplit_once("', ")?;
+            (message.to_string(), location)
+        }
+        // thread 'main' panicked at src/main.rs:2:5:\nmessage
+        None => {
+            let message: Vec<&str> = lines
+                .clone()
+                .take_while(|line| *line != "stack backtrace:" && !line.starts_with("note: "))
+                .collect();
+            (message.join("\n"), header.trim_end_matches(':'))
+        }
+    };
+
+    let mut frames = vec![];
+    for line in lines.skip_while(|line| *line != "stack backtrace:").skip(1) {
+        let line = line.trim();
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some((_, frame_location)) = frames.last_mut() {
+                *frame_location = Some(location);
+            }
+        } else if let Some((idx, function)) = line.split_once(": ") {
+            if idx.chars().all(|c| c.is_ascii_digit()) {
+                frames.push((function, None));
+            }
+        }
+    }
+
+    Some((
+        before,
+        Panic {
+            message,
+            location,
+            frames,
+        },
+    ))
+}
+
+fn is_hidden(function: &str, location: Option<&str>) -> bool {
+    // trait methods, exp: `<usize as core::slice::index::SliceIndex<[u8]>>::index`
+    let path = match function.split_once(" as ") {
+        Some((_, trait_path)) if function.starts_with('<') => trait_path,
+        _ => function,
+    };
+    path == "main"
+        || path == "<unknown>"
+        || HIDDEN_FRAMES.iter().any(|prefix| path.starts_with(prefix))
+        || matches!(location, Some(location) if location.starts_with("/rustc/"))
+}
+
+/// `./src/main.rs:8:9` => 8, `None` for locations outside of the session code
+fn main_rs_line(location: &str) -> Option<usize> {
+    let (path, position) = location.split_once(".rs:")?;
+    if !path.ends_with("src/main") {
+        return None;
+    }
+    position.split(':').next()?.parse().ok()
+}
+
+/// Replace the panic in `stderr` with the panic message and the backtrace frames of the session code
+///
+/// `main_rs` is the code that was running, used to show the source line of each frame
+pub fn beautify_panic(stderr: &str, main_rs: &str) -> Option<String> {
+    let (before, panic) = parse_panic(stderr)?;
+    let source_line = |location| main_rs.lines().nth(main_rs_line(location)?.checked_sub(1)?);
+    let frames: Vec<_> = panic
+        .frames
+        .iter()
+        .filter(|(function, location)| !is_hidden(function, *location))
+        .collect();
+
+    let mut out = before.to_string();
+    out += &format!("panicked: {}\n", panic.message)
+        .red()
+        .bold()
+        .to_string();
+    // the session code location is meaningless to the user, show the code instead
+    match source_line(panic.location) {
+        Some(code) if frames.is_empty() => out += &format!("      {}\n", code.trim()),
+        Some(_) => (),
+        None => out += &format!("  at {}\n", panic.location).dark_grey().to_string(),
+    }
+
+    if !frames.is_empty() {
+        out += &"backtrace:\n".yellow().to_string();
+    }
+    for (function, location) in &frames {
+        let function = function.strip_prefix("irust_repl::").unwrap_or(function);
+        out += &format!("  {}\n", function.cyan());
+        match (location.and_then(source_line), location) {
+            (Some(code), _) => out += &format!("      {}\n", code.trim()),
+            (None, Some(location)) => {
+                out += &format!("      at {}\n", location).dark_grey().to_string()
+            }
+            (None, None) => (),
+        }
+    }
+    let hidden = panic.frames.len() - frames.len();
+    if hidden != 0 {
+        out += &format!(
+            "({} runtime frames hidden, set RUST_BACKTRACE=full to see them)\n",
+            hidden
+        )
+        .dark_grey()
+        .to_string();
+    }
+    Some(out)
+}
+
+#[test]
+fn parse_panic_test() {
+    let stderr = "\
+log
+thread 'main' (18070) panicked at src/main.rs:2:5:
+index out of bounds: the len is 1 but the index is 3
+stack backtrace:
+   0: __rustc::rust_begin_unwind
+             at /rustc/5980/library/std/src/panicking.rs:689:5
+   1: irust_repl::foo
+             at ./src/main.rs:2:5
+   2: irust_repl::main::{{closure}}
+             at ./src/main.rs:6:16
+   3: core::ops::function::FnOnce::call_once
+             at /rustc/5980/library/core/src/ops/function.rs:250:5
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.";
+
+    let (before, panic) = parse_panic(stderr).unwrap();
+    assert_eq!(before, "log\n");
+    assert_eq!(
+        panic,
+        Panic {
+            message: "index out of bounds: the len is 1 but the index is 3".to_string(),
+            location: "src/main.rs:2:5",
+            frames: vec![
+                (
+                    "__rustc::rust_begin_unwind",
+                    Some("/rustc/5980/library/std/src/panicking.rs:689:5")
+                ),
+                ("irust_repl::foo", Some("./src/main.rs:2:5")),
+                ("irust_repl::main::{{closure}}", Some("./src/main.rs:6:16")),
+                (
+                    "core::ops::function::FnOnce::call_once",
+                    Some("/rustc/5980/library/core/src/ops/function.rs:250:5")
+                ),
+            ]
+        }
+    );
+
+    let (_, panic) = parse_panic("thread 'main' panicked at 'boom', src/main.rs:3:5\n").unwrap();
+    assert_eq!(panic.message, "boom");
+    assert_eq!(panic.location, "src/main.rs:3:5");
+    assert!(parse_panic("no panic here").is_none());
+}
+
+#[test]
+fn is_hidden_test() {
+    assert!(is_hidden("core::panicking::panic_fmt", None));
+    assert!(is_hidden(
+        "<usize as core::slice::index::SliceIndex<[u8]>>::index",
+        None
+    ));
+    assert!(is_hidden(
+        "<fn() as core::ops::function::FnOnce<()>>::call_once",
+        Some("/rustc/b940/library/core/src/ops/function.rs:250:5")
+    ));
+    assert!(!is_hidden("irust_repl::main", Some("./src/main.rs:4:5")));
+    assert!(!is_hidden("<irust_repl::A as irust_repl::T>::f", None));
+}
+
+#[test]
+fn main_rs_line_test() {
+    assert_eq!(main_rs_line("./src/main.rs:8:9"), Some(8));
+    assert_eq!(main_rs_line("/home/u/.cargo/registry/src/lib.rs:8:9"), None);
+}
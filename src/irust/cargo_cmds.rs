@@ -1,20 +1,53 @@
+use super::backtrace::beautify_panic;
 use super::Result;
-use crate::utils::stdout_and_stderr;
 use crate::utils::ProcessUtils;
-use once_cell::sync::Lazy;
+use crate::utils::{stdout_and_stderr, strip_ansi_escapes};
+use once_cell::sync::{Lazy, OnceCell};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::{env::temp_dir, process::Stdio};
 
 // TODO:
 // Move these paths to KnownPaths struct
 pub static TMP_DIR: Lazy<PathBuf> = Lazy::new(|| dirs_next::cache_dir().unwrap_or_else(temp_dir));
-pub static IRUST_DIR: Lazy<PathBuf> = Lazy::new(|| TMP_DIR.join("irust_repl"));
+/// Set before the first use of the paths below to use a session directory separate from the interactive one
+pub static SEPARATE_SESSION: AtomicBool = AtomicBool::new(false);
+/// Seconds after which the evaluated code is killed, 0 means no limit, see `Options::eval_timeout`
+pub static EVAL_TIMEOUT: AtomicU64 = AtomicU64::new(0);
+/// Address space limit of the evaluated code in MiB, 0 means no limit, see `Options::eval_memory_limit`
+pub static EVAL_MEMORY_LIMIT: AtomicU64 = AtomicU64::new(0);
+/// Evaluate the code built with `--release`, see `Options::eval_release`
+pub static EVAL_RELEASE: AtomicBool = AtomicBool::new(false);
+/// Smallest accepted `EVAL_MEMORY_LIMIT`, the address space of a process is much larger than the memory
+/// it uses (its binary, shared libraries, thread stacks) so lower limits keep any program from starting
+pub const MIN_EVAL_MEMORY_LIMIT: u64 = 64;
+/// Build of the empty session crate started at startup, see `cargo_new_in_background`
+static WARM_BUILD: Lazy<Mutex<Option<std::process::Child>>> = Lazy::new(|| Mutex::new(None));
+/// Dependencies of every new session, see `Options::default_deps`
+pub static DEFAULT_DEPS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+pub static IRUST_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    if SEPARATE_SESSION.load(Ordering::Relaxed) {
+        TMP_DIR.join("irust_eval")
+    } else {
+        TMP_DIR.join("irust_repl")
+    }
+});
+/// Target directory shared by all sessions, see `Options::shared_target_dir`, set before the first use of `IRUST_TARGET_DIR`
+pub static SHARED_TARGET_DIR: OnceCell<PathBuf> = OnceCell::new();
+/// Compile through `sccache`, see `Options::sccache`
+pub static USE_SCCACHE: AtomicBool = AtomicBool::new(false);
+/// Target of the session builds, None builds for the host, see `:target`
+pub static TARGET: Lazy<Mutex<Option<Target>>> = Lazy::new(|| Mutex::new(None));
 pub static IRUST_TARGET_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    if let Some(p) = SHARED_TARGET_DIR.get() {
+        return p.clone();
+    }
     if let Ok(p) = std::env::var("CARGO_TARGET_DIR") {
         if !p.is_empty() {
             return Path::new(&p).to_path_buf();
@@ -27,6 +60,7 @@ pub static IRUST_SRC_DIR: Lazy<PathBuf> = Lazy::new(|| IRUST_DIR.join("src"));
 pub static MAIN_FILE: Lazy<PathBuf> = Lazy::new(|| IRUST_SRC_DIR.join("main.rs"));
 pub static MAIN_FILE_EXTERN: Lazy<PathBuf> = Lazy::new(|| IRUST_SRC_DIR.join("main_extern.rs"));
 pub static LIB_FILE: Lazy<PathBuf> = Lazy::new(|| IRUST_SRC_DIR.join("lib.rs"));
+pub static META_FILE: Lazy<PathBuf> = Lazy::new(|| IRUST_SRC_DIR.join("irust_meta.rs"));
 #[cfg(windows)]
 pub static EXE_PATH: Lazy<PathBuf> = Lazy::new(|| IRUST_TARGET_DIR.join("debug/irust_repl.exe"));
 #[cfg(windows)]
@@ -38,6 +72,43 @@ pub static EXE_PATH: Lazy<PathBuf> = Lazy::new(|| IRUST_TARGET_DIR.join("debug/i
 pub static RELEASE_EXE_PATH: Lazy<PathBuf> =
     Lazy::new(|| IRUST_TARGET_DIR.join("release/irust_repl"));
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    /// exp: `wasm32-wasi`
+    pub triple: String,
+    /// Command the built program is given to, empty runs it directly, exp: `wasmtime run --dir=.`
+    pub runner: Vec<String>,
+}
+
+impl Target {
+    fn exe_path(&self, release: bool) -> PathBuf {
+        let extension = if self.triple.starts_with("wasm") {
+            ".wasm"
+        } else if self.triple.contains("windows") {
+            ".exe"
+        } else {
+            ""
+        };
+        IRUST_TARGET_DIR
+            .join(&self.triple)
+            .join(if release { "release" } else { "debug" })
+            .join(format!("irust_repl{}", extension))
+    }
+}
+
+/// The selected target, None for the host
+pub fn target() -> Option<Target> {
+    TARGET.lock().expect("poisoned lock").clone()
+}
+
+/// `--target <triple>` when a target is selected
+fn target_args() -> Vec<String> {
+    match target() {
+        Some(target) => vec!["--target".to_string(), target.triple],
+        None => vec![],
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Copy)]
 pub enum ToolChain {
     Stable,
@@ -56,6 +127,15 @@ impl ToolChain {
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        use ToolChain::*;
+        match self {
+            Stable => "stable",
+            Beta => "beta",
+            Nightly => "nightly",
+        }
+    }
+
     fn as_arg(&self) -> String {
         use ToolChain::*;
         match self {
@@ -67,50 +147,218 @@ impl ToolChain {
 }
 
 pub fn cargo_new(toolchain: ToolChain) -> std::result::Result<(), io::Error> {
+    cargo_new_in_background(toolchain)?;
+    wait_for_warm_build()
+}
+
+/// Like `cargo_new` but the session crate is built in the background, so the first evaluation starts from a warm cache
+pub fn cargo_new_in_background(toolchain: ToolChain) -> std::result::Result<(), io::Error> {
+    // a previous build would be racing with this one
+    wait_for_warm_build()?;
     // Ignore directory exists error
     let _ = std::fs::create_dir_all(&*IRUST_SRC_DIR);
     clean_cargo_toml()?;
     clean_files()?;
 
-    cargo_build(toolchain)?.wait()?;
+    *WARM_BUILD.lock().expect("poisoned lock") = Some(cargo_build(toolchain)?);
+    Ok(())
+}
+
+/// Wait for the build started by `cargo_new_in_background`
+///
+/// Cargo would wait for the build directory lock anyway, but this makes sure the outputs read afterwards are not from a build racing with the session code
+pub fn wait_for_warm_build() -> std::result::Result<(), io::Error> {
+    let build = WARM_BUILD.lock().expect("poisoned lock").take();
+    if let Some(mut build) = build {
+        build.wait()?;
+    }
     Ok(())
 }
 
+/// Names of the repl crate dependencies
+pub fn dependencies() -> Vec<String> {
+    let cargo_toml = fs::read_to_string(&*CARGO_TOML_FILE).unwrap_or_default();
+    cargo_toml
+        .lines()
+        .skip_while(|line| line.trim() != "[dependencies]")
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .filter(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .filter_map(|line| Some(line.split('=').next()?.trim().to_string()))
+        .collect()
+}
+
+/// The value of a dependency in the repl Cargo.toml, exp: `"0.8.5"`
+pub fn dependency_spec(name: &str) -> Option<String> {
+    let cargo_toml = fs::read_to_string(&*CARGO_TOML_FILE).ok()?;
+    cargo_toml
+        .lines()
+        .skip_while(|line| line.trim() != "[dependencies]")
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .find_map(|line| {
+            let (dep, spec) = line.split_once('=')?;
+            if dep.trim() == name {
+                Some(spec.trim().to_string())
+            } else {
+                None
+            }
+        })
+}
+
+/// The crate a failed build complains about if it looks like a missing dependency
+///
+/// exp: "unresolved import `rand`" or "can't find crate for `rand`"
+pub fn missing_crate(output: &str) -> Option<String> {
+    const MISSING_CRATE_ERRORS: &[&str] = &[
+        "unresolved import `",
+        "can't find crate for `",
+        "use of undeclared crate or module `",
+        "use of unresolved module or unlinked crate `",
+    ];
+    const NOT_CRATES: &[&str] = &["std", "core", "alloc", "crate", "self", "super"];
+
+    // the build output is colored
+    let output = strip_ansi_escapes(output);
+    output.lines().find_map(|line| {
+        MISSING_CRATE_ERRORS.iter().find_map(|error| {
+            let path = &line[line.find(error)? + error.len()..];
+            let name = path[..path.find('`')?].split("::").next()?;
+            let is_crate_name = name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                && name.starts_with(|c: char| c.is_ascii_lowercase());
+            if is_crate_name && !NOT_CRATES.contains(&name) {
+                Some(name.to_string())
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// Number of dependencies added to the repl
+pub fn dependencies_count() -> usize {
+    dependencies().len()
+}
+
 pub fn cargo_run(color: bool, release: bool, toolchain: ToolChain) -> Result<(ExitStatus, String)> {
+    let release = release || EVAL_RELEASE.load(Ordering::Relaxed);
     let (status, output) = cargo_build_output(color, release, toolchain)?;
 
     if !status.success() {
         Ok((status, output))
     } else {
-        // Run the exexcutable directly instead of cargo run
-        // This allows to run it without modifying the current working directory
-        // example: std::process::Commmand::new("pwd") will output the expected path instead of `/tmp/irust_repl`
-        if !release {
-            Ok((
-                status,
-                stdout_and_stderr(
-                    std::process::Command::new(&*EXE_PATH)
-                        .stdout(Stdio::piped())
-                        .stderr(Stdio::piped())
-                        .spawn()?
-                        .output_with_ctrlc_cancel()?,
-                ),
-            ))
+        let output = run_exe(release)?;
+        if output.status.success() {
+            Ok((status, stdout_and_stderr(output)))
         } else {
+            // keep what was printed before the panic
             Ok((
                 status,
-                stdout_and_stderr(
-                    std::process::Command::new(&*RELEASE_EXE_PATH)
-                        .stdout(Stdio::piped())
-                        .stderr(Stdio::piped())
-                        .spawn()?
-                        .output_with_ctrlc_cancel()?,
-                ),
+                String::from_utf8_lossy(&output.stdout).to_string()
+                    + &String::from_utf8_lossy(&output.stderr),
             ))
         }
     }
 }
 
+/// Like `cargo_run` but the program stderr is returned separately, build errors are still part of the output
+pub fn cargo_run_separate_stderr(
+    color: bool,
+    release: bool,
+    toolchain: ToolChain,
+) -> Result<(ExitStatus, String, String)> {
+    let release = release || EVAL_RELEASE.load(Ordering::Relaxed);
+    let (status, output) = cargo_build_output(color, release, toolchain)?;
+
+    if !status.success() {
+        Ok((status, output, String::new()))
+    } else {
+        let output = run_exe(release)?;
+        Ok((
+            status,
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    }
+}
+
+fn run_exe(release: bool) -> Result<std::process::Output> {
+    // Run the exexcutable directly instead of cargo run
+    // This allows to run it without modifying the current working directory
+    // example: std::process::Commmand::new("pwd") will output the expected path instead of `/tmp/irust_repl`
+    let mut exe = match target() {
+        Some(target) => match target.runner.split_first() {
+            Some((runner, args)) => {
+                let mut exe = std::process::Command::new(runner);
+                exe.args(args).arg(target.exe_path(release));
+                exe
+            }
+            None => std::process::Command::new(target.exe_path(release)),
+        },
+        None if release => std::process::Command::new(&*RELEASE_EXE_PATH),
+        None => std::process::Command::new(&*EXE_PATH),
+    };
+    // the backtrace is rendered by `beautify_panic`, unless the user asked for it
+    let user_backtrace = std::env::var("RUST_BACKTRACE").ok();
+    if user_backtrace.is_none() {
+        exe.env("RUST_BACKTRACE", "1");
+    }
+    limit_memory(&mut exe);
+    let timeout = match EVAL_TIMEOUT.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(std::time::Duration::from_secs(secs)),
+    };
+    // exp: the runner of the target isn't installed
+    let program = exe.get_program().to_string_lossy().into_owned();
+    let mut output = exe
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run `{}`: {}", program, e))?
+        .output_with_ctrlc_cancel(timeout)?;
+
+    if !output.status.success() && user_backtrace.as_deref() != Some("full") {
+        let main_rs = fs::read_to_string(&*MAIN_FILE).unwrap_or_default();
+        if let Some(panic) = beautify_panic(&String::from_utf8_lossy(&output.stderr), &main_rs) {
+            output.stderr = panic.into_bytes();
+        }
+    }
+    Ok(output)
+}
+
+/// Apply `EVAL_MEMORY_LIMIT` to the process with an rlimit, allocations over it fail
+fn limit_memory(exe: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let limit = EVAL_MEMORY_LIMIT.load(Ordering::Relaxed);
+        if limit == 0 {
+            return;
+        }
+        let bytes = limit.saturating_mul(1024 * 1024) as nix::libc::rlim_t;
+        // setrlimit is async-signal-safe so it can be called between fork and exec
+        unsafe {
+            exe.pre_exec(move || {
+                let rlimit = nix::libc::rlimit {
+                    rlim_cur: bytes,
+                    rlim_max: bytes,
+                };
+                if nix::libc::setrlimit(nix::libc::RLIMIT_AS, &rlimit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = exe;
+}
+
 pub fn cargo_add(dep: &[String]) -> io::Result<std::process::Child> {
     //TODO is this required?
     clean_files()?;
@@ -132,12 +380,30 @@ macro_rules! cargo_common {
         Command::new("cargo")
             .arg($toolchain.as_arg())
             .arg($cmd)
+            .args(target_args())
             .env("CARGO_TARGET_DIR", &*IRUST_TARGET_DIR)
+            .envs(rustc_wrapper())
             //.env("RUSTFLAGS", "-Awarnings") // Not required anymore
             .current_dir(&*IRUST_DIR)
     };
 }
 
+/// `Command::output` that is interrupted on ctrl-c
+fn interruptible_output(cmd: &mut Command) -> Result<std::process::Output> {
+    cmd.stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?
+        .output_with_ctrlc_cancel(None)
+}
+
+fn rustc_wrapper() -> Option<(&'static str, &'static str)> {
+    if USE_SCCACHE.load(Ordering::Relaxed) {
+        Some(("RUSTC_WRAPPER", "sccache"))
+    } else {
+        None
+    }
+}
+
 pub fn cargo_check(toolchain: ToolChain) -> std::result::Result<std::process::Child, io::Error> {
     cargo_common!("check", toolchain)
         .stdout(std::process::Stdio::null())
@@ -145,7 +411,19 @@ pub fn cargo_check(toolchain: ToolChain) -> std::result::Result<std::process::Ch
         .spawn()
 }
 
-pub fn cargo_check_output(toolchain: ToolChain) -> std::result::Result<String, io::Error> {
+/// `cargo check` with one line per diagnostic on stderr, exp: `src/main.rs:2:5: error[E0425]: ..`
+pub fn cargo_check_short(
+    toolchain: ToolChain,
+) -> std::result::Result<std::process::Child, io::Error> {
+    cargo_common!("check", toolchain)
+        .args(["--message-format", "short", "--color", "never"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+}
+
+pub fn cargo_check_output(toolchain: ToolChain) -> Result<String> {
+    wait_for_warm_build()?;
     #[cfg(not(windows))]
     let color = "always";
     #[cfg(windows)]
@@ -155,11 +433,9 @@ pub fn cargo_check_output(toolchain: ToolChain) -> std::result::Result<String, i
         "never"
     };
 
-    Ok(stdout_and_stderr(
-        cargo_common!("check", toolchain)
-            .args(&["--color", color])
-            .output()?,
-    ))
+    Ok(stdout_and_stderr(interruptible_output(
+        cargo_common!("check", toolchain).args(["--color", color]),
+    )?))
 }
 
 pub fn cargo_build(toolchain: ToolChain) -> std::result::Result<std::process::Child, io::Error> {
@@ -173,7 +449,8 @@ pub fn cargo_build_output(
     color: bool,
     release: bool,
     toolchain: ToolChain,
-) -> std::result::Result<(ExitStatus, String), io::Error> {
+) -> Result<(ExitStatus, String)> {
+    wait_for_warm_build()?;
     #[cfg(not(windows))]
     let color = if color { "always" } else { "never" };
     #[cfg(windows)]
@@ -188,26 +465,33 @@ pub fn cargo_build_output(
     };
 
     let output = if !release {
-        cargo_common!("build", toolchain)
-            .args(&["--color", color])
-            .output()?
+        interruptible_output(cargo_common!("build", toolchain).args(["--color", color]))?
     } else {
-        cargo_common!("build", toolchain)
-            .arg("--release")
-            .args(&["--color", color])
-            .output()?
+        interruptible_output(
+            cargo_common!("build", toolchain)
+                .arg("--release")
+                .args(["--color", color]),
+        )?
     };
     let status = output.status;
 
     Ok((status, stdout_and_stderr(output)))
 }
 
-pub fn cargo_bench(toolchain: ToolChain) -> std::result::Result<String, io::Error> {
-    Ok(stdout_and_stderr(
-        cargo_common!("bench", toolchain)
-            .args(&["--color", "always"])
-            .output()?,
-    ))
+/// Remove the build artifacts of the session, the shared target directory if it's used
+pub fn cargo_clean(toolchain: ToolChain) -> std::result::Result<(ExitStatus, String), io::Error> {
+    wait_for_warm_build()?;
+    let output = cargo_common!("clean", toolchain)
+        .args(["--color", "always"])
+        .output()?;
+    Ok((output.status, stdout_and_stderr(output)))
+}
+
+pub fn cargo_bench(toolchain: ToolChain) -> Result<String> {
+    wait_for_warm_build()?;
+    Ok(stdout_and_stderr(interruptible_output(
+        cargo_common!("bench", toolchain).args(["--color", "always"]),
+    )?))
 }
 
 fn clean_cargo_toml() -> io::Result<()> {
@@ -219,15 +503,41 @@ version = "0.1.0"
 edition = "2018""#;
     let mut cargo_toml_file = fs::File::create(&*CARGO_TOML_FILE)?;
     write!(cargo_toml_file, "{}", CARGO_TOML)?;
+
+    let default_deps = DEFAULT_DEPS.lock().expect("poisoned lock");
+    if !default_deps.is_empty() {
+        write!(cargo_toml_file, "\n\n[dependencies]")?;
+        for dep in default_deps.iter() {
+            write!(cargo_toml_file, "\n{}", dependency_line(dep))?;
+        }
+    }
     Ok(())
 }
 
+/// `name`, `name=version` or a Cargo.toml dependency line => Cargo.toml dependency line
+///
+/// exp: `serde = { version = "1", features = ["derive"] }`
+fn dependency_line(dep: &str) -> String {
+    match dep.split_once('=') {
+        Some((name, value)) => {
+            let value = value.trim();
+            if value.starts_with('"') || value.starts_with('{') {
+                format!("{} = {}", name.trim(), value)
+            } else {
+                format!("{} = \"{}\"", name.trim(), value)
+            }
+        }
+        None => format!("{} = \"*\"", dep.trim()),
+    }
+}
+
 fn clean_files() -> io::Result<()> {
     const MAIN_SRC: &str = "fn main() {\n\n}";
     let mut main = fs::File::create(&*MAIN_FILE)?;
     write!(main, "{}", MAIN_SRC)?;
     std::fs::copy(&*MAIN_FILE, &*MAIN_FILE_EXTERN)?;
     let _ = std::fs::remove_file(&*LIB_FILE);
+    super::meta::write_default_meta()?;
     Ok(())
 }
 
@@ -238,6 +548,7 @@ pub fn cargo_fmt(c: &str) -> std::io::Result<String> {
 
     let mut fmt_file = fs::OpenOptions::new()
         .create(true)
+        .truncate(true)
         .read(true)
         .write(true)
         .open(&fmt_path)?;
@@ -254,12 +565,12 @@ pub fn cargo_fmt(c: &str) -> std::io::Result<String> {
 }
 
 pub fn cargo_asm(fnn: &str, toolchain: ToolChain) -> Result<String> {
-    Ok(stdout_and_stderr(
+    wait_for_warm_build()?;
+    Ok(stdout_and_stderr(interruptible_output(
         cargo_common!("asm", toolchain)
             .arg("--lib")
-            .arg(format!("irust_repl::{}", fnn))
-            .output()?,
-    ))
+            .arg(format!("irust_repl::{}", fnn)),
+    )?))
 }
 
 pub fn cargo_fmt_file(file: &Path) {
@@ -282,3 +593,58 @@ fn try_cargo_fmt_file(file: &Path) -> io::Result<()> {
         .wait()?;
     Ok(())
 }
+
+#[test]
+fn dependency_line_test() {
+    assert_eq!(dependency_line("itertools"), "itertools = \"*\"");
+    assert_eq!(dependency_line("rand=0.8"), "rand = \"0.8\"");
+    assert_eq!(
+        dependency_line("serde = { version = \"1\", features = [\"derive\"] }"),
+        "serde = { version = \"1\", features = [\"derive\"] }"
+    );
+}
+
+#[test]
+fn missing_crate_test() {
+    assert_eq!(
+        missing_crate("\x1b[0m\x1b[1m\x1b[38;5;9merror[E0432]\x1b[0m\x1b[0m\x1b[1m: unresolved import `rand`\x1b[0m"),
+        Some("rand".to_string())
+    );
+    assert_eq!(
+        missing_crate(
+            "error[E0433]: failed to resolve: use of undeclared crate or module `itertools`"
+        ),
+        Some("itertools".to_string())
+    );
+    assert_eq!(
+        missing_crate("error[E0463]: can't find crate for `serde_json`"),
+        Some("serde_json".to_string())
+    );
+    assert_eq!(
+        missing_crate("error[E0432]: unresolved import `regex::Regex`"),
+        Some("regex".to_string())
+    );
+    assert_eq!(
+        missing_crate("error[E0432]: unresolved import `std::foo`"),
+        None
+    );
+    assert_eq!(missing_crate("error[E0432]: unresolved import `Foo`"), None);
+    assert_eq!(
+        missing_crate("error[E0425]: cannot find value `a` in this scope"),
+        None
+    );
+}
+
+#[test]
+fn target_exe_path_test() {
+    let target = |triple: &str| Target {
+        triple: triple.to_string(),
+        runner: vec![],
+    };
+    assert!(target("wasm32-wasi")
+        .exe_path(false)
+        .ends_with("wasm32-wasi/debug/irust_repl.wasm"));
+    assert!(target("x86_64-pc-windows-gnu")
+        .exe_path(true)
+        .ends_with("x86_64-pc-windows-gnu/release/irust_repl.exe"));
+}
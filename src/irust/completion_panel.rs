@@ -0,0 +1,163 @@
+use super::racer::Racer;
+use super::{BelowInputRow, IRust, Result};
+use crate::utils::StringTools;
+
+/// The word being completed before the cursor, `None` if there is nothing to complete
+///
+/// Right after `.` or `::` the word is empty, every member is a candidate then
+fn current_word(input: &str) -> Option<&str> {
+    let word_start = input
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(|idx| idx + input[idx..].chars().next().unwrap().len_utf8())
+        .unwrap_or(0);
+    let word = &input[word_start..];
+
+    if !word.is_empty() || input.ends_with('.') || input.ends_with("::") {
+        Some(word)
+    } else {
+        None
+    }
+}
+
+impl IRust {
+    pub fn completion_panel_is_shown(&self) -> bool {
+        self.options.completion_panel
+            && self
+                .racer
+                .as_ref()
+                .map(|r| !r.suggestions().is_empty())
+                .unwrap_or(false)
+    }
+
+    /// Fetch completions for the word before the cursor, they are drawn by `print_input`
+    pub fn update_completion_panel(&mut self) -> Result<()> {
+        if !self.options.completion_panel {
+            return Ok(());
+        }
+        let racer = match self.racer.as_mut() {
+            Some(racer) => racer,
+            None => return Ok(()),
+        };
+
+        let input: String = self.buffer.iter().take(self.buffer.buffer_pos).collect();
+        let word = if input.starts_with("::") {
+            // don't autocomplete shell commands
+            None
+        } else if input.starts_with(':') {
+            // IRust commands and their arguments are already filtered
+            Some("")
+        } else {
+            current_word(&input)
+        };
+
+        match word {
+            Some(word) => {
+                racer.unlock_racer_update()?;
+                racer.update_live_suggestions(&self.buffer, &mut self.repl)?;
+                racer.retain_suggestions(|(name, _)| name.starts_with(word));
+            }
+            None => racer.retain_suggestions(|_| false),
+        }
+        Ok(())
+    }
+
+    /// The completions rows drawn under the input, with the selected one highlighted
+    pub fn completion_panel_rows(&self) -> Vec<BelowInputRow> {
+        let racer = match self.racer.as_ref() {
+            Some(racer) if self.completion_panel_is_shown() => racer,
+            _ => return vec![],
+        };
+        let rows_num = std::cmp::min(
+            racer.suggestions().len(),
+            self.options.completion_panel_rows,
+        );
+
+        // keep the selected suggestion in view
+        let selected = racer.selected_suggestion_idx();
+        let first = selected
+            .map(|idx| idx + 1)
+            .unwrap_or(0)
+            .saturating_sub(rows_num);
+        racer
+            .suggestions()
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows_num)
+            .map(|(idx, suggestion)| {
+                let bg = if Some(idx) == selected {
+                    Some(self.options.racer_selected_suggestion_color)
+                } else {
+                    None
+                };
+                vec![(
+                    Racer::full_suggestion(suggestion),
+                    self.options.racer_suggestions_table_color,
+                    bg,
+                )]
+            })
+            .collect()
+    }
+
+    /// Hide the panel and clear it from the screen
+    pub fn hide_completion_panel(&mut self) -> Result<()> {
+        if self.completion_panel_is_shown() {
+            // safe unwrap: the panel is only shown with racer
+            self.racer.as_mut().unwrap().retain_suggestions(|_| false);
+            self.print_input()?;
+        }
+        Ok(())
+    }
+
+    /// Move the panel selection with Up/Down
+    pub fn cycle_completion_panel(&mut self, down: bool) -> Result<()> {
+        if let Some(racer) = self.racer.as_mut() {
+            if down {
+                racer.goto_next_suggestion();
+            } else {
+                racer.goto_previous_suggestion();
+            }
+        }
+        self.print_below_input()
+    }
+
+    /// Insert the selected completion, or the first one if none is selected
+    pub fn accept_completion(&mut self) -> Result<()> {
+        let suggestion = match self.racer.as_ref() {
+            Some(racer) => {
+                let idx = racer.selected_suggestion_idx().unwrap_or(0);
+                racer.suggestions().get(idx).map(|(name, _)| name.clone())
+            }
+            None => None,
+        };
+        if let Some(mut suggestion) = suggestion {
+            // safe unwrap: we just got a suggestion from racer
+            self.racer.as_mut().unwrap().record_acceptance(&suggestion);
+            StringTools::strings_unique(
+                &self
+                    .buffer
+                    .iter()
+                    .take(self.buffer.buffer_pos)
+                    .collect::<String>(),
+                &mut suggestion,
+            );
+            self.buffer.insert_str(&suggestion);
+            // safe unwrap: we just got a suggestion from racer
+            self.racer.as_mut().unwrap().retain_suggestions(|_| false);
+            self.print_input()?;
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn current_word_test() {
+    assert_eq!(current_word("let a = ve"), Some("ve"));
+    assert_eq!(current_word("v.it"), Some("it"));
+    assert_eq!(current_word("v."), Some(""));
+    assert_eq!(current_word("std::"), Some(""));
+    assert_eq!(current_word("émoji_ö"), Some("émoji_ö"));
+    assert_eq!(current_word("foo("), None);
+    assert_eq!(current_word(""), None);
+}
@@ -0,0 +1,123 @@
+use super::cargo_cmds::IRUST_DIR;
+use super::Result;
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Crates found with `cargo search` are saved here, so they can be completed/searched offline
+static CRATES_CACHE_FILE: Lazy<PathBuf> = Lazy::new(|| IRUST_DIR.join("crates_cache"));
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crate {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+}
+
+/// Search crates.io with `cargo search`, the results are added to the offline cache
+pub fn search(term: &str, limit: usize) -> Result<Vec<Crate>> {
+    let output = Command::new("cargo")
+        .arg("search")
+        .arg(term)
+        .args(["--limit", &limit.to_string()])
+        .args(["--color", "never"])
+        .output()?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().into());
+    }
+
+    let crates = parse_cargo_search(&String::from_utf8_lossy(&output.stdout));
+    // The cache is optional
+    let _ = update_cache(&crates);
+    Ok(crates)
+}
+
+/// Crates saved in the offline cache
+pub fn cached() -> Vec<Crate> {
+    std::fs::read_to_string(&*CRATES_CACHE_FILE)
+        .map(|cache| {
+            cache
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.splitn(3, '\t');
+                    Some(Crate {
+                        name: fields.next()?.to_string(),
+                        version: fields.next()?.to_string(),
+                        description: fields.next().unwrap_or_default().to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Search the offline cache, used when crates.io can't be reached
+pub fn search_cached(term: &str, limit: usize) -> Vec<Crate> {
+    let term = term.to_lowercase();
+    cached()
+        .into_iter()
+        .filter(|c| c.name.contains(&term) || c.description.to_lowercase().contains(&term))
+        .take(limit)
+        .collect()
+}
+
+fn update_cache(crates: &[Crate]) -> Result<()> {
+    let mut cache = cached();
+    for krate in crates {
+        cache.retain(|c| c.name != krate.name);
+        cache.push(krate.clone());
+    }
+    cache.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let cache: Vec<String> = cache
+        .iter()
+        .map(|c| format!("{}\t{}\t{}", c.name, c.version, c.description))
+        .collect();
+    std::fs::write(&*CRATES_CACHE_FILE, cache.join("\n"))?;
+    Ok(())
+}
+
+/// Parse `cargo search` output
+///
+/// exp: serde = "1.0.125"    # A generic serialization/deserialization framework
+fn parse_cargo_search(output: &str) -> Vec<Crate> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(" = \"")?;
+            let (version, rest) = rest.split_once('"')?;
+            let description = rest
+                .trim_start()
+                .strip_prefix('#')
+                .unwrap_or_default()
+                .trim();
+            Some(Crate {
+                name: name.to_string(),
+                version: version.to_string(),
+                description: description.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn parse_cargo_search_test() {
+    let output = r#"serde = "1.0.125"             # A generic serialization/deserialization framework
+serde_json = "1.0.64"        # A JSON serialization file format
+... and 3452 crates more (use --limit N to see more)"#;
+    assert_eq!(
+        parse_cargo_search(output),
+        vec![
+            Crate {
+                name: "serde".into(),
+                version: "1.0.125".into(),
+                description: "A generic serialization/deserialization framework".into()
+            },
+            Crate {
+                name: "serde_json".into(),
+                version: "1.0.64".into(),
+                description: "A JSON serialization file format".into()
+            }
+        ]
+    );
+}
@@ -0,0 +1,112 @@
+use super::cargo_cmds::{cargo_build_output, target, EXE_PATH};
+use super::format::format_err;
+use super::{IRust, Result};
+use printer::printer::PrintQueue;
+
+/// Arguments that stop the program at `line` of `main.rs` and start it
+fn breakpoint_args(debugger: &str, line: usize) -> Vec<String> {
+    if debugger.contains("lldb") {
+        vec![
+            "-o".into(),
+            format!("breakpoint set --file main.rs --line {}", line),
+            "-o".into(),
+            "run".into(),
+        ]
+    } else {
+        vec![
+            "-ex".into(),
+            format!("break main.rs:{}", line),
+            "-ex".into(),
+            "run".into(),
+        ]
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn ignore_signal(_: nix::libc::c_int) {}
+
+impl IRust {
+    /// `:debug <expression>`: build the session with the expression and run it under `rust-gdb`/`rust-lldb`
+    ///
+    /// The debugger stops at the expression, IRust gives it the terminal until it exits
+    pub fn debug(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        let expression = buffer
+            .strip_prefix(":debug")
+            .expect("already checked")
+            .trim();
+        if expression.is_empty() {
+            return Err("No expression specified".into());
+        }
+        if let Some(target) = target() {
+            return Err(format!(
+                "`:debug` only supports the host target, the session targets `{}` (see `:target host`)",
+                target.triple
+            )
+            .into());
+        }
+        let input = format!("println!(\"{{:?}}\", {{\n{}\n}});", expression);
+        // 1-based line of the expression in main.rs
+        let line = self.repl.input_line() + 1;
+
+        let toolchain = self.options.toolchain;
+        let debugger = self.options.debugger.clone();
+        let printer = &mut self.printer;
+        let mut build_errors = None;
+        self.repl.eval_in_tmp_repl(input, || -> Result<()> {
+            // debug builds have debug info
+            let (status, output) = cargo_build_output(true, false, toolchain)?;
+            if !status.success() {
+                build_errors = Some(output);
+                return Ok(());
+            }
+
+            printer.suspend()?;
+            let status = run_debugger(&debugger, line);
+            printer.resume()?;
+            status
+        })?;
+
+        match build_errors {
+            Some(errors) => Ok(format_err(&errors)),
+            None => Ok(PrintQueue::default()),
+        }
+    }
+}
+
+fn run_debugger(debugger: &str, line: usize) -> Result<()> {
+    // ctrl-c is meant for the debugger, don't let it kill IRust
+    // the handler is reset in the debugger process since it's not inherited by exec
+    #[cfg(unix)]
+    let previous_handler = unsafe {
+        use nix::sys::signal::{signal, SigHandler, Signal};
+        signal(Signal::SIGINT, SigHandler::Handler(ignore_signal))?
+    };
+
+    let status = std::process::Command::new(debugger)
+        .args(breakpoint_args(debugger, line))
+        .arg(&*EXE_PATH)
+        .status();
+
+    #[cfg(unix)]
+    unsafe {
+        nix::sys::signal::signal(nix::sys::signal::Signal::SIGINT, previous_handler)?;
+    }
+
+    match status {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("failed to start {}: {}", debugger, e).into()),
+    }
+}
+
+#[test]
+fn breakpoint_args_test() {
+    assert_eq!(
+        breakpoint_args("rust-gdb", 12),
+        ["-ex", "break main.rs:12", "-ex", "run"]
+    );
+    assert_eq!(
+        breakpoint_args("rust-lldb", 3)[1],
+        "breakpoint set --file main.rs --line 3"
+    );
+}
@@ -0,0 +1,142 @@
+use super::cargo_cmds::cargo_check_short;
+use super::{IRust, Result};
+use std::io::Read;
+use std::time::Duration;
+
+/// (line, column) of the errors reported by `cargo check --message-format short` in `src/main.rs`, both start at 1
+fn parse_short_errors(output: &str) -> Vec<(usize, usize)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.strip_prefix("src/main.rs:")?.splitn(3, ':');
+            let line = parts.next()?.parse().ok()?;
+            let column = parts.next()?.parse().ok()?;
+            if !parts.next()?.trim_start().starts_with("error") {
+                return None;
+            }
+            Some((line, column))
+        })
+        .collect()
+}
+
+/// The span of the word starting at (row, column) of the input, rows and columns start at 0
+fn word_span(input: &str, row: usize, column: usize) -> Option<std::ops::Range<usize>> {
+    let mut start = 0;
+    for line in input.split('\n').take(row) {
+        start += line.chars().count() + 1;
+    }
+    let line = input.split('\n').nth(row)?;
+    let rest: Vec<char> = line.chars().skip(column).collect();
+    if rest.is_empty() {
+        return None;
+    }
+    let len = rest
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_')
+        .count()
+        .max(1);
+
+    Some(start + column..start + column + len)
+}
+
+impl IRust {
+    /// The input changed, check it again once the user stops typing
+    pub fn schedule_input_check(&mut self) {
+        self.printer.underlined.clear();
+        self.input_check_pending = self.options.check_as_you_type;
+    }
+
+    /// Remove the error underlines
+    pub fn clear_diagnostics(&mut self) -> Result<()> {
+        self.input_check_pending = false;
+        if !self.printer.underlined.is_empty() {
+            self.printer.underlined.clear();
+            self.print_input()?;
+        }
+        Ok(())
+    }
+
+    /// `cargo check` the input merged into the session and underline the errors
+    ///
+    /// The check is abandoned as soon as a new event is available
+    pub fn check_input(&mut self) -> Result<()> {
+        self.input_check_pending = false;
+        let input = self.buffer.to_string();
+        let trimmed = input.trim();
+        // commands and crate attributes are not checked
+        if trimmed.is_empty() || trimmed.starts_with(':') || trimmed.starts_with("#!") {
+            return Ok(());
+        }
+
+        // check expressions the same way they are evaluated
+        let (code, wrapped) = if trimmed.ends_with(';') {
+            (input.clone(), false)
+        } else {
+            (format!("println!(\"{{:?}}\", {{\n{}\n}});", input), true)
+        };
+        let mut repl = self.repl.clone();
+        let first_input_line = repl.input_line() + 1 + wrapped as usize;
+        repl.insert(code);
+        repl.write()?;
+
+        let mut child = cargo_check_short(self.options.toolchain)?;
+        let mut stderr = child.stderr.take().ok_or("failed to access cargo stderr")?;
+        // read concurrently so cargo never blocks on a full pipe
+        let output = std::thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        });
+
+        let finished = loop {
+            if child.try_wait()?.is_some() {
+                break true;
+            }
+            if crossterm::event::poll(Duration::from_millis(20))? {
+                // the input is probably outdated
+                child.kill()?;
+                child.wait()?;
+                break false;
+            }
+        };
+        // restore the session code on disk
+        self.repl.write()?;
+
+        let output = output.join().map_err(|_| "failed to read cargo output")?;
+        if !finished {
+            return Ok(());
+        }
+
+        self.printer.underlined = parse_short_errors(&output)
+            .into_iter()
+            .filter_map(|(line, column)| {
+                let row = line.checked_sub(first_input_line)?;
+                word_span(&input, row, column.checked_sub(1)?)
+            })
+            .collect();
+        if !self.printer.underlined.is_empty() {
+            self.print_input()?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn parse_short_errors_test() {
+    let output = "\
+    Checking irust_repl v0.1.0 (/tmp/irust_repl)
+src/main.rs:3:13: error[E0425]: cannot find value `b` in this scope
+src/main.rs:2:9: warning: unused variable: `a`
+src/main.rs:4:1: error: expected expression, found `}`
+error: could not compile `irust_repl` due to 2 previous errors";
+
+    assert_eq!(parse_short_errors(output), [(3, 13), (4, 1)]);
+}
+
+#[test]
+fn word_span_test() {
+    let input = "let a = 1;\nlet b = foo + 2;";
+    assert_eq!(word_span(input, 1, 8), Some(19..22));
+    assert_eq!(word_span(input, 1, 12), Some(23..24));
+    assert_eq!(word_span(input, 2, 0), None);
+}
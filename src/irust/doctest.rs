@@ -0,0 +1,330 @@
+//! `:doctest` evaluates the examples of a doc comment or a markdown ```rust block
+use super::format::{format_check_output, format_err};
+use super::{IRust, Result};
+use printer::printer::{PrintQueue, PrinterItem};
+use std::process::Command;
+
+/// Printed after the example ran, its absence means the example panicked
+const DOCTEST_MARK: &str = "\u{1}IRUST_DOCTEST\u{1}";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    Run,
+    Ignore,
+    NoRun,
+    CompileFail,
+    ShouldPanic,
+}
+
+#[derive(Debug, PartialEq)]
+struct Example {
+    code: String,
+    kind: Kind,
+    /// Line of the opening fence, starts at 1
+    line: usize,
+}
+
+/// Remove the `///` and `//!` doc comment markers
+fn strip_doc_comment(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            match trimmed
+                .strip_prefix("///")
+                .or_else(|| trimmed.strip_prefix("//!"))
+            {
+                Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+                None => line,
+            }
+        })
+        .collect()
+}
+
+fn fence(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    line.strip_prefix("```")
+        .or_else(|| line.strip_prefix("~~~"))
+}
+
+/// The kind of a fenced block from its info string (exp: `rust,no_run`), None if it isn't rust
+fn fence_kind(info: &str) -> Option<Kind> {
+    let mut kind = Kind::Run;
+    for attribute in info.split(|c: char| c == ',' || c.is_whitespace()) {
+        match attribute.trim() {
+            "" | "rust" | "test_harness" | "allow_fail" => (),
+            "ignore" => kind = Kind::Ignore,
+            "no_run" => kind = Kind::NoRun,
+            "compile_fail" => kind = Kind::CompileFail,
+            "should_panic" => kind = Kind::ShouldPanic,
+            attribute if attribute.starts_with("edition") || attribute.starts_with("ignore-") => {}
+            // exp: ```text
+            _ => return None,
+        }
+    }
+    Some(kind)
+}
+
+/// Rustdoc hidden lines (`# let a = 1;`) are part of the example
+fn unhide(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    if trimmed == "#" {
+        ""
+    } else if let Some(rest) = trimmed.strip_prefix("# ") {
+        rest
+    } else if trimmed.starts_with("##") {
+        &trimmed[1..]
+    } else {
+        line
+    }
+}
+
+/// The rust examples of the text, all of it is one example when there are no fenced blocks
+fn examples(text: &str) -> Vec<Example> {
+    let lines = strip_doc_comment(text);
+    if !lines.iter().any(|line| fence(line).is_some()) {
+        let code: Vec<&str> = lines.iter().map(|line| unhide(line)).collect();
+        return vec![Example {
+            code: code.join("\n"),
+            kind: Kind::Run,
+            line: 1,
+        }];
+    }
+
+    let mut examples = vec![];
+    let mut lines = lines.iter().enumerate();
+    while let Some((idx, line)) = lines.next() {
+        let info = match fence(line) {
+            Some(info) => info,
+            None => continue,
+        };
+        let code: Vec<&str> = lines
+            .by_ref()
+            .map(|(_, line)| *line)
+            .take_while(|line| fence(line).is_none())
+            .map(unhide)
+            .collect();
+        if let Some(kind) = fence_kind(info) {
+            examples.push(Example {
+                code: code.join("\n"),
+                kind,
+                line: idx + 1,
+            });
+        }
+    }
+    examples
+}
+
+/// A `:doctest` input with a fenced block that isn't closed yet continues on the next line
+pub fn unclosed_fence(buffer: &str) -> bool {
+    match buffer.strip_prefix(":doctest") {
+        Some(text) => {
+            strip_doc_comment(text)
+                .iter()
+                .filter(|line| fence(line).is_some())
+                .count()
+                % 2
+                == 1
+        }
+        None => false,
+    }
+}
+
+/// Like rustdoc, `main` is called if the example defines it
+fn example_body(code: &str) -> String {
+    if code.contains("fn main(") {
+        format!("{}\nmain()", code)
+    } else {
+        code.to_string()
+    }
+}
+
+fn read_clipboard() -> Result<String> {
+    #[cfg(target_os = "macos")]
+    const COMMANDS: &[&[&str]] = &[&["pbpaste"]];
+    #[cfg(windows)]
+    const COMMANDS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard"]];
+    #[cfg(all(unix, not(target_os = "macos")))]
+    const COMMANDS: &[&[&str]] = &[
+        &["wl-paste", "--no-newline"],
+        &["xclip", "-selection", "clipboard", "-o"],
+        &["xsel", "--clipboard", "--output"],
+    ];
+
+    for cmd in COMMANDS {
+        if let Ok(output) = Command::new(cmd[0]).args(&cmd[1..]).output() {
+            if output.status.success() {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+            }
+        }
+    }
+    Err("Failed to read the clipboard (requires wl-paste, xclip or xsel), the example can be written after `:doctest` instead".into())
+}
+
+enum Outcome {
+    Passed,
+    Ignored,
+    /// The details of the failure
+    Failed(PrintQueue),
+}
+
+impl IRust {
+    /// `:doctest [text]` evaluates the examples of the text (the clipboard by default) in the session
+    pub fn doctest(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        let text = buffer[":doctest".len()..].trim_start_matches(' ');
+        let text = if text.trim().is_empty() {
+            read_clipboard()?
+        } else {
+            text.to_string()
+        };
+        let examples = examples(&text);
+        if examples
+            .iter()
+            .all(|example| example.code.trim().is_empty())
+        {
+            return Err("No rust example found".into());
+        }
+
+        let mut print_queue = PrintQueue::default();
+        let (mut passed, mut failed, mut ignored) = (0, 0, 0);
+        for (idx, example) in examples.iter().enumerate() {
+            let outcome = self.run_example(example)?;
+            let (status, color) = match outcome {
+                Outcome::Passed => {
+                    passed += 1;
+                    ("ok", self.options.ok_color)
+                }
+                Outcome::Ignored => {
+                    ignored += 1;
+                    ("ignored", self.options.irust_warn_color)
+                }
+                Outcome::Failed(_) => {
+                    failed += 1;
+                    ("FAILED", self.options.err_color)
+                }
+            };
+            print_queue.push(PrinterItem::String(
+                format!("example {} (line {}) ... ", idx + 1, example.line),
+                self.options.irust_color,
+            ));
+            print_queue.push(PrinterItem::Str(status, color));
+            print_queue.add_new_line(1);
+            if let Outcome::Failed(mut details) = outcome {
+                print_queue.append(&mut details);
+            }
+        }
+
+        let color = if failed == 0 {
+            self.options.ok_color
+        } else {
+            self.options.err_color
+        };
+        print_queue.push(PrinterItem::String(
+            format!(
+                "doctest result: {} passed, {} failed, {} ignored",
+                passed, failed, ignored
+            ),
+            color,
+        ));
+        print_queue.add_new_line(1);
+        Ok(print_queue)
+    }
+
+    fn run_example(&mut self, example: &Example) -> Result<Outcome> {
+        let body = example_body(&example.code);
+        match example.kind {
+            Kind::Ignore => Ok(Outcome::Ignored),
+            Kind::NoRun | Kind::CompileFail => {
+                let output = self
+                    .repl
+                    .check(format!("{{\n{}\n}};", body), self.options.toolchain)?;
+                match (format_check_output(output), example.kind) {
+                    (Some(errors), Kind::NoRun) => Ok(Outcome::Failed(errors)),
+                    (None, Kind::CompileFail) => {
+                        Ok(Outcome::Failed(self.failure(
+                            "the example compiled but it was expected to fail".to_string(),
+                        )))
+                    }
+                    _ => Ok(Outcome::Passed),
+                }
+            }
+            Kind::Run | Kind::ShouldPanic => {
+                // the closure gives `?` and `return` the same meaning as in the example main
+                let input = format!(
+                    "{{ let result = (|| {{\n{}\n}})(); print!(\"{}\"); result }}",
+                    body, DOCTEST_MARK
+                );
+                let (status, out, stderr) = self.repl.eval(input, self.options.toolchain)?;
+                if !status.success() {
+                    return Ok(Outcome::Failed(format_err(&out)));
+                }
+                let (printed, result) = match out.split_once(DOCTEST_MARK) {
+                    Some((printed, result)) => (printed, Some(result.trim())),
+                    None => (out.as_str(), None),
+                };
+                let output = format!("{}{}", printed, stderr);
+                match (result, example.kind) {
+                    (None, Kind::Run) => Ok(Outcome::Failed(self.failure(output))),
+                    (Some(result), Kind::Run) if result.starts_with("Err(") => Ok(Outcome::Failed(
+                        self.failure(format!("{}the example returned {}", output, result)),
+                    )),
+                    (Some(_), Kind::ShouldPanic) => Ok(Outcome::Failed(
+                        self.failure(format!("{}the example didn't panic", output)),
+                    )),
+                    _ => Ok(Outcome::Passed),
+                }
+            }
+        }
+    }
+
+    fn failure(&self, details: String) -> PrintQueue {
+        let mut print_queue = PrintQueue::default();
+        print_queue.push(PrinterItem::String(
+            details.trim_end().to_string(),
+            self.options.err_color,
+        ));
+        print_queue.add_new_line(1);
+        print_queue
+    }
+}
+
+#[test]
+fn examples_test() {
+    let doc = r#"
+    /// Adds one
+    ///
+    /// ```
+    /// # let a = 1;
+    /// assert_eq!(add_one(a), 2);
+    /// ```
+    ///
+    /// ```text
+    /// not rust
+    /// ```
+    ///
+    /// ```rust,should_panic
+    /// #[derive(Debug)]
+    /// struct A;
+    /// panic!();
+    /// ```"#;
+    assert_eq!(
+        examples(doc),
+        vec![
+            Example {
+                code: "let a = 1;\nassert_eq!(add_one(a), 2);".to_string(),
+                kind: Kind::Run,
+                line: 4,
+            },
+            Example {
+                code: "#[derive(Debug)]\nstruct A;\npanic!();".to_string(),
+                kind: Kind::ShouldPanic,
+                line: 13,
+            },
+        ]
+    );
+    assert_eq!(examples("let a = 1;\n## b")[0].code, "let a = 1;\n# b");
+    assert_eq!(fence_kind("rust,no_run"), Some(Kind::NoRun));
+    assert_eq!(fence_kind("toml"), None);
+    assert!(unclosed_fence(":doctest ```rust\nlet a = 1;"));
+    assert!(!unclosed_fence(":doctest ```\nlet a = 1;\n```"));
+}
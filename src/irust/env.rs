@@ -0,0 +1,115 @@
+//! Environment variables of the processes started by IRust (evaluations, builds, shell commands..)
+use super::{IRust, Result};
+use crate::utils::{glob_match, split_args};
+use printer::printer::{PrintQueue, PrinterItem};
+
+/// Returns the `(name, value)` pairs of `VAR=value` arguments
+fn parse_assignments(vars: &str) -> Result<Vec<(String, String)>> {
+    let vars = split_args(vars.to_string());
+    if vars.is_empty() {
+        return Err("Expected VAR=value".into());
+    }
+    vars.into_iter()
+        .map(|var| {
+            let (name, value) = var
+                .split_once('=')
+                .ok_or_else(|| format!("Expected VAR=value, found `{}`", var))?;
+            check_name(name)?;
+            if value.contains('\0') {
+                return Err(format!("The value of `{}` contains a nul character", name).into());
+            }
+            Ok((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// `std::env::set_var` panics with these names
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains('=') || name.contains('\0') {
+        return Err(format!("Invalid environment variable name: `{}`", name).into());
+    }
+    Ok(())
+}
+
+/// Set environment variables for the processes started afterwards, exp: `RUST_LOG=debug A="b c"`
+pub fn set_env_vars(vars: &str) -> Result<PrintQueue> {
+    for (name, value) in parse_assignments(vars)? {
+        std::env::set_var(name, value);
+    }
+    Ok(PrintQueue::default())
+}
+
+/// Remove environment variables for the processes started afterwards
+pub fn unset_env_vars(names: &str) -> Result<PrintQueue> {
+    let names = split_args(names.to_string());
+    if names.is_empty() {
+        return Err("Expected the name of a variable".into());
+    }
+    for name in &names {
+        check_name(name)?;
+    }
+    for name in names {
+        std::env::remove_var(name);
+    }
+    Ok(PrintQueue::default())
+}
+
+impl IRust {
+    /// `:env [pattern]` lists the environment variables, exp: `:env RUST*`
+    pub fn env(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        let pattern = buffer[":env".len()..].trim();
+
+        let mut vars: Vec<(String, String)> = std::env::vars_os()
+            .map(|(name, value)| {
+                (
+                    name.to_string_lossy().into_owned(),
+                    value.to_string_lossy().into_owned(),
+                )
+            })
+            .filter(|(name, _)| pattern.is_empty() || glob_match(pattern, name))
+            .collect();
+        if vars.is_empty() {
+            return Err(format!("No environment variable matches `{}`", pattern).into());
+        }
+        vars.sort();
+
+        let mut print_queue = PrintQueue::default();
+        for (name, value) in vars {
+            print_queue.push(PrinterItem::String(name, self.options.irust_color));
+            print_queue.push(PrinterItem::String(
+                format!("={}", value),
+                self.options.ok_color,
+            ));
+            print_queue.add_new_line(1);
+        }
+        Ok(print_queue)
+    }
+
+    /// `:setenv VAR=value..`
+    pub fn setenv(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        set_env_vars(&buffer[":setenv".len()..])
+    }
+
+    /// `:unsetenv VAR..`
+    pub fn unsetenv(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        unset_env_vars(&buffer[":unsetenv".len()..])
+    }
+}
+
+#[test]
+fn parse_assignments_test() {
+    assert_eq!(
+        parse_assignments(r#" RUST_LOG=debug RUSTFLAGS="-C target-cpu=native" EMPTY="#).unwrap(),
+        vec![
+            ("RUST_LOG".to_string(), "debug".to_string()),
+            ("RUSTFLAGS".to_string(), "-C target-cpu=native".to_string()),
+            ("EMPTY".to_string(), String::new()),
+        ]
+    );
+    assert!(parse_assignments("RUST_LOG").is_err());
+    assert!(parse_assignments("=value").is_err());
+    assert!(parse_assignments("").is_err());
+}
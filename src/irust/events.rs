@@ -12,9 +12,27 @@ mod history_events;
 
 impl IRust {
     pub fn handle_character(&mut self, c: char) -> Result<()> {
-        self.buffer.insert(c);
+        let steps_over = self.options.auto_close_brackets
+            && is_closing_pair(c)
+            && self.buffer.current_char() == Some(&c);
+        if steps_over {
+            // step over the auto inserted closing pair instead of duplicating it
+            self.buffer.move_forward();
+        } else {
+            if c == '}' {
+                self.dedent_before_closing_brace();
+            }
+            self.buffer.insert(c);
+            if let Some(closing) = closing_pair(c).filter(|_| self.options.auto_close_brackets) {
+                self.buffer.insert(closing);
+                self.buffer.move_backward();
+            }
+        }
+        self.schedule_input_check();
+        self.update_signature_help()?;
+        self.update_completion_panel()?;
         self.print_input()?;
-        self.printer.cursor.move_right_unbounded();
+        self.printer.cursor.goto_buffer_pos(&self.buffer);
         self.history.unlock();
         // Ignore RacerDisabled error
         let _ = self.racer.as_mut().map(Racer::unlock_racer_update);
@@ -27,21 +45,44 @@ impl IRust {
 
         let buffer = self.buffer.to_string();
 
-        if !force_eval && !self.input_is_cmd_or_shell(&buffer) && self.incomplete_input(&buffer) {
-            self.buffer.insert('\n');
-            self.print_input()?;
-            self.printer.cursor.move_right();
+        if self.expand_history(&buffer)? {
             return Ok(());
         }
 
+        if !force_eval && !self.input_is_cmd_or_shell(&buffer) && self.incomplete_input(&buffer) {
+            return self.insert_newline();
+        }
+        // the example of `:doctest` ends with its closing fence
+        if !force_eval && super::doctest::unclosed_fence(&buffer) {
+            return self.insert_newline();
+        }
+
+        // don't leave the history ghost text, the completions and the error underlines on screen
+        self.remove_history_suggestion_and_reprint()?;
+        self.hide_completion_panel()?;
+        self.hide_signature_help()?;
+        self.clear_diagnostics()?;
+
         self.printer.cursor.hide();
 
         // create a new line
         self.printer.write_newline(&self.buffer);
 
+        // number every non empty input, so it can be recalled later with `:!N`
+        let is_empty_input = buffer.trim().is_empty();
+        if !is_empty_input {
+            self.history
+                .record_session_input(self.global_variables.operation_number, buffer.clone());
+        }
+
         // add commands to history
         if self.should_push_to_history(&buffer) {
-            self.history.push(buffer);
+            self.history.push(buffer.clone());
+        }
+
+        // the evaluated code can read the state of the repl from `irust_meta`
+        if !is_empty_input {
+            self.update_meta()?;
         }
 
         // parse and handle errors
@@ -58,13 +99,25 @@ impl IRust {
         // ensure buffer is cleaned
         self.buffer.clear();
 
+        if !is_empty_input {
+            self.transcript.record(
+                self.global_variables.operation_number,
+                buffer,
+                output.clone(),
+            );
+        }
+
         // print output
         if !output.is_empty() {
             // clear racer suggestions is present
-            self.printer.writer.raw.clear(ClearType::FromCursorDown)?;
+            self.printer.clear_from_cursor_down()?;
             self.printer.print_output(output)?;
+        }
+
+        if !is_empty_input {
             self.global_variables.operation_number += 1;
             self.update_input_prompt();
+            self.update_status_line()?;
         }
 
         // print a new input prompt
@@ -75,21 +128,24 @@ impl IRust {
     }
 
     pub fn handle_alt_enter(&mut self) -> Result<()> {
-        self.buffer.insert('\n');
-        self.print_input()?;
-        self.printer.cursor.move_right();
-        Ok(())
+        self.insert_newline()
     }
 
     pub fn handle_tab(&mut self) -> Result<()> {
+        if self.completion_panel_is_shown() {
+            return self.accept_completion();
+        }
+
+        if self.expand_snippet()? {
+            return Ok(());
+        }
+
         if self.buffer.is_at_string_line_start() {
             const TAB: &str = "   \t";
 
             self.buffer.insert_str(TAB);
             self.print_input()?;
-            for _ in 0..4 {
-                self.printer.cursor.move_right_unbounded();
-            }
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
             return Ok(());
         }
 
@@ -123,37 +179,58 @@ impl IRust {
     }
 
     pub fn handle_right(&mut self) -> Result<()> {
+        self.hide_completion_panel()?;
         if let Some(suggestion) = self
             .racer
             .as_mut()
-            .map(|r| r.active_suggestion.take())
-            .flatten()
+            .and_then(Racer::accept_active_suggestion)
         {
             for c in suggestion.chars() {
                 self.handle_character(c)?;
             }
+        } else if self.history_suggestion.is_some() {
+            self.accept_history_suggestion()?;
         } else if !self.buffer.is_at_end() {
-            self.printer.cursor.move_right();
-            self.buffer.move_forward();
+            let near_bracket = self.is_near_bracket();
+            self.buffer.move_forward_cluster();
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
+            self.refresh_matching_bracket(near_bracket)?;
         }
         Ok(())
     }
 
     pub fn handle_left(&mut self) -> Result<()> {
+        self.hide_completion_panel()?;
         self.remove_racer_sugesstion_and_reprint()?;
+        self.remove_history_suggestion_and_reprint()?;
 
         if !self.buffer.is_at_start() && !self.buffer.is_empty() {
-            self.printer.cursor.move_left();
-            self.buffer.move_backward();
+            let near_bracket = self.is_near_bracket();
+            self.buffer.move_backward_cluster();
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
+            self.refresh_matching_bracket(near_bracket)?;
         }
         Ok(())
     }
 
     pub fn handle_backspace(&mut self) -> Result<()> {
         if !self.buffer.is_at_start() {
-            self.buffer.move_backward();
-            self.printer.cursor.move_left();
-            self.buffer.remove_current_char();
+            // remove an empty auto inserted pair at once, exp: (|)
+            if self.options.auto_close_brackets {
+                if let (Some(previous), Some(current)) =
+                    (self.buffer.previous_char(), self.buffer.current_char())
+                {
+                    if closing_pair(*previous) == Some(*current) {
+                        self.buffer.remove_current_char();
+                    }
+                }
+            }
+            self.buffer.move_backward_cluster();
+            self.buffer.remove_current_cluster();
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
+            self.schedule_input_check();
+            self.update_signature_help()?;
+            self.update_completion_panel()?;
             self.print_input()?;
             // Ignore RacerDisabled error
             self.history.unlock();
@@ -164,7 +241,10 @@ impl IRust {
 
     pub fn handle_del(&mut self) -> Result<()> {
         if !self.buffer.is_empty() {
-            self.buffer.remove_current_char();
+            self.buffer.remove_current_cluster();
+            self.schedule_input_check();
+            self.update_signature_help()?;
+            self.update_completion_panel()?;
             self.print_input()?;
             // Ignore RacerDisabled error
             self.history.unlock();
@@ -173,13 +253,32 @@ impl IRust {
         Ok(())
     }
 
+    /// Clear the input, pressing it twice on an empty input shows how to exit
+    ///
+    /// Running evaluations, builds and shell commands are interrupted by ctrl-c while they wait
     pub fn handle_ctrl_c(&mut self) -> Result<()> {
+        if !self.buffer.is_empty() || !self.ctrl_c_on_empty_input {
+            self.ctrl_c_on_empty_input = self.buffer.is_empty();
+            return self.reset_input();
+        }
+
+        self.ctrl_c_on_empty_input = false;
+        self.printer.write_newline(&self.buffer);
+        self.printer
+            .write("(To exit, press ctrl-d on an empty input)", Color::Grey)?;
+        self.printer.write_newline(&self.buffer);
+        self.printer.print_prompt_if_set()?;
+        Ok(())
+    }
+
+    /// Clear the input and redraw the prompt
+    pub fn reset_input(&mut self) -> Result<()> {
         self.buffer.clear();
         self.history.unlock();
         let _ = self.racer.as_mut().map(Racer::unlock_racer_update);
         self.printer.cursor.goto_start();
         self.printer.print_prompt_if_set()?;
-        self.printer.writer.raw.clear(ClearType::FromCursorDown)?;
+        self.printer.clear_from_cursor_down()?;
         self.print_input()?;
         Ok(())
     }
@@ -224,11 +323,25 @@ impl IRust {
     }
 
     pub fn exit(&mut self) -> Result<()> {
+        self.save_state()?;
+        if self.printer.is_in_alternate_screen() {
+            // removed before leaving so the previous screen isn't touched
+            self.printer.set_status_line(None)?;
+            self.printer.set_top_pane(None)?;
+            self.printer.leave_alternate_screen()?;
+            self.dump_transcript()?;
+        } else {
+            self.printer.write_newline(&self.buffer);
+        }
+        self.printer.cursor.show();
+        Ok(())
+    }
+
+    /// Save the history, the options and the theme
+    pub fn save_state(&mut self) -> Result<()> {
         self.history.save()?;
         self.options.save()?;
         self.theme.save()?;
-        self.printer.write_newline(&self.buffer);
-        self.printer.cursor.show();
         Ok(())
     }
 
@@ -239,9 +352,18 @@ impl IRust {
                 sys::signal::{kill, Signal},
                 unistd::Pid,
             };
-            self.printer.writer.raw.clear(ClearType::All)?;
+            let alternate_screen = self.printer.is_in_alternate_screen();
+            if alternate_screen {
+                self.printer.leave_alternate_screen()?;
+                std::io::Write::flush(&mut self.printer.writer.raw)?;
+            } else {
+                self.printer.writer.raw.clear(ClearType::All)?;
+            }
             kill(Pid::this(), Some(Signal::SIGTSTP))
                 .map_err(|e| format!("failed to sigstop irust. {}", e))?;
+            if alternate_screen {
+                self.printer.enter_alternate_screen()?;
+            }
 
             // display empty prompt after SIGCONT
             self.handle_ctrl_l()?;
@@ -266,95 +388,83 @@ impl IRust {
     }
 
     pub fn handle_end_key(&mut self) -> Result<()> {
-        while !self.buffer.is_empty() && !self.printer.cursor.is_at_line_end() {
-            self.buffer.move_forward();
-            self.printer.cursor.move_right();
+        let near_bracket = self.is_near_bracket();
+        while !self.buffer.is_at_end() && !self.printer.cursor.is_at_line_end() {
+            let row = self.printer.cursor.current_pos().1;
+            self.buffer.move_forward_cluster();
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
+            // a wide char filled the row
+            if self.printer.cursor.current_pos().1 != row {
+                self.buffer.move_backward_cluster();
+                self.printer.cursor.goto_buffer_pos(&self.buffer);
+                break;
+            }
         }
+        self.refresh_matching_bracket(near_bracket)?;
         // check for racer suggestion at the end
         if let Some(suggestion) = self
             .racer
             .as_mut()
-            .map(|r| r.active_suggestion.take())
-            .flatten()
+            .and_then(Racer::accept_active_suggestion)
         {
             for c in suggestion.chars() {
                 self.handle_character(c)?;
             }
+        } else if self.history_suggestion.is_some() {
+            self.accept_history_suggestion()?;
         }
         Ok(())
     }
 
     pub fn handle_ctrl_left(&mut self) -> Result<()> {
         self.handle_left()?;
-
-        if let Some(current_char) = self.buffer.current_char() {
-            match *current_char {
-                ' ' => {
-                    while self.buffer.previous_char() == Some(&' ') {
-                        self.printer.cursor.move_left();
-                        self.buffer.move_backward()
-                    }
-                }
-                c if c.is_alphanumeric() => {
-                    while let Some(previous_char) = self.buffer.previous_char() {
-                        if previous_char.is_alphanumeric() {
-                            self.printer.cursor.move_left();
-                            self.buffer.move_backward()
-                        } else {
-                            break;
-                        }
-                    }
-                }
-
-                _ => {
-                    while let Some(previous_char) = self.buffer.previous_char() {
-                        if !previous_char.is_alphanumeric() && *previous_char != ' ' {
-                            self.printer.cursor.move_left();
-                            self.buffer.move_backward()
-                        } else {
-                            break;
-                        }
-                    }
-                }
-            }
-        }
+        self.buffer.move_to_word_start();
+        self.printer.cursor.goto_buffer_pos(&self.buffer);
         Ok(())
     }
 
     pub fn handle_ctrl_right(&mut self) -> Result<()> {
         self.handle_right()?;
+        self.buffer.move_to_word_end();
+        self.printer.cursor.goto_buffer_pos(&self.buffer);
+        Ok(())
+    }
 
-        if let Some(current_char) = self.buffer.current_char() {
-            match *current_char {
-                ' ' => {
-                    while self.buffer.next_char() == Some(&' ') {
-                        self.printer.cursor.move_right();
-                        self.buffer.move_forward();
-                    }
-                    self.printer.cursor.move_right();
-                    self.buffer.move_forward();
-                }
-                c if c.is_alphanumeric() => {
-                    while let Some(character) = self.buffer.current_char() {
-                        if !character.is_alphanumeric() {
-                            break;
-                        }
-                        self.printer.cursor.move_right();
-                        self.buffer.move_forward();
-                    }
-                }
+    /// Delete the word before the cursor, the deleted part is what ctrl-left jumps over
+    pub fn handle_alt_backspace(&mut self) -> Result<()> {
+        if self.buffer.is_at_start() {
+            return Ok(());
+        }
+        let end = self.buffer.buffer_pos;
+        self.buffer.move_backward_cluster();
+        self.buffer.move_to_word_start();
+        self.buffer.remove_range(self.buffer.buffer_pos, end);
+        self.after_word_removal()
+    }
 
-                _ => {
-                    while let Some(character) = self.buffer.current_char() {
-                        if character.is_alphanumeric() || *character == ' ' {
-                            break;
-                        }
-                        self.printer.cursor.move_right();
-                        self.buffer.move_forward();
-                    }
-                }
-            }
+    /// Delete the word after the cursor, the deleted part is what ctrl-right jumps over
+    pub fn handle_alt_d(&mut self) -> Result<()> {
+        if self.buffer.is_at_end() {
+            return Ok(());
         }
+        let start = self.buffer.buffer_pos;
+        self.buffer.move_forward_cluster();
+        self.buffer.move_to_word_end();
+        self.buffer.remove_range(start, self.buffer.buffer_pos);
+        self.after_word_removal()
+    }
+
+    fn after_word_removal(&mut self) -> Result<()> {
+        self.hide_completion_panel()?;
+        self.remove_racer_sugesstion_and_reprint()?;
+        self.remove_history_suggestion_and_reprint()?;
+        self.printer.cursor.goto_buffer_pos(&self.buffer);
+        self.schedule_input_check();
+        self.update_signature_help()?;
+        self.print_input()?;
+        // Ignore RacerDisabled error
+        self.history.unlock();
+        let _ = self.racer.as_mut().map(Racer::unlock_racer_update);
         Ok(())
     }
 
@@ -367,8 +477,7 @@ impl IRust {
         if self
             .racer
             .as_mut()
-            .map(|r| r.active_suggestion.take())
-            .flatten()
+            .and_then(|r| r.active_suggestion.take())
             .is_some()
         {
             // and reprint
@@ -379,14 +488,48 @@ impl IRust {
 
     // helper functions
 
+    fn accept_history_suggestion(&mut self) -> Result<()> {
+        if let Some(suggestion) = self.history_suggestion.take() {
+            self.buffer.insert_str(&suggestion);
+            self.print_input()?;
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
+            self.history.unlock();
+        }
+        Ok(())
+    }
+
+    fn is_near_bracket(&self) -> bool {
+        let is_bracket = |c: Option<&char>| matches!(c, Some('(' | ')' | '[' | ']' | '{' | '}'));
+        is_bracket(self.buffer.current_char()) || is_bracket(self.buffer.previous_char())
+    }
+
+    /// Reprint the input if the matching bracket highlight needs to change after a cursor movement
+    fn refresh_matching_bracket(&mut self, was_near_bracket: bool) -> Result<()> {
+        if self.options.highlight_matching_bracket && (was_near_bracket || self.is_near_bracket()) {
+            self.print_input()?;
+        }
+        Ok(())
+    }
+
     fn incomplete_input(&self, buffer: &str) -> bool {
-        StringTools::unmatched_brackets(&buffer)
-            || buffer
-                .trim_end()
-                .ends_with(|c| c == ':' || c == '.' || c == '=')
+        StringTools::unmatched_brackets(buffer) || buffer.trim_end().ends_with([':', '.', '='])
     }
 
     fn input_is_cmd_or_shell(&self, buffer: &str) -> bool {
         buffer.starts_with(':') || buffer.starts_with("::")
     }
 }
+
+fn closing_pair(c: char) -> Option<char> {
+    match c {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' => Some('"'),
+        _ => None,
+    }
+}
+
+fn is_closing_pair(c: char) -> bool {
+    matches!(c, ')' | ']' | '}' | '"')
+}
@@ -13,6 +13,9 @@ enum Dir {
 
 impl super::IRust {
     pub fn handle_up(&mut self) -> Result<()> {
+        if self.completion_panel_is_shown() {
+            return self.cycle_completion_panel(false);
+        }
         if self.printer.cursor.is_at_first_input_line() {
             let buffer = self.buffer.take();
             self.handle_history(Dir::Up, buffer)?;
@@ -21,13 +24,18 @@ impl super::IRust {
             self.remove_racer_sugesstion_and_reprint()?;
             self.printer.cursor.move_up_bounded(1);
             // set buffer cursor
-            let buffer_pos = self.printer.cursor.cursor_pos_to_buffer_pos();
+            let buffer_pos = self.printer.cursor.cursor_pos_to_buffer_pos(&self.buffer);
             self.buffer.set_buffer_pos(buffer_pos);
+            // the cursor might be in the middle of a wide char
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
         }
         Ok(())
     }
 
     pub fn handle_down(&mut self) -> Result<()> {
+        if self.completion_panel_is_shown() {
+            return self.cycle_completion_panel(true);
+        }
         if self.buffer.is_empty() {
             return Ok(());
         }
@@ -39,8 +47,10 @@ impl super::IRust {
             self.remove_racer_sugesstion_and_reprint()?;
             self.printer.cursor.move_down_bounded(1, &self.buffer);
             // set buffer cursor
-            let buffer_pos = self.printer.cursor.cursor_pos_to_buffer_pos();
+            let buffer_pos = self.printer.cursor.cursor_pos_to_buffer_pos(&self.buffer);
             self.buffer.set_buffer_pos(buffer_pos);
+            // the cursor might be in the middle of a wide char
+            self.printer.cursor.goto_buffer_pos(&self.buffer);
         }
         Ok(())
     }
@@ -64,7 +74,45 @@ impl super::IRust {
         Ok(())
     }
 
+    /// Replace `!!`, `:!N` and `:edit-history [N]` with the corresponding previous input so it can be edited
+    ///
+    /// `!N` is left alone since it is a valid rust expression
+    ///
+    /// Returns true if the buffer was expanded
+    pub fn expand_history(&mut self, buffer: &str) -> Result<bool> {
+        let buffer = buffer.trim();
+        let operation_number = if buffer == "!!" || buffer == ":edit-history" {
+            None
+        } else if let Some(n) = buffer
+            .strip_prefix(":!")
+            .or_else(|| buffer.strip_prefix(":edit-history "))
+            .map(str::trim)
+            .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        {
+            Some(n.parse()?)
+        } else {
+            return Ok(false);
+        };
+
+        let input = match operation_number {
+            Some(n) => self.history.session_input(n),
+            None => self.history.last_session_input(),
+        };
+        // leave the buffer as it is if there is nothing to recall
+        if let Some(input) = input.cloned() {
+            self.buffer = input.into();
+            self.print_input()?;
+            let last_input_pos = self.printer.cursor.input_last_pos(&self.buffer);
+            self.buffer.goto_end();
+            self.printer.cursor.goto(last_input_pos.0, last_input_pos.1);
+        }
+        Ok(true)
+    }
+
     pub fn handle_ctrl_r(&mut self) -> Result<()> {
+        // pick up entries from other IRust instances
+        self.history.sync();
+
         // make space for the search bar
         if self.printer.cursor.is_at_last_terminal_row() {
             self.printer.scroll_up(1);
@@ -74,7 +122,7 @@ impl super::IRust {
         const SEARCH_TITLE: &str = "search history: ";
         const TITLE_WIDTH: usize = 16; // SEARCH_TITLE.chars().count()
         self.printer.write_at_no_cursor(
-            &SEARCH_TITLE,
+            SEARCH_TITLE,
             Color::Red,
             0,
             self.printer.cursor.height() - 1,
@@ -170,7 +218,7 @@ impl super::IRust {
                         needle.clear();
                         self.printer.clear_last_line()?;
                         self.printer.write_at_no_cursor(
-                            &SEARCH_TITLE,
+                            SEARCH_TITLE,
                             Color::Red,
                             0,
                             self.printer.cursor.height() - 1,
@@ -186,10 +234,8 @@ impl super::IRust {
                     Event::Key(KeyEvent {
                         code: KeyCode::Char('d'),
                         modifiers: KeyModifiers::CONTROL,
-                    }) => {
-                        if needle.is_empty() {
-                            break;
-                        }
+                    }) if needle.is_empty() => {
+                        break;
                     }
                     _ => (),
                 }
@@ -197,7 +243,7 @@ impl super::IRust {
         }
         self.printer.clear_last_line()?;
         self.remove_racer_sugesstion_and_reprint()?;
-        let buffer_pos = self.printer.cursor.cursor_pos_to_buffer_pos();
+        let buffer_pos = self.printer.cursor.cursor_pos_to_buffer_pos(&self.buffer);
         self.buffer.set_buffer_pos(buffer_pos);
         Ok(())
     }
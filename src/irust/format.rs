@@ -38,6 +38,100 @@ pub fn format_eval_output(
     Some(eval_output)
 }
 
+/// Larger outputs are shown in full, the diff is quadratic
+const MAX_DIFF_SIZE: usize = 250_000;
+/// Unchanged lines shown around the changed ones
+const DIFF_CONTEXT: usize = 2;
+
+#[derive(Debug, PartialEq)]
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Line diff based on the longest common subsequence
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Option<Vec<DiffLine<'a>>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if old.len() * new.len() > MAX_DIFF_SIZE {
+        return None;
+    }
+
+    // lcs[i][j] is the common subsequence length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    Some(diff)
+}
+
+/// The output of a re-evaluated input as a diff against its previous output
+///
+/// Returns `None` if the output is too large to be diffed
+pub fn format_eval_diff(previous: &str, output: &str, prompt: String) -> Option<PrintQueue> {
+    let mut eval_output = PrintQueue::default();
+    eval_output.push(PrinterItem::String(prompt, Color::Red));
+    if previous == output {
+        eval_output.push(PrinterItem::Str(
+            "(same output as the last run)",
+            Color::DarkGrey,
+        ));
+        eval_output.add_new_line(1);
+        return Some(eval_output);
+    }
+
+    let diff = diff_lines(previous, output)?;
+    let is_near_change = |idx: usize| {
+        diff[idx.saturating_sub(DIFF_CONTEXT)..(idx + DIFF_CONTEXT + 1).min(diff.len())]
+            .iter()
+            .any(|line| !matches!(line, DiffLine::Same(_)))
+    };
+    eval_output.add_new_line(1);
+    let mut skipped = false;
+    for (idx, line) in diff.iter().enumerate() {
+        let (line, color) = match line {
+            DiffLine::Same(_) if !is_near_change(idx) => {
+                if !skipped {
+                    eval_output.push(PrinterItem::Str("  ...", Color::DarkGrey));
+                    eval_output.add_new_line(1);
+                }
+                skipped = true;
+                continue;
+            }
+            DiffLine::Same(line) => (format!("  {}", line), Color::DarkGrey),
+            DiffLine::Removed(line) => (format!("- {}", line), Color::Red),
+            DiffLine::Added(line) => (format!("+ {}", line), Color::Green),
+        };
+        skipped = false;
+        eval_output.push(PrinterItem::String(line, color));
+        eval_output.add_new_line(1);
+    }
+    Some(eval_output)
+}
+
 fn check_is_err(s: &str) -> bool {
     !s.contains("dev [unoptimized + debuginfo]")
 }
@@ -49,3 +143,13 @@ pub fn format_check_output(output: String) -> Option<PrintQueue> {
         None
     }
 }
+
+#[test]
+fn diff_lines_test() {
+    use DiffLine::*;
+    assert_eq!(
+        diff_lines("a\nb\nc\n", "a\nx\nc\nd\n").unwrap(),
+        [Same("a"), Removed("b"), Added("x"), Same("c"), Added("d")]
+    );
+    assert_eq!(diff_lines("", "a").unwrap(), [Added("a")]);
+}
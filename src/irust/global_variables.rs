@@ -1,5 +1,7 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
+/// Passed by reference to the scripts, its layout must stay the same as `script_template/types.rs`
 pub struct GlobalVariables {
     current_working_dir: PathBuf,
     previous_working_dir: PathBuf,
@@ -9,6 +11,14 @@ pub struct GlobalVariables {
     pub operation_number: usize,
 }
 
+/// The outcome of the last evaluation, kept out of `GlobalVariables` so the scripts ABI doesn't change
+#[derive(Default)]
+pub struct LastEval {
+    pub duration: Option<Duration>,
+    /// None if nothing was evaluated yet
+    pub success: Option<bool>,
+}
+
 impl GlobalVariables {
     pub fn new() -> Self {
         let cwd = std::env::current_dir().expect("Error getting current working directory");
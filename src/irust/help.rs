@@ -6,14 +6,92 @@ use printer::{
     printer::{PrintQueue, PrinterItem},
 };
 
+fn readme() -> &'static str {
+    #[cfg(unix)]
+    let readme = include_str!("../../README.md");
+    #[cfg(windows)]
+    let readme = include_str!("..\\..\\README.md");
+    readme
+}
+
+/// A `## title` section of the README
+struct Section<'a> {
+    title: &'a str,
+    body: Vec<&'a str>,
+}
+
+fn sections(readme: &str) -> Vec<Section<'_>> {
+    let mut sections: Vec<Section> = vec![];
+    for line in readme.lines() {
+        if let Some(title) = line.strip_prefix("## ") {
+            sections.push(Section {
+                title: title.trim(),
+                body: vec![],
+            });
+        } else if let Some(section) = sections.last_mut() {
+            section.body.push(line);
+        }
+    }
+    sections
+}
+
+/// The paragraphs that contain `term`, under their section title
+fn matching_entries(sections: &[Section], term: &str) -> String {
+    let term = term.to_lowercase();
+    let mut entries = String::new();
+    for section in sections {
+        let body = section.body.join("\n");
+        let paragraphs: Vec<&str> = body
+            .split("\n\n")
+            .filter(|paragraph| paragraph.to_lowercase().contains(&term))
+            .map(str::trim)
+            .collect();
+        if !paragraphs.is_empty() {
+            entries.push_str(&format!(
+                "## {}\n\n{}\n\n",
+                section.title,
+                paragraphs.join("\n\n")
+            ));
+        }
+    }
+    entries
+}
+
 impl IRust {
+    /// `:help` browses the manual, `:help <term>` prints the entries that mention the term
     pub fn help(&mut self) -> Result<PrintQueue> {
-        #[cfg(unix)]
-        let readme = include_str!("../../README.md");
-        #[cfg(windows)]
-        let readme = include_str!("..\\..\\README.md");
+        let buffer = self.buffer.to_string();
+        let term = buffer.trim_start_matches(":help").trim();
+        let sections = sections(readme());
+
+        if !term.is_empty() {
+            let entries = matching_entries(&sections, term);
+            if entries.is_empty() {
+                return Err(format!("No help entry mentions `{}`", term).into());
+            }
+            return Ok(parse_markdown(&entries.into(), &self.theme));
+        }
 
-        Ok(parse_markdown(&readme.into(), &self.theme))
+        // a numbered index of the sections, the digit keys jump to them
+        let mut index = String::from("# IRust manual\n\n");
+        for (idx, section) in sections.iter().enumerate().take(9) {
+            index.push_str(&format!("**{}** {}\n", idx + 1, section.title));
+        }
+        let mut manual = parse_markdown(&index.into(), &self.theme);
+        let mut marks = vec![];
+        for section in &sections {
+            // code blocks can span a different number of lines once highlighted
+            let line_count = manual
+                .clone()
+                .filter(|item| matches!(item, PrinterItem::NewLine))
+                .count();
+            marks.push(line_count + 1);
+            let section = format!("\n## {}\n{}", section.title, section.body.join("\n"));
+            manual.append(&mut parse_markdown(&section.into(), &self.theme));
+        }
+
+        self.page("IRust help", manual, marks, 0)?;
+        Ok(PrintQueue::default())
     }
 }
 
@@ -113,3 +191,16 @@ fn parse_markdown(buffer: &Buffer, theme: &Theme) -> PrintQueue {
     })();
     queue
 }
+
+#[test]
+fn help_entries_test() {
+    let readme = "# IRust\n## Keybindings\n\n**ctrl-l** clear screen\n\n**ctrl-r** search history\n## FAQ\n\nsearch engines\n";
+    let sections = sections(readme);
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[1].title, "FAQ");
+    assert_eq!(
+        matching_entries(&sections, "Search"),
+        "## Keybindings\n\n**ctrl-r** search history\n\n## FAQ\n\nsearch engines\n\n"
+    );
+    assert_eq!(matching_entries(&sections, "ctrl-z"), "");
+}
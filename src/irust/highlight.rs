@@ -1,11 +1,72 @@
+use super::repl::ItemKind;
 use crossterm::style::Color;
 use printer::buffer::Buffer;
 use printer::printer::{PrintQueue, PrinterItem};
+use serde::{Deserialize, Serialize};
 use theme::Theme;
+mod lexer;
+mod syntect;
 pub mod theme;
 
 const PAREN_COLORS: [&str; 4] = ["green", "red", "yellow", "blue"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum HighlightEngine {
+    /// The original tokenizer
+    #[default]
+    Classic,
+    /// Handles raw strings, lifetimes, attributes, generics and re-lexes only edited lines
+    Lexer,
+    /// Uses syntect and its Rust grammar, re-parses only edited lines
+    Syntect,
+}
+
 pub fn highlight(buffer: &Buffer, theme: &Theme) -> PrintQueue {
+    match theme.engine {
+        HighlightEngine::Classic => highlight_classic(buffer, theme),
+        HighlightEngine::Lexer => highlight_tokens(lexer::lex(&buffer.buffer), theme),
+        HighlightEngine::Syntect => match syntect::lex(&buffer.buffer) {
+            Some(lines) => highlight_tokens(lines, theme),
+            None => highlight_tokens(lexer::lex(&buffer.buffer), theme),
+        },
+    }
+}
+
+/// Color the lines of tokens of the `Lexer` and `Syntect` engines
+fn highlight_tokens(lines: Vec<Vec<lexer::Token>>, theme: &Theme) -> PrintQueue {
+    let mut print_queue = PrintQueue::default();
+    let color = |color: &str| theme::theme_color_to_term_color(color).unwrap_or(Color::White);
+
+    for (line_idx, line) in lines.into_iter().enumerate() {
+        if line_idx != 0 {
+            print_queue.push(PrinterItem::NewLine);
+        }
+        for (kind, s) in line {
+            use lexer::Kind::*;
+            let token_color = match kind {
+                Keyword => color(&theme.keyword),
+                Keyword2 => color(&theme.keyword2),
+                Function => color(&theme.function),
+                Type => color(&theme.r#type),
+                Number => color(&theme.number),
+                Symbol => color(&theme.symbol),
+                // attributes are colored like macros
+                Macro | Attribute => color(&theme.r#macro),
+                StringLiteral => color(&theme.string_literal),
+                Character => color(&theme.character),
+                LifeTime => color(&theme.lifetime),
+                Comment => color(&theme.comment),
+                Const => color(&theme.r#const),
+                Paren(idx) => color(PAREN_COLORS[idx.unsigned_abs() % 4]),
+                X => color(&theme.x),
+            };
+            print_queue.push(PrinterItem::String(s, token_color));
+        }
+    }
+    print_queue
+}
+
+fn highlight_classic(buffer: &Buffer, theme: &Theme) -> PrintQueue {
     let mut print_queue = PrintQueue::default();
 
     macro_rules! push_to_printer {
@@ -36,10 +97,10 @@ pub fn highlight(buffer: &Buffer, theme: &Theme) -> PrintQueue {
             X(s) => push_to_printer!(String, s, &theme.x[..]),
             Xc(c) => push_to_printer!(Char, c, &theme.x[..]),
             Token::LeftParen(s, idx) => {
-                push_to_printer!(Char, s, PAREN_COLORS[idx.abs() as usize % 4])
+                push_to_printer!(Char, s, PAREN_COLORS[idx.unsigned_abs() % 4])
             }
             Token::RightParen(s, idx) => {
-                push_to_printer!(Char, s, PAREN_COLORS[idx.abs() as usize % 4])
+                push_to_printer!(Char, s, PAREN_COLORS[idx.unsigned_abs() % 4])
             }
             Token::NewLine => {
                 print_queue.push(PrinterItem::NewLine);
@@ -49,6 +110,140 @@ pub fn highlight(buffer: &Buffer, theme: &Theme) -> PrintQueue {
     print_queue
 }
 
+/// Recolor identifiers defined earlier in the session, `kind_of` returns the item kind of a known identifier
+pub fn highlight_session_items(
+    queue: PrintQueue,
+    theme: &Theme,
+    kind_of: impl Fn(&str) -> Option<ItemKind>,
+) -> PrintQueue {
+    let color = |color: &str| theme::theme_color_to_term_color(color).unwrap_or(Color::White);
+    // only plain identifiers are recolored, not keywords, strings or comments
+    let identifier_colors = [
+        color(&theme.x),
+        color(&theme.function),
+        color(&theme.r#type),
+        color(&theme.r#const),
+    ];
+    let session_color = |s: &str| -> Option<Color> {
+        use ItemKind::*;
+        if !s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            || !s.chars().all(|c| c.is_alphanumeric() || c == '_')
+        {
+            return None;
+        }
+        Some(match kind_of(s)? {
+            Function | Macro => color(&theme.session_function),
+            Struct | Enum | Union | Trait | TypeAlias | Module => color(&theme.session_type),
+            Const | Static | Let => color(&theme.session_variable),
+        })
+    };
+
+    let mut print_queue = PrintQueue::default();
+    for item in queue {
+        match item {
+            PrinterItem::String(s, c) if identifier_colors.contains(&c) => {
+                let c = session_color(&s).unwrap_or(c);
+                print_queue.push(PrinterItem::String(s, c));
+            }
+            item => print_queue.push(item),
+        }
+    }
+    print_queue
+}
+
+/// Recolor the bracket matching the one under the cursor (or right before it)
+pub fn highlight_matching_bracket(queue: PrintQueue, buffer: &Buffer, color: Color) -> PrintQueue {
+    let target = match find_matching_bracket(buffer) {
+        Some(target) => target,
+        None => return queue,
+    };
+
+    let mut print_queue = PrintQueue::default();
+    let mut idx = 0;
+    let push_str = |print_queue: &mut PrintQueue, idx: &mut usize, s: String, c: Color| {
+        let len = s.chars().count();
+        if (*idx..*idx + len).contains(&target) {
+            let split = target - *idx;
+            let before: String = s.chars().take(split).collect();
+            let after: String = s.chars().skip(split + 1).collect();
+            if !before.is_empty() {
+                print_queue.push(PrinterItem::String(before, c));
+            }
+            // safe unwrap: target is inside this string
+            print_queue.push(PrinterItem::Char(s.chars().nth(split).unwrap(), color));
+            if !after.is_empty() {
+                print_queue.push(PrinterItem::String(after, c));
+            }
+        } else {
+            print_queue.push(PrinterItem::String(s, c));
+        }
+        *idx += len;
+    };
+
+    for item in queue {
+        match item {
+            PrinterItem::Char(c, item_color) => {
+                let item_color = if idx == target { color } else { item_color };
+                print_queue.push(PrinterItem::Char(c, item_color));
+                idx += 1;
+            }
+            PrinterItem::String(s, c) => push_str(&mut print_queue, &mut idx, s, c),
+            PrinterItem::Str(s, c) => push_str(&mut print_queue, &mut idx, s.to_string(), c),
+            PrinterItem::NewLine => {
+                print_queue.push(PrinterItem::NewLine);
+                idx += 1;
+            }
+        }
+    }
+    print_queue
+}
+
+/// Returns the buffer index of the bracket matching the one under the cursor,
+/// if there is none it tries the bracket right before the cursor
+fn find_matching_bracket(buffer: &Buffer) -> Option<usize> {
+    let try_at = |pos: usize| -> Option<usize> {
+        let c = *buffer.get(pos)?;
+        let (open, close, forward) = match c {
+            '(' => ('(', ')', true),
+            '[' => ('[', ']', true),
+            '{' => ('{', '}', true),
+            ')' => ('(', ')', false),
+            ']' => ('[', ']', false),
+            '}' => ('{', '}', false),
+            _ => return None,
+        };
+
+        let mut depth = 0;
+        let mut check = |idx: usize| -> Option<bool> {
+            match *buffer.get(idx)? {
+                c if c == open => depth += 1,
+                c if c == close => depth -= 1,
+                _ => (),
+            }
+            Some(depth == 0)
+        };
+        if forward {
+            (pos..buffer.len()).find(|idx| check(*idx) == Some(true))
+        } else {
+            (0..=pos).rev().find(|idx| check(*idx) == Some(true))
+        }
+    };
+
+    try_at(buffer.buffer_pos).or_else(|| try_at(buffer.buffer_pos.checked_sub(1)?))
+}
+
+#[test]
+fn find_matching_bracket_test() {
+    let mut buffer: Buffer = "foo(a[1], {b})".into();
+    buffer.set_buffer_pos(3);
+    assert_eq!(find_matching_bracket(&buffer), Some(13));
+    // cursor right after a closing bracket
+    buffer.set_buffer_pos(8);
+    assert_eq!(find_matching_bracket(&buffer), Some(5));
+    buffer.set_buffer_pos(1);
+    assert_eq!(find_matching_bracket(&buffer), None);
+}
+
 #[derive(Debug)]
 enum Token {
     Keyword(String),
@@ -107,7 +302,7 @@ fn parse(s: &[char]) -> Vec<Token> {
             '(' => {
                 // maybe function
                 if !alphanumeric.is_empty() {
-                    tokens.push(Token::Function(alphanumeric.drain(..).collect()));
+                    tokens.push(Token::Function(std::mem::take(&mut alphanumeric)));
                 }
                 tokens.push(Token::LeftParen('(', paren_idx));
                 paren_idx += 1;
@@ -115,21 +310,21 @@ fn parse(s: &[char]) -> Vec<Token> {
             '<' | '>' => {
                 // maybe type <u8>
                 if !alphanumeric.is_empty() {
-                    tokens.push(Token::Type(alphanumeric.drain(..).collect()));
+                    tokens.push(Token::Type(std::mem::take(&mut alphanumeric)));
                 }
                 tokens.push(Token::Symbol(c));
             }
             '!' => {
                 //maybe macro hello!
                 if !alphanumeric.is_empty() {
-                    tokens.push(Token::Macro(alphanumeric.drain(..).collect()));
+                    tokens.push(Token::Macro(std::mem::take(&mut alphanumeric)));
                 }
                 tokens.push(Token::Symbol(c));
             }
             '\'' => {
                 // maybe character || maybe lifetime
                 if !alphanumeric.is_empty() {
-                    tokens.push(Token::X(alphanumeric.drain(..).collect()));
+                    tokens.push(Token::X(std::mem::take(&mut alphanumeric)));
                 }
                 // ' is considered Token::Character in both cases
                 tokens.push(Token::Character(c));
@@ -140,7 +335,7 @@ fn parse(s: &[char]) -> Vec<Token> {
             '"' => {
                 // maybe literal
                 if !alphanumeric.is_empty() {
-                    tokens.push(Token::X(alphanumeric.drain(..).collect()));
+                    tokens.push(Token::X(std::mem::take(&mut alphanumeric)));
                 }
                 if let Some('\\') = previous_char {
                     tokens.push(Token::StringLiteralC(c));
@@ -160,23 +355,24 @@ fn parse(s: &[char]) -> Vec<Token> {
                 } else {
                     // maybe const
                     // let HELLO: usize =
-                    let token = parse_as(alphanumeric.drain(..).collect(), vec![TokenName::Const]);
+                    let token = parse_as(std::mem::take(&mut alphanumeric), vec![TokenName::Const]);
                     tokens.push(token);
                     tokens.push(Token::Symbol(':'));
                     continue;
                 }
                 // maybe function with type annotation
                 if s.peek() == Some(&&'<') {
-                    tokens.push(Token::Function(alphanumeric.drain(..).collect()));
+                    tokens.push(Token::Function(std::mem::take(&mut alphanumeric)));
                 } else {
-                    tokens.push(Token::X(alphanumeric.drain(..).collect()));
+                    tokens.push(Token::X(std::mem::take(&mut alphanumeric)));
                 }
                 tokens.extend(vec![Token::Symbol(':'), Token::Symbol(':')]);
             }
             '/' => {
                 // maybe division || maybe comment
                 if !alphanumeric.is_empty() {
-                    let token = parse_as(alphanumeric.drain(..).collect(), vec![TokenName::Number]);
+                    let token =
+                        parse_as(std::mem::take(&mut alphanumeric), vec![TokenName::Number]);
                     tokens.push(token);
                 }
                 if s.peek() == Some(&&'/') || s.peek() == Some(&&'*') {
@@ -190,13 +386,13 @@ fn parse(s: &[char]) -> Vec<Token> {
                     let mut comment = String::new();
                     while let Some(c) = s.next() {
                         if c == &end && end == '\n' {
-                            tokens.push(Token::Comment(comment.drain(..).collect()));
+                            tokens.push(Token::Comment(std::mem::take(&mut comment)));
                             tokens.push(Token::NewLine);
                             break;
                         } else if c == &end && s.peek() == Some(&&'/') {
                             // consume /
                             s.next();
-                            tokens.push(Token::Comment(comment.drain(..).collect()));
+                            tokens.push(Token::Comment(std::mem::take(&mut comment)));
                             tokens.push(Token::CommentS("*/"));
                             break;
                         } else {
@@ -240,7 +436,7 @@ fn catch_all(alphanumeric: &mut String, tokens: &mut Vec<Token>) {
     // catch all: parse the alphanumeric buffer
     if !alphanumeric.is_empty() {
         let token = parse_as(
-            alphanumeric.drain(..).collect(),
+            std::mem::take(alphanumeric),
             vec![
                 TokenName::Const,
                 TokenName::Keyword,
@@ -259,10 +455,9 @@ fn parse_character_lifetime<'a>(
     // a'  b' c' d' \r' \t'
     // try as char
 
-    let mut counter = 0;
     let mut previous_char = None;
     let mut characters = String::new();
-    while let Some(c) = s.next() {
+    for (counter, c) in s.by_ref().enumerate() {
         if c == &'\'' && previous_char != Some('\\') {
             // we reached the end
             characters.push('\'');
@@ -275,7 +470,6 @@ fn parse_character_lifetime<'a>(
             }
         }
         previous_char = Some(*c);
-        counter += 1;
     }
 
     // try as lifetime
@@ -397,3 +591,21 @@ const TYPES: &[&str] = &[
     "bool", "char", "usize", "isize", "u8", "i8", "u32", "i32", "u64", "i64", "u128", "i128",
     "str", "String",
 ];
+
+#[test]
+fn highlight_session_items_test() {
+    let theme = Theme::default();
+    let queue = highlight(&"foo(bar)".into(), &theme);
+    let queue = highlight_session_items(queue, &theme, |name| match name {
+        "foo" => Some(ItemKind::Function),
+        _ => None,
+    });
+    let colors: Vec<_> = queue
+        .filter_map(|item| match item {
+            PrinterItem::String(s, c) => Some((s, c)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(colors[0], ("foo".to_string(), Color::DarkCyan));
+    assert_eq!(colors[1], ("bar".to_string(), Color::White));
+}
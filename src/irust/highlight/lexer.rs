@@ -0,0 +1,416 @@
+//! A more complete rust lexer, used when `highlight_engine = "Lexer"`
+//!
+//! It handles raw strings, nested block comments, lifetimes vs characters, attributes and generics.
+//! The input is lexed line by line, each line result is cached with the state it started with
+//! (inside a comment, a string..) so editing a line only re-lexes that line.
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::{KEYWORDS, KEYWORDS2, SYMBOLS, TYPES};
+
+/// Lines cached before the cache is flushed
+const CACHE_LIMIT: usize = 1000;
+
+/// (state at the line start, line) => (tokens, state at the line end)
+type LineCache = HashMap<(LineState, String), (Vec<Token>, LineState)>;
+
+thread_local! {
+    static CACHE: RefCell<LineCache> = RefCell::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    Keyword,
+    Keyword2,
+    Function,
+    Type,
+    Number,
+    Symbol,
+    Macro,
+    Attribute,
+    StringLiteral,
+    Character,
+    LifeTime,
+    Comment,
+    Const,
+    Paren(isize),
+    X,
+}
+
+pub type Token = (Kind, String);
+
+/// What the previous lines left open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+struct LineState {
+    block_comment_depth: usize,
+    in_string: bool,
+    /// Number of `#` of the raw string we are in
+    raw_string: Option<usize>,
+    paren_depth: isize,
+}
+
+/// Lex the input, the returned lines should be separated with new lines
+pub fn lex(input: &[char]) -> Vec<Vec<Token>> {
+    let mut state = LineState::default();
+    input
+        .split(|c| *c == '\n')
+        .map(|line| {
+            let (tokens, next_state) = lex_line_cached(line, state);
+            state = next_state;
+            tokens
+        })
+        .collect()
+}
+
+fn lex_line_cached(line: &[char], state: LineState) -> (Vec<Token>, LineState) {
+    let key = (state, line.iter().collect::<String>());
+    CACHE.with(|cache| {
+        if let Some(cached) = cache.borrow().get(&key) {
+            return cached.clone();
+        }
+        let lexed = lex_line(line, state);
+        let mut cache = cache.borrow_mut();
+        if cache.len() > CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(key, lexed.clone());
+        lexed
+    })
+}
+
+fn lex_line(line: &[char], mut state: LineState) -> (Vec<Token>, LineState) {
+    let mut tokens: Vec<Token> = vec![];
+    let mut i = 0;
+    let at = |idx: usize| line.get(idx).copied();
+    let collect = |from: usize, to: usize| line[from..to].iter().collect::<String>();
+
+    while i < line.len() {
+        let start = i;
+
+        // continue what the previous line left open
+        if state.block_comment_depth > 0 {
+            i = end_of_block_comment(line, i, &mut state.block_comment_depth);
+            tokens.push((Kind::Comment, collect(start, i)));
+            continue;
+        }
+        if let Some(hashes) = state.raw_string {
+            i = end_of_raw_string(line, i, hashes, &mut state.raw_string);
+            tokens.push((Kind::StringLiteral, collect(start, i)));
+            continue;
+        }
+        if state.in_string {
+            i = end_of_string(line, i, &mut state.in_string);
+            tokens.push((Kind::StringLiteral, collect(start, i)));
+            continue;
+        }
+
+        let c = line[i];
+        match c {
+            '/' if at(i + 1) == Some('/') => {
+                i = line.len();
+                tokens.push((Kind::Comment, collect(start, i)));
+            }
+            '/' if at(i + 1) == Some('*') => {
+                state.block_comment_depth = 1;
+                i = end_of_block_comment(line, i + 2, &mut state.block_comment_depth);
+                tokens.push((Kind::Comment, collect(start, i)));
+            }
+            '#' if at(i + 1) == Some('[') || (at(i + 1) == Some('!') && at(i + 2) == Some('[')) => {
+                i = end_of_attribute(line, i);
+                tokens.push((Kind::Attribute, collect(start, i)));
+            }
+            'r' | 'b' if is_raw_string_start(line, i) => {
+                // r"..", r#".."#, br".."
+                let mut j = if c == 'b' { i + 2 } else { i + 1 };
+                let mut hashes = 0;
+                while at(j) == Some('#') {
+                    hashes += 1;
+                    j += 1;
+                }
+                state.raw_string = Some(hashes);
+                i = end_of_raw_string(line, j + 1, hashes, &mut state.raw_string);
+                tokens.push((Kind::StringLiteral, collect(start, i)));
+            }
+            'b' if at(i + 1) == Some('"') => {
+                state.in_string = true;
+                i = end_of_string(line, i + 2, &mut state.in_string);
+                tokens.push((Kind::StringLiteral, collect(start, i)));
+            }
+            'b' if at(i + 1) == Some('\'') => {
+                i = end_of_character(line, i + 1).unwrap_or(i + 2);
+                tokens.push((Kind::Character, collect(start, i)));
+            }
+            '"' => {
+                state.in_string = true;
+                i = end_of_string(line, i + 1, &mut state.in_string);
+                tokens.push((Kind::StringLiteral, collect(start, i)));
+            }
+            '\'' => {
+                if let Some(end) = end_of_character(line, i) {
+                    i = end;
+                    tokens.push((Kind::Character, collect(start, i)));
+                } else if at(i + 1).map(is_ident_start) == Some(true) {
+                    // lifetime
+                    i = end_of_ident(line, i + 1);
+                    tokens.push((Kind::LifeTime, collect(start, i)));
+                } else {
+                    i += 1;
+                    tokens.push((Kind::Symbol, c.to_string()));
+                }
+            }
+            c if c.is_ascii_digit() => {
+                i = end_of_number(line, i);
+                tokens.push((Kind::Number, collect(start, i)));
+            }
+            c if is_ident_start(c) => {
+                i = end_of_ident(line, i);
+                let ident = collect(start, i);
+                let kind = classify_ident(&ident, line, i, &tokens);
+                tokens.push((kind, ident));
+            }
+            '(' => {
+                i += 1;
+                tokens.push((Kind::Paren(state.paren_depth), c.to_string()));
+                state.paren_depth += 1;
+            }
+            ')' => {
+                i += 1;
+                state.paren_depth -= 1;
+                tokens.push((Kind::Paren(state.paren_depth), c.to_string()));
+            }
+            c if SYMBOLS.contains(&c) || c == '<' || c == '>' => {
+                i += 1;
+                tokens.push((Kind::Symbol, c.to_string()));
+            }
+            c => {
+                i += 1;
+                tokens.push((Kind::X, c.to_string()));
+            }
+        }
+    }
+
+    (tokens, state)
+}
+
+fn classify_ident(ident: &str, line: &[char], end: usize, previous: &[Token]) -> Kind {
+    let next = line.get(end).copied();
+    let next_next = line.get(end + 1).copied();
+    let previous_ident = previous
+        .iter()
+        .rev()
+        .find(|(kind, s)| !(*kind == Kind::X && s.trim().is_empty()))
+        .map(|(_, s)| s.as_str());
+
+    if next == Some('!') && next_next != Some('=') && !KEYWORDS.contains(&ident) {
+        Kind::Macro
+    } else if KEYWORDS.contains(&ident) {
+        Kind::Keyword
+    } else if KEYWORDS2.contains(&ident) {
+        Kind::Keyword2
+    } else if previous_ident == Some("fn")
+        || next == Some('(')
+        || line[end..].starts_with(&[':', ':', '<'])
+    {
+        // fn hello, hello(), collect::<Vec<_>>()
+        Kind::Function
+    } else if TYPES.contains(&ident) || (next == Some('<') && !matches!(next_next, Some('=' | '<')))
+    {
+        Kind::Type
+    } else if ident
+        .chars()
+        .all(|c| c.is_uppercase() || c == '_' || c.is_ascii_digit())
+    {
+        Kind::Const
+    } else if ident.starts_with(char::is_uppercase) {
+        Kind::Type
+    } else {
+        Kind::X
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_raw_string_start(line: &[char], i: usize) -> bool {
+    let mut j = i;
+    if line[j] == 'b' {
+        j += 1;
+        if line.get(j) != Some(&'r') {
+            return false;
+        }
+    }
+    j += 1;
+    // an identifier ending with r is not a raw string, exp: for"
+    if i > 0 && (line[i - 1].is_alphanumeric() || line[i - 1] == '_') {
+        return false;
+    }
+    while line.get(j) == Some(&'#') {
+        j += 1;
+    }
+    line.get(j) == Some(&'"')
+}
+
+fn end_of_ident(line: &[char], mut i: usize) -> usize {
+    while i < line.len() && (line[i].is_alphanumeric() || line[i] == '_') {
+        i += 1;
+    }
+    i
+}
+
+fn end_of_number(line: &[char], mut i: usize) -> usize {
+    while i < line.len() {
+        let c = line[i];
+        let is_decimal_point = c == '.'
+            && line.get(i + 1).map(char::is_ascii_digit) == Some(true)
+            && line.get(i.wrapping_sub(1)) != Some(&'.');
+        // 1e-3
+        let is_exponent_sign = (c == '-' || c == '+')
+            && matches!(line.get(i.wrapping_sub(1)), Some('e' | 'E'))
+            && !line[..i].starts_with(&['0', 'x']);
+        if c.is_alphanumeric() || c == '_' || is_decimal_point || is_exponent_sign {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// Returns the index after the closing `'` if this is a character literal
+fn end_of_character(line: &[char], i: usize) -> Option<usize> {
+    // 'a' '\n' '\'' '\u{1F600}'
+    match line.get(i + 1)? {
+        '\\' => {
+            let mut j = i + 3;
+            while j < line.len() {
+                if line[j] == '\'' {
+                    return Some(j + 1);
+                }
+                j += 1;
+            }
+            None
+        }
+        _ if line.get(i + 2) == Some(&'\'') => Some(i + 3),
+        _ => None,
+    }
+}
+
+fn end_of_string(line: &[char], mut i: usize, in_string: &mut bool) -> usize {
+    while i < line.len() {
+        match line[i] {
+            '\\' => i += 2,
+            '"' => {
+                *in_string = false;
+                return i + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    line.len()
+}
+
+fn end_of_raw_string(
+    line: &[char],
+    mut i: usize,
+    hashes: usize,
+    raw_string: &mut Option<usize>,
+) -> usize {
+    while i < line.len() {
+        if line[i] == '"' && line[i + 1..].iter().take_while(|c| **c == '#').count() >= hashes {
+            *raw_string = None;
+            return i + 1 + hashes;
+        }
+        i += 1;
+    }
+    line.len()
+}
+
+fn end_of_block_comment(line: &[char], mut i: usize, depth: &mut usize) -> usize {
+    while i < line.len() {
+        if line[i..].starts_with(&['/', '*']) {
+            *depth += 1;
+            i += 2;
+        } else if line[i..].starts_with(&['*', '/']) {
+            *depth -= 1;
+            i += 2;
+            if *depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    line.len()
+}
+
+fn end_of_attribute(line: &[char], mut i: usize) -> usize {
+    let mut depth = 0;
+    let mut in_string = false;
+    while i < line.len() {
+        match line[i] {
+            '"' => in_string = !in_string,
+            '\\' if in_string => i += 1,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    line.len()
+}
+
+#[test]
+fn lex_test() {
+    let lex_str = |s: &str| {
+        lex(&s.chars().collect::<Vec<_>>())
+            .into_iter()
+            .flatten()
+            .filter(|(_, s)| !s.trim().is_empty())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        lex_str(r####"let s = r#"a "quoted" str"#;"####)[3],
+        (Kind::StringLiteral, r###"r#"a "quoted" str"#"###.into())
+    );
+    assert_eq!(
+        lex_str("fn f<'a>(c: char) { 'x' }"),
+        vec![
+            (Kind::Keyword2, "fn".into()),
+            (Kind::Function, "f".into()),
+            (Kind::Symbol, "<".into()),
+            (Kind::LifeTime, "'a".into()),
+            (Kind::Symbol, ">".into()),
+            (Kind::Paren(0), "(".into()),
+            (Kind::X, "c".into()),
+            (Kind::Symbol, ":".into()),
+            (Kind::Type, "char".into()),
+            (Kind::Paren(0), ")".into()),
+            (Kind::X, "{".into()),
+            (Kind::Character, "'x'".into()),
+            (Kind::X, "}".into()),
+        ]
+    );
+    assert_eq!(
+        lex_str("#[derive(Debug)]")[0],
+        (Kind::Attribute, "#[derive(Debug)]".into())
+    );
+    let generics = lex_str("HashMap<String, Vec<Option<u8>>>");
+    assert!(generics
+        .iter()
+        .filter(|(_, s)| s.chars().all(char::is_alphanumeric))
+        .all(|(kind, _)| *kind == Kind::Type));
+    // state is carried across lines
+    assert_eq!(
+        lex_str("/* a\n/* nested */ b */ c")
+            .last()
+            .map(|(kind, _)| kind.clone()),
+        Some(Kind::X)
+    );
+}
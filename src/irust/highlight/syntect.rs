@@ -0,0 +1,230 @@
+//! A grammar based highlighter using syntect and its bundled Rust syntax, used when `highlight_engine = "Syntect"`
+//!
+//! Scopes are mapped to the same token kinds as the `Lexer` engine, so both use the same theme colors.
+//! The result of each line is cached with the parser state it started with, on the next highlight
+//! a line is parsed again only if it was edited or if an edit above it changed its starting state.
+use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxSet};
+
+use super::lexer::{Kind, Token};
+use super::{KEYWORDS, SYMBOLS, TYPES};
+
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+
+thread_local! {
+    static CACHE: RefCell<Vec<CachedLine>> = const { RefCell::new(Vec::new()) };
+}
+
+/// What the previous lines left open
+#[derive(Clone, PartialEq)]
+struct LineState {
+    parse_state: ParseState,
+    scopes: ScopeStack,
+    paren_depth: isize,
+}
+
+impl LineState {
+    fn new() -> Option<Self> {
+        let syntax = SYNTAX_SET.find_syntax_by_extension("rs")?;
+        Some(Self {
+            parse_state: ParseState::new(syntax),
+            scopes: ScopeStack::new(),
+            paren_depth: 0,
+        })
+    }
+}
+
+struct CachedLine {
+    start: LineState,
+    line: String,
+    tokens: Vec<Token>,
+}
+
+/// Highlight the input, the returned lines should be separated with new lines
+///
+/// Returns None if the Rust syntax can't be parsed, the caller falls back to another engine then
+pub fn lex(input: &[char]) -> Option<Vec<Vec<Token>>> {
+    let lines: Vec<String> = input
+        .split(|c| *c == '\n')
+        .map(|line| line.iter().collect())
+        .collect();
+
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let mut state = LineState::new()?;
+        for (idx, line) in lines.iter().enumerate() {
+            let cached = cache.get(idx);
+            if cached.is_some_and(|cached| cached.line == *line && cached.start == state) {
+                // the state at the end of a cached line is the start of the next one
+                if let Some(next) = cache.get(idx + 1) {
+                    state = next.start.clone();
+                    continue;
+                }
+            }
+            let start = state.clone();
+            let tokens = highlight_line(line, &mut state)?;
+            cache.truncate(idx);
+            cache.push(CachedLine {
+                start,
+                line: line.clone(),
+                tokens,
+            });
+        }
+        cache.truncate(lines.len());
+        Some(cache.iter().map(|cached| cached.tokens.clone()).collect())
+    })
+}
+
+fn highlight_line(line: &str, state: &mut LineState) -> Option<Vec<Token>> {
+    // the bundled syntaxes expect lines with their new line
+    let line_nl = format!("{}\n", line);
+    let ops = state.parse_state.parse_line(&line_nl, &SYNTAX_SET).ok()?;
+
+    let mut tokens = Vec::new();
+    let mut ops = ops.into_iter().peekable();
+    let mut start = 0;
+    while start < line.len() {
+        while let Some((_, op)) = ops.next_if(|(pos, _)| *pos <= start) {
+            state.scopes.apply(&op).ok()?;
+        }
+        let end = ops
+            .peek()
+            .map_or(line.len(), |(pos, _)| (*pos).min(line.len()));
+        push_tokens(&mut tokens, &line[start..end], state);
+        start = end;
+    }
+    // the scopes closed at the end of the line, exp: a line comment
+    for (_, op) in ops {
+        state.scopes.apply(&op).ok()?;
+    }
+    Some(tokens)
+}
+
+fn push_tokens(tokens: &mut Vec<Token>, text: &str, state: &mut LineState) {
+    let scopes = state.scopes.as_slice();
+    let kind = classify(text, scopes);
+
+    match kind {
+        // parens are colored by depth like the other engines, one token each
+        Kind::Symbol | Kind::X if text.contains(['(', ')']) => {
+            for c in text.chars() {
+                let kind = match c {
+                    '(' => {
+                        state.paren_depth += 1;
+                        Kind::Paren(state.paren_depth - 1)
+                    }
+                    ')' => {
+                        state.paren_depth -= 1;
+                        Kind::Paren(state.paren_depth)
+                    }
+                    _ => kind.clone(),
+                };
+                tokens.push((kind, c.to_string()));
+            }
+        }
+        kind => tokens.push((kind, text.to_string())),
+    }
+}
+
+fn classify(text: &str, scopes: &[Scope]) -> Kind {
+    let has = |prefix: &str| -> bool {
+        let prefix = Scope::new(prefix).expect("valid scope");
+        scopes.iter().any(|scope| prefix.is_prefix_of(*scope))
+    };
+
+    // the enclosing scopes come first: everything inside a comment is a comment
+    if has("comment") {
+        return Kind::Comment;
+    }
+    // char literals are single quoted strings in this syntax
+    if has("string.quoted.single") {
+        return Kind::Character;
+    }
+    if has("string") {
+        return Kind::StringLiteral;
+    }
+    if has("meta.annotation") {
+        return Kind::Attribute;
+    }
+
+    let innermost = match scopes.last() {
+        Some(scope) => scope.build_string(),
+        None => return Kind::X,
+    };
+    let is = |prefix: &str| innermost.starts_with(prefix);
+    if is("storage.modifier.lifetime") {
+        Kind::LifeTime
+    } else if is("support.macro") || is("entity.name.macro") {
+        Kind::Macro
+    } else if is("constant.numeric") {
+        Kind::Number
+    } else if is("constant") {
+        Kind::Const
+    } else if is("entity.name.function") || is("support.function") {
+        Kind::Function
+    } else if is("entity.name") || is("support.type") {
+        Kind::Type
+    } else if is("storage.type") {
+        // `u8`, `let` and `impl` share this scope, sort them like the other engines
+        if TYPES.contains(&text) {
+            Kind::Type
+        } else if KEYWORDS.contains(&text) {
+            Kind::Keyword
+        } else {
+            Kind::Keyword2
+        }
+    } else if is("keyword.operator") {
+        Kind::Symbol
+    } else if is("keyword") || is("storage.modifier") {
+        Kind::Keyword
+    } else if is("punctuation")
+        && text
+            .chars()
+            .all(|c| SYMBOLS.contains(&c) || c == '<' || c == '>')
+    {
+        Kind::Symbol
+    } else {
+        Kind::X
+    }
+}
+
+#[test]
+fn syntect_lex_test() {
+    let lex_str = |s: &str| {
+        lex(&s.chars().collect::<Vec<_>>())
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .filter(|(_, s)| !s.trim().is_empty())
+            .collect::<Vec<_>>()
+    };
+
+    let tokens = lex_str("fn f<'a>(c: char) -> u8 { 'x'; r#\"raw\"#; println!(\"{}\", 1) }");
+    let kind_of = |text: &str| {
+        tokens
+            .iter()
+            .find(|(_, s)| s == text)
+            .map(|(kind, _)| kind.clone())
+    };
+    assert_eq!(kind_of("fn"), Some(Kind::Keyword2));
+    assert_eq!(kind_of("f"), Some(Kind::Function));
+    assert_eq!(kind_of("'a"), Some(Kind::LifeTime));
+    assert_eq!(kind_of("char"), Some(Kind::Type));
+    assert_eq!(kind_of("("), Some(Kind::Paren(0)));
+    assert_eq!(kind_of("println!"), Some(Kind::Macro));
+    assert!(tokens
+        .iter()
+        .filter(|(_, s)| s.contains('x') || s.contains("raw"))
+        .all(|(kind, _)| *kind == Kind::Character || *kind == Kind::StringLiteral));
+
+    // state is carried across lines, and an edit after a comment is highlighted again
+    assert_eq!(
+        lex_str("/* a\nb */ c").last().map(|(kind, _)| kind.clone()),
+        Some(Kind::X)
+    );
+    assert_eq!(
+        lex_str("/* a\nb c").last().map(|(kind, _)| kind.clone()),
+        Some(Kind::Comment)
+    );
+}
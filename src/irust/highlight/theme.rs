@@ -1,20 +1,75 @@
+use crate::irust::terminal::Background;
 use crate::irust::Result;
 use crossterm::style::Color;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::io::Write;
 
-pub fn theme() -> Result<Theme> {
-    let theme_file = dirs_next::config_dir()
+pub const BUILTIN_THEMES: &[&str] = &["default", "gruvbox", "solarized", "one-dark", "one-light"];
+
+/// Hex colors are downgraded to what the terminal supports
+static COLOR_SUPPORT: Lazy<ColorSupport> = Lazy::new(ColorSupport::detect);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorSupport {
+    fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" || cfg!(windows) {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+}
+
+fn theme_path() -> Result<std::path::PathBuf> {
+    Ok(dirs_next::config_dir()
         .ok_or("Error accessing config_dir")?
         .join("irust")
-        .join("theme");
+        .join("theme"))
+}
 
-    let data = std::fs::read_to_string(theme_file)?;
+pub fn theme() -> Result<Theme> {
+    let data = std::fs::read_to_string(theme_path()?)?;
 
     Ok(toml::from_str(&data)?)
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+/// Copy the theme file to `theme.bak` before it is replaced, unless it only holds a builtin theme
+///
+/// Returns the backup path if a backup was made
+pub fn backup_theme() -> Result<Option<std::path::PathBuf>> {
+    let theme_path = theme_path()?;
+    let data = match std::fs::read_to_string(&theme_path) {
+        Ok(data) => data,
+        Err(_) => return Ok(None),
+    };
+    let is_builtin = toml::from_str::<Theme>(&data).is_ok_and(|theme| {
+        BUILTIN_THEMES
+            .iter()
+            .any(|name| builtin(name).as_ref() == Some(&theme))
+    });
+    if is_builtin {
+        return Ok(None);
+    }
+
+    let backup_path = theme_path.with_extension("bak");
+    std::fs::write(&backup_path, data)?;
+    Ok(Some(backup_path))
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+// Missing colors in the theme file use the default ones
+#[serde(default)]
 pub struct Theme {
     pub keyword: String,
     pub keyword2: String,
@@ -29,20 +84,91 @@ pub struct Theme {
     pub comment: String,
     pub r#const: String,
     pub x: String,
+    // identifiers defined earlier in the session
+    pub session_function: String,
+    pub session_type: String,
+    pub session_variable: String,
+    /// Set from `Options::highlight_engine`
+    #[serde(skip)]
+    pub engine: super::HighlightEngine,
 }
 
 impl Theme {
     pub fn save(&self) -> Result<()> {
-        let theme_path = dirs_next::config_dir()
-            .ok_or("Error accessing config_dir")?
-            .join("irust")
-            .join("theme");
-        let mut theme = std::fs::File::create(&theme_path)?;
+        let mut theme = std::fs::File::create(theme_path()?)?;
         write!(theme, "{}", toml::to_string(&self)?)?;
         Ok(())
     }
+    /// Switch between the default dark and light themes depending on the terminal background,
+    /// custom themes are left untouched
+    pub fn adapt_to(&mut self, background: Background) {
+        let (from, to) = match background {
+            Background::Light => ("default", "one-light"),
+            Background::Dark => ("one-light", "default"),
+            Background::Auto => return,
+        };
+        // safe unwraps: these are builtin themes
+        let mut from = builtin(from).unwrap();
+        from.engine = self.engine;
+        if *self == from {
+            *self = Self {
+                engine: self.engine,
+                ..builtin(to).unwrap()
+            };
+        }
+    }
+
     pub fn reset(&mut self) {
-        *self = Self::default();
+        *self = Self {
+            engine: self.engine,
+            ..Self::default()
+        };
+    }
+}
+
+/// Bundled themes, see `BUILTIN_THEMES`
+pub fn builtin(name: &str) -> Option<Theme> {
+    // keyword, keyword2, function, type, number, symbol, macro, string_literal, character,
+    // lifetime, comment, const, x, session_function, session_type, session_variable
+    let palette = |c: [&str; 16]| Theme {
+        keyword: c[0].into(),
+        keyword2: c[1].into(),
+        function: c[2].into(),
+        r#type: c[3].into(),
+        number: c[4].into(),
+        symbol: c[5].into(),
+        r#macro: c[6].into(),
+        string_literal: c[7].into(),
+        character: c[8].into(),
+        lifetime: c[9].into(),
+        comment: c[10].into(),
+        r#const: c[11].into(),
+        x: c[12].into(),
+        session_function: c[13].into(),
+        session_type: c[14].into(),
+        session_variable: c[15].into(),
+        engine: Default::default(),
+    };
+
+    match name {
+        "default" => Some(Theme::default()),
+        "gruvbox" => Some(palette([
+            "#fb4934", "#fe8019", "#b8bb26", "#fabd2f", "#d3869b", "#8ec07c", "#8ec07c", "#b8bb26",
+            "#d3869b", "#83a598", "#928374", "#d3869b", "#ebdbb2", "#83a598", "#fe8019", "#8ec07c",
+        ])),
+        "solarized" => Some(palette([
+            "#859900", "#cb4b16", "#268bd2", "#b58900", "#d33682", "#839496", "#6c71c4", "#2aa198",
+            "#2aa198", "#6c71c4", "#586e75", "#b58900", "#839496", "#6c71c4", "#dc322f", "#d33682",
+        ])),
+        "one-dark" => Some(palette([
+            "#c678dd", "#e06c75", "#61afef", "#e5c07b", "#d19a66", "#56b6c2", "#56b6c2", "#98c379",
+            "#98c379", "#c678dd", "#5c6370", "#d19a66", "#abb2bf", "#56b6c2", "#e5c07b", "#e06c75",
+        ])),
+        "one-light" => Some(palette([
+            "#a626a4", "#e45649", "#4078f2", "#c18401", "#986801", "#e45649", "#0184bc", "#50a14f",
+            "#50a14f", "#a626a4", "#a0a1a7", "#986801", "#383a42", "#0184bc", "#c18401", "#e45649",
+        ])),
+        _ => None,
     }
 }
 
@@ -62,6 +188,10 @@ impl Default for Theme {
             comment: "dark_grey".into(),
             r#const: "dark_green".into(),
             x: "white".into(),
+            session_function: "dark_cyan".into(),
+            session_type: "dark_magenta".into(),
+            session_variable: "green".into(),
+            engine: super::HighlightEngine::default(),
         }
     }
 }
@@ -77,7 +207,11 @@ pub fn theme_color_to_term_color(color: &str) -> Option<Color> {
             let r = u8::from_str_radix(&color[0..2], 16).ok()?;
             let g = u8::from_str_radix(&color[2..4], 16).ok()?;
             let b = u8::from_str_radix(&color[4..], 16).ok()?;
-            Some(Color::Rgb { r, g, b })
+            Some(match *COLOR_SUPPORT {
+                ColorSupport::TrueColor => Color::Rgb { r, g, b },
+                ColorSupport::Ansi256 => Color::AnsiValue(rgb_to_ansi256(r, g, b)),
+                ColorSupport::Ansi16 => rgb_to_ansi16(r, g, b),
+            })
         };
         parse()
     } else {
@@ -104,3 +238,55 @@ pub fn theme_color_to_term_color(color: &str) -> Option<Color> {
         }
     }
 }
+
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    // grayscale ramp
+    if r == g && g == b {
+        return match r {
+            0..=7 => 16,
+            249..=255 => 231,
+            _ => 232 + ((r as u16 - 8) * 24 / 247) as u8,
+        };
+    }
+    // 6x6x6 color cube
+    let scale = |c: u8| (c as u16 * 5 + 127) / 255;
+    (16 + 36 * scale(r) + 6 * scale(g) + scale(b)) as u8
+}
+
+fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    const ANSI16: [(Color, (i32, i32, i32)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::DarkRed, (128, 0, 0)),
+        (Color::DarkGreen, (0, 128, 0)),
+        (Color::DarkYellow, (128, 128, 0)),
+        (Color::DarkBlue, (0, 0, 128)),
+        (Color::DarkMagenta, (128, 0, 128)),
+        (Color::DarkCyan, (0, 128, 128)),
+        (Color::Grey, (192, 192, 192)),
+        (Color::DarkGrey, (128, 128, 128)),
+        (Color::Red, (255, 0, 0)),
+        (Color::Green, (0, 255, 0)),
+        (Color::Yellow, (255, 255, 0)),
+        (Color::Blue, (0, 0, 255)),
+        (Color::Magenta, (255, 0, 255)),
+        (Color::Cyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    // safe unwrap: ANSI16 is not empty
+    ANSI16
+        .iter()
+        .min_by_key(|(_, (r2, g2, b2))| (r - r2).pow(2) + (g - g2).pow(2) + (b - b2).pow(2))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+#[test]
+fn color_fallback_test() {
+    assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+    assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+    assert_eq!(rgb_to_ansi256(128, 128, 128), 243);
+    assert_eq!(rgb_to_ansi16(250, 10, 10), Color::Red);
+    assert_eq!(rgb_to_ansi16(0x92, 0x83, 0x74), Color::DarkGrey);
+    assert!(BUILTIN_THEMES.iter().all(|name| builtin(name).is_some()));
+}
@@ -1,5 +1,6 @@
 use super::Result;
 use std::fs;
+use std::io::Write;
 use std::path;
 
 /// Mark to keep backward-compatibility with the old way of saving history
@@ -12,36 +13,52 @@ pub struct History {
     history_file_path: path::PathBuf,
     pub lock: bool,
     last_buffer: Vec<char>,
+    /// Maximum number of saved entries, 0 means unlimited
+    max_entries: usize,
+    /// Index of the first entry added in this session
+    session_start: usize,
+    /// Share the history file live with other IRust instances
+    share: bool,
+    /// Size of the history file the last time we synced with it
+    synced_len: u64,
+    /// Inputs entered in this session with their operation number
+    session_inputs: Vec<(usize, String)>,
 }
 
 impl History {
-    pub fn new() -> Result<Self> {
+    pub fn new(max_entries: usize, share: bool) -> Result<Self> {
         let history_file_path = crate::irust::cargo_cmds::IRUST_DIR.join("history");
         if !history_file_path.exists() {
             fs::File::create(&history_file_path)?;
         }
 
-        let history: String = fs::read_to_string(&history_file_path)?;
-
-        let history: Vec<String> = if history.starts_with(NEW_HISTORY_MARK) {
-            history
-                .split("\n//\n")
-                .skip(1)
-                .map(ToOwned::to_owned)
-                .collect()
-        } else {
-            history.lines().map(ToOwned::to_owned).collect()
-        };
+        let raw_history = fs::read_to_string(&history_file_path)?;
+        let mut history = parse_history(&raw_history);
+        truncate_history(&mut history, max_entries);
 
         let cursor = 0;
+        let session_start = history.len();
 
-        Ok(Self {
+        let mut history = Self {
             history,
             cursor,
             history_file_path,
             lock: false,
             last_buffer: Vec::new(),
-        })
+            max_entries,
+            session_start,
+            share,
+            synced_len: raw_history.len() as u64,
+            session_inputs: Vec::new(),
+        };
+
+        // Shared history appends to the file in the new format, so convert old history files first
+        if share && !raw_history.is_empty() && !raw_history.starts_with(NEW_HISTORY_MARK) {
+            history.save()?;
+            history.sync();
+        }
+
+        Ok(history)
     }
     pub fn down(&mut self, buffer: &[char]) -> Option<String> {
         if !self.lock {
@@ -61,6 +78,8 @@ impl History {
 
     pub fn up(&mut self, buffer: &[char]) -> Option<String> {
         if !self.lock {
+            // starting a new history navigation, pick up entries from other IRust instances
+            self.sync();
             self.last_buffer = buffer.to_owned();
             self.cursor = 0;
         }
@@ -77,34 +96,118 @@ impl History {
     }
 
     pub fn push(&mut self, buffer: String) {
+        // skip consecutive duplicates
         if !buffer.is_empty() && Some(&buffer) != self.history.last() {
+            if self.share {
+                // History is optional
+                let _ = self.append_entry(&buffer);
+            }
             self.history.push(buffer);
+            let removed = truncate_history(&mut self.history, self.max_entries);
+            self.session_start = self.session_start.saturating_sub(removed);
             self.go_to_last();
         }
     }
 
+    /// Rest of the most recent single line entry that starts with `buffer`
+    pub fn suggest(&self, buffer: &str) -> Option<&str> {
+        if buffer.is_empty() || buffer.contains('\n') {
+            return None;
+        }
+        self.history
+            .iter()
+            .rev()
+            .filter_map(|entry| entry.strip_prefix(buffer))
+            .find(|rest| !rest.is_empty() && !rest.contains('\n'))
+    }
+
+    /// Save the history atomically
+    ///
+    /// The history file is re-read first and only the entries added in this session are appended to it,
+    /// so concurrent IRust instances don't overwrite each other's history
+    /// (with a shared history they are already on disk, so this only trims the file)
     pub fn save(&self) -> Result<()> {
-        let is_comment = |s: &str| -> bool { s.trim_start().starts_with("//") };
-        let mut history = self.history.clone();
+        let _lock = HistoryLock::acquire(&self.history_file_path)?;
 
-        if history.is_empty() || history[0] != NEW_HISTORY_MARK {
-            history.insert(0, NEW_HISTORY_MARK.to_string());
+        let mut history = fs::read_to_string(&self.history_file_path)
+            .map(|history| parse_history(&history))
+            .unwrap_or_default();
+        if !self.share {
+            for entry in &self.history[self.session_start..] {
+                if Some(entry) != history.last() {
+                    history.push(entry.clone());
+                }
+            }
         }
+        truncate_history(&mut history, self.max_entries);
 
-        let history: Vec<String> = history
-            .into_iter()
-            .map(|e| {
-                let e: Vec<String> = e
-                    .lines()
-                    .filter(|l| !is_comment(l))
-                    .map(ToOwned::to_owned)
-                    .collect();
-                e.join("\n")
-            })
-            .collect();
-        let history = history.join("\n//\n");
+        // write to a temporary file then rename it, so the history file is never left half written
+        let tmp_path = self
+            .history_file_path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp_path, serialize_history(history))?;
+        fs::rename(&tmp_path, &self.history_file_path)?;
+        Ok(())
+    }
+
+    pub fn record_session_input(&mut self, operation_number: usize, input: String) {
+        self.session_inputs.push((operation_number, input));
+    }
+
+    pub fn session_inputs(&self) -> &[(usize, String)] {
+        &self.session_inputs
+    }
+
+    pub fn session_input(&self, operation_number: usize) -> Option<&String> {
+        self.session_inputs
+            .iter()
+            .find(|(n, _)| *n == operation_number)
+            .map(|(_, input)| input)
+    }
+
+    pub fn last_session_input(&self) -> Option<&String> {
+        self.session_inputs.last().map(|(_, input)| input)
+    }
+
+    /// Reload the history file if another IRust instance appended to it since the last sync
+    pub fn sync(&mut self) {
+        if !self.share {
+            return;
+        }
+        match fs::metadata(&self.history_file_path) {
+            Ok(metadata) if metadata.len() != self.synced_len => (),
+            _ => return,
+        }
+
+        if let Ok(raw_history) = fs::read_to_string(&self.history_file_path) {
+            self.history = parse_history(&raw_history);
+            truncate_history(&mut self.history, self.max_entries);
+            self.session_start = self.history.len();
+            self.synced_len = raw_history.len() as u64;
+        }
+    }
+
+    fn append_entry(&mut self, entry: &str) -> Result<()> {
+        let _lock = HistoryLock::acquire(&self.history_file_path)?;
+
+        let mut history_file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.history_file_path)?;
+        let len = history_file.metadata()?.len();
+
+        let entry = serialize_entry(entry);
+        let data = if len == 0 {
+            format!("{}{}", NEW_HISTORY_MARK, entry)
+        } else {
+            format!("\n//\n{}", entry)
+        };
+        history_file.write_all(data.as_bytes())?;
 
-        fs::write(&self.history_file_path, history)?;
+        // if someone else wrote to the file since our last sync, leave it out of sync so it gets reloaded
+        if len == self.synced_len {
+            self.synced_len += data.len() as u64;
+        }
         Ok(())
     }
 
@@ -148,3 +251,124 @@ impl History {
         self.lock = false;
     }
 }
+
+fn parse_history(history: &str) -> Vec<String> {
+    if history.starts_with(NEW_HISTORY_MARK) {
+        history
+            .split("\n//\n")
+            .skip(1)
+            .map(ToOwned::to_owned)
+            .collect()
+    } else {
+        history.lines().map(ToOwned::to_owned).collect()
+    }
+}
+
+fn serialize_history(mut history: Vec<String>) -> String {
+    if history.is_empty() || history[0] != NEW_HISTORY_MARK {
+        history.insert(0, NEW_HISTORY_MARK.to_string());
+    }
+
+    let history: Vec<String> = history.iter().map(|e| serialize_entry(e)).collect();
+    history.join("\n//\n")
+}
+
+fn serialize_entry(entry: &str) -> String {
+    let is_comment = |s: &str| -> bool { s.trim_start().starts_with("//") };
+
+    let entry: Vec<&str> = entry.lines().filter(|l| !is_comment(l)).collect();
+    entry.join("\n")
+}
+
+/// Keep only the last `max_entries` entries (0 means unlimited), returns the number of removed entries
+fn truncate_history(history: &mut Vec<String>, max_entries: usize) -> usize {
+    if max_entries == 0 || history.len() <= max_entries {
+        return 0;
+    }
+    let removed = history.len() - max_entries;
+    history.drain(..removed);
+    removed
+}
+
+/// Exclusive lock shared by all IRust instances writing to the history file
+///
+/// On unix the lock file is locked with `flock` and released when it is closed (on drop),
+/// elsewhere holding the lock means having created the lock file, which is removed on drop
+struct HistoryLock {
+    _lock_file: fs::File,
+    #[cfg(not(unix))]
+    lock_file_path: path::PathBuf,
+}
+
+impl HistoryLock {
+    #[cfg(unix)]
+    fn acquire(history_file_path: &path::Path) -> Result<Self> {
+        use nix::fcntl::{flock, FlockArg};
+        use std::os::unix::io::AsRawFd;
+
+        let lock_file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(history_file_path.with_extension("lock"))?;
+        flock(lock_file.as_raw_fd(), FlockArg::LockExclusive)?;
+
+        Ok(Self {
+            _lock_file: lock_file,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn acquire(history_file_path: &path::Path) -> Result<Self> {
+        // a lock file left by a crashed instance is taken over after this delay
+        const STALE_LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+        let lock_file_path = history_file_path.with_extension("lock");
+        let start = std::time::Instant::now();
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_file_path)
+            {
+                Ok(lock_file) => {
+                    return Ok(Self {
+                        _lock_file: lock_file,
+                        lock_file_path,
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if start.elapsed() > STALE_LOCK_TIMEOUT {
+                        let _ = fs::remove_file(&lock_file_path);
+                    } else {
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl Drop for HistoryLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.lock_file_path);
+    }
+}
+
+#[test]
+fn suggest_test() {
+    let history = History {
+        history: vec![
+            "let a = 1;".into(),
+            "let ab = 2;".into(),
+            "fn f() {\n}".into(),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(history.suggest("let a"), Some("b = 2;"));
+    assert_eq!(history.suggest("let a = "), Some("1;"));
+    assert_eq!(history.suggest("fn"), None);
+    assert_eq!(history.suggest(""), None);
+}
@@ -0,0 +1,120 @@
+use super::{IRust, Result};
+
+/// One indentation level
+const INDENT: &str = "    ";
+
+/// The current line up to the cursor
+fn current_line(before_cursor: &[char]) -> &[char] {
+    let line_start = before_cursor
+        .iter()
+        .rposition(|c| *c == '\n')
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    &before_cursor[line_start..]
+}
+
+/// Indentation of a new line: the current line indentation, plus one level after `{`
+fn newline_indent(before_cursor: &[char]) -> String {
+    let line = current_line(before_cursor);
+    let mut indent: String = line.iter().take_while(|c| c.is_whitespace()).collect();
+    if line.iter().rev().find(|c| !c.is_whitespace()) == Some(&'{') {
+        indent.push_str(INDENT);
+    }
+    indent
+}
+
+/// Indentation of a `}` typed on a blank line: the one of the line that opened its block
+fn closing_brace_indent(before_cursor: &[char]) -> Option<String> {
+    if !current_line(before_cursor)
+        .iter()
+        .all(|c| c.is_whitespace())
+    {
+        return None;
+    }
+    let mut depth = 0;
+    let open_idx = before_cursor.iter().rposition(|c| match c {
+        '}' => {
+            depth += 1;
+            false
+        }
+        '{' if depth == 0 => true,
+        '{' => {
+            depth -= 1;
+            false
+        }
+        _ => false,
+    })?;
+    Some(
+        current_line(&before_cursor[..open_idx])
+            .iter()
+            .take_while(|c| c.is_whitespace())
+            .collect(),
+    )
+}
+
+impl IRust {
+    /// Insert a line break, indented like the current line, and one level more inside a new block
+    ///
+    /// `{|}` becomes a block with the cursor on its own line
+    pub fn insert_newline(&mut self) -> Result<()> {
+        // pasted code is already indented
+        if !self.options.auto_indent || self.input_is_queued() {
+            self.buffer.insert('\n');
+        } else {
+            let before_cursor = &self.buffer.buffer[..self.buffer.buffer_pos];
+            let indent = newline_indent(before_cursor);
+            let opens_block =
+                before_cursor.last() == Some(&'{') && self.buffer.current_char() == Some(&'}');
+            let outer_indent: String = current_line(before_cursor)
+                .iter()
+                .take_while(|c| c.is_whitespace())
+                .collect();
+
+            self.buffer.insert('\n');
+            self.buffer.insert_str(&indent);
+            if opens_block {
+                let cursor = self.buffer.buffer_pos;
+                self.buffer.insert('\n');
+                self.buffer.insert_str(&outer_indent);
+                self.buffer.set_buffer_pos(cursor);
+            }
+        }
+        self.print_input()?;
+        self.printer.cursor.goto_buffer_pos(&self.buffer);
+        Ok(())
+    }
+
+    /// Align a `}` typed on a blank line with the line that opened its block
+    pub fn dedent_before_closing_brace(&mut self) {
+        if !self.options.auto_indent {
+            return;
+        }
+        let before_cursor = &self.buffer.buffer[..self.buffer.buffer_pos];
+        if let Some(indent) = closing_brace_indent(before_cursor) {
+            let end = self.buffer.buffer_pos;
+            self.buffer
+                .remove_range(end - current_line(before_cursor).len(), end);
+            self.buffer.insert_str(&indent);
+        }
+    }
+}
+
+#[test]
+fn indent_test() {
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    assert_eq!(newline_indent(&chars("fn a() {")), INDENT);
+    assert_eq!(newline_indent(&chars("{\n    let a = 1;")), INDENT);
+    assert_eq!(newline_indent(&chars("{\n    if a { ")), INDENT.repeat(2));
+    assert_eq!(newline_indent(&chars("let a = 1;")), "");
+
+    assert_eq!(
+        closing_brace_indent(&chars("fn a() {\n    if b {\n    }\n        ")),
+        Some(String::new())
+    );
+    assert_eq!(
+        closing_brace_indent(&chars("  {\n    {}\n   \t")),
+        Some("  ".to_string())
+    );
+    assert_eq!(closing_brace_indent(&chars("{\n    a")), None);
+    assert_eq!(closing_brace_indent(&chars("    ")), None);
+}
@@ -0,0 +1,235 @@
+//! Turn key events into the chars they stand for before they are handled
+use super::{IRust, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use std::time::Duration;
+
+/// (base, combining mark, precomposed char)
+const COMPOSITIONS: &[(char, char, char)] = &[
+    // grave
+    ('A', '\u{300}', 'À'),
+    ('E', '\u{300}', 'È'),
+    ('I', '\u{300}', 'Ì'),
+    ('O', '\u{300}', 'Ò'),
+    ('U', '\u{300}', 'Ù'),
+    ('a', '\u{300}', 'à'),
+    ('e', '\u{300}', 'è'),
+    ('i', '\u{300}', 'ì'),
+    ('o', '\u{300}', 'ò'),
+    ('u', '\u{300}', 'ù'),
+    // acute
+    ('A', '\u{301}', 'Á'),
+    ('C', '\u{301}', 'Ć'),
+    ('E', '\u{301}', 'É'),
+    ('I', '\u{301}', 'Í'),
+    ('L', '\u{301}', 'Ĺ'),
+    ('N', '\u{301}', 'Ń'),
+    ('O', '\u{301}', 'Ó'),
+    ('R', '\u{301}', 'Ŕ'),
+    ('S', '\u{301}', 'Ś'),
+    ('U', '\u{301}', 'Ú'),
+    ('Y', '\u{301}', 'Ý'),
+    ('Z', '\u{301}', 'Ź'),
+    ('a', '\u{301}', 'á'),
+    ('c', '\u{301}', 'ć'),
+    ('e', '\u{301}', 'é'),
+    ('i', '\u{301}', 'í'),
+    ('l', '\u{301}', 'ĺ'),
+    ('n', '\u{301}', 'ń'),
+    ('o', '\u{301}', 'ó'),
+    ('r', '\u{301}', 'ŕ'),
+    ('s', '\u{301}', 'ś'),
+    ('u', '\u{301}', 'ú'),
+    ('y', '\u{301}', 'ý'),
+    ('z', '\u{301}', 'ź'),
+    // circumflex
+    ('A', '\u{302}', 'Â'),
+    ('C', '\u{302}', 'Ĉ'),
+    ('E', '\u{302}', 'Ê'),
+    ('G', '\u{302}', 'Ĝ'),
+    ('H', '\u{302}', 'Ĥ'),
+    ('I', '\u{302}', 'Î'),
+    ('J', '\u{302}', 'Ĵ'),
+    ('O', '\u{302}', 'Ô'),
+    ('S', '\u{302}', 'Ŝ'),
+    ('U', '\u{302}', 'Û'),
+    ('W', '\u{302}', 'Ŵ'),
+    ('Y', '\u{302}', 'Ŷ'),
+    ('a', '\u{302}', 'â'),
+    ('c', '\u{302}', 'ĉ'),
+    ('e', '\u{302}', 'ê'),
+    ('g', '\u{302}', 'ĝ'),
+    ('h', '\u{302}', 'ĥ'),
+    ('i', '\u{302}', 'î'),
+    ('j', '\u{302}', 'ĵ'),
+    ('o', '\u{302}', 'ô'),
+    ('s', '\u{302}', 'ŝ'),
+    ('u', '\u{302}', 'û'),
+    ('w', '\u{302}', 'ŵ'),
+    ('y', '\u{302}', 'ŷ'),
+    // tilde
+    ('A', '\u{303}', 'Ã'),
+    ('I', '\u{303}', 'Ĩ'),
+    ('N', '\u{303}', 'Ñ'),
+    ('O', '\u{303}', 'Õ'),
+    ('U', '\u{303}', 'Ũ'),
+    ('a', '\u{303}', 'ã'),
+    ('i', '\u{303}', 'ĩ'),
+    ('n', '\u{303}', 'ñ'),
+    ('o', '\u{303}', 'õ'),
+    ('u', '\u{303}', 'ũ'),
+    // diaeresis
+    ('A', '\u{308}', 'Ä'),
+    ('E', '\u{308}', 'Ë'),
+    ('I', '\u{308}', 'Ï'),
+    ('O', '\u{308}', 'Ö'),
+    ('U', '\u{308}', 'Ü'),
+    ('Y', '\u{308}', 'Ÿ'),
+    ('a', '\u{308}', 'ä'),
+    ('e', '\u{308}', 'ë'),
+    ('i', '\u{308}', 'ï'),
+    ('o', '\u{308}', 'ö'),
+    ('u', '\u{308}', 'ü'),
+    ('y', '\u{308}', 'ÿ'),
+    // ring above
+    ('A', '\u{30a}', 'Å'),
+    ('U', '\u{30a}', 'Ů'),
+    ('a', '\u{30a}', 'å'),
+    ('u', '\u{30a}', 'ů'),
+    // caron
+    ('C', '\u{30c}', 'Č'),
+    ('D', '\u{30c}', 'Ď'),
+    ('E', '\u{30c}', 'Ě'),
+    ('L', '\u{30c}', 'Ľ'),
+    ('N', '\u{30c}', 'Ň'),
+    ('R', '\u{30c}', 'Ř'),
+    ('S', '\u{30c}', 'Š'),
+    ('T', '\u{30c}', 'Ť'),
+    ('Z', '\u{30c}', 'Ž'),
+    ('c', '\u{30c}', 'č'),
+    ('d', '\u{30c}', 'ď'),
+    ('e', '\u{30c}', 'ě'),
+    ('l', '\u{30c}', 'ľ'),
+    ('n', '\u{30c}', 'ň'),
+    ('r', '\u{30c}', 'ř'),
+    ('s', '\u{30c}', 'š'),
+    ('t', '\u{30c}', 'ť'),
+    ('z', '\u{30c}', 'ž'),
+    // cedilla
+    ('C', '\u{327}', 'Ç'),
+    ('G', '\u{327}', 'Ģ'),
+    ('K', '\u{327}', 'Ķ'),
+    ('L', '\u{327}', 'Ļ'),
+    ('N', '\u{327}', 'Ņ'),
+    ('R', '\u{327}', 'Ŗ'),
+    ('S', '\u{327}', 'Ş'),
+    ('T', '\u{327}', 'Ţ'),
+    ('c', '\u{327}', 'ç'),
+    ('g', '\u{327}', 'ģ'),
+    ('k', '\u{327}', 'ķ'),
+    ('l', '\u{327}', 'ļ'),
+    ('n', '\u{327}', 'ņ'),
+    ('r', '\u{327}', 'ŗ'),
+    ('s', '\u{327}', 'ş'),
+    ('t', '\u{327}', 'ţ'),
+];
+
+fn compose(base: char, mark: char) -> Option<char> {
+    COMPOSITIONS
+        .iter()
+        .find(|(b, m, _)| *b == base && *m == mark)
+        .map(|(_, _, composed)| *composed)
+}
+
+/// The char typed with this key event, if any
+///
+/// AltGr arrives as Ctrl+Alt on windows, and non ascii chars can come with Alt from
+/// layouts that use it as a compose key
+fn typed_char(key_event: &KeyEvent) -> Option<char> {
+    match key_event.code {
+        KeyCode::Char(c)
+            if key_event.modifiers == KeyModifiers::NONE
+                || key_event.modifiers == KeyModifiers::SHIFT
+                || key_event
+                    .modifiers
+                    .contains(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                || key_event.modifiers.contains(KeyModifiers::ALT) && !c.is_ascii() =>
+        {
+            Some(c)
+        }
+        _ => None,
+    }
+}
+
+impl IRust {
+    /// Read the next event, typed chars are normalized with `normalize_char`
+    pub fn read_input_event(&mut self) -> Result<Event> {
+        let event = match self.pending_events.pop_front() {
+            Some(event) => event,
+            None => crossterm::event::read()?,
+        };
+        match event {
+            Event::Key(key_event) => match typed_char(&key_event) {
+                Some(c) => Ok(Event::Key(KeyEvent {
+                    code: KeyCode::Char(self.normalize_char(c)?),
+                    modifiers: KeyModifiers::NONE,
+                })),
+                None => Ok(event),
+            },
+            event => Ok(event),
+        }
+    }
+
+    /// An event was read ahead and still needs to be handled
+    pub fn has_pending_event(&self) -> bool {
+        !self.pending_events.is_empty()
+    }
+
+    /// More input arrived in the same burst, exp: pasted text
+    pub fn input_is_queued(&self) -> bool {
+        self.has_pending_event()
+            || crossterm::event::poll(Duration::from_millis(0)).unwrap_or(false)
+    }
+
+    /// Merge the combining marks that follow `c` in the same burst of input into it
+    ///
+    /// The first event that doesn't combine is kept for the next read
+    fn normalize_char(&mut self, mut c: char) -> Result<char> {
+        // events read ahead come before the ones still waiting to be read
+        while !self.has_pending_event() && crossterm::event::poll(Duration::from_millis(0))? {
+            let event = crossterm::event::read()?;
+            let composed = match &event {
+                Event::Key(key_event) => typed_char(key_event).and_then(|mark| compose(c, mark)),
+                _ => None,
+            };
+            match composed {
+                Some(composed) => c = composed,
+                None => {
+                    self.pending_events.push_back(event);
+                    break;
+                }
+            }
+        }
+        Ok(c)
+    }
+}
+
+#[test]
+fn compose_test() {
+    assert_eq!(compose('e', '\u{301}'), Some('é'));
+    assert_eq!(compose('N', '\u{303}'), Some('Ñ'));
+    assert_eq!(compose('x', '\u{301}'), None);
+    assert_eq!(
+        typed_char(&KeyEvent {
+            code: KeyCode::Char('@'),
+            modifiers: KeyModifiers::CONTROL | KeyModifiers::ALT
+        }),
+        Some('@')
+    );
+    assert_eq!(
+        typed_char(&KeyEvent {
+            code: KeyCode::Char('b'),
+            modifiers: KeyModifiers::ALT
+        }),
+        None
+    );
+}
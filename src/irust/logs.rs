@@ -0,0 +1,182 @@
+//! Log lines of the evaluated code (`log`/`tracing`..) found in its stderr, colored by level and sent to `log_sink`
+use super::{IRust, Result};
+use crate::utils::{expand_home, strip_ansi_escapes};
+use crossterm::style::Color;
+use printer::printer::{PrintQueue, PrinterItem};
+use std::io::Write;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn parse(s: &str) -> Option<Self> {
+        // lowercase words are too common in ordinary messages
+        match s {
+            "ERROR" => Some(Level::Error),
+            "WARN" | "WARNING" => Some(Level::Warn),
+            "INFO" => Some(Level::Info),
+            "DEBUG" => Some(Level::Debug),
+            "TRACE" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Level::Error => Color::Red,
+            Level::Warn => Color::Yellow,
+            Level::Info => Color::Green,
+            Level::Debug => Color::Blue,
+            Level::Trace => Color::Magenta,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct LogLine {
+    text: String,
+    level: Level,
+    /// Byte range of the level in `text`
+    level_range: std::ops::Range<usize>,
+}
+
+/// Find the level among the first words of the line, this covers the common formats:
+///
+/// `[2021-05-01T10:00:00Z INFO  my_crate] msg` (env_logger)\
+/// `2021-05-01T10:00:00.000000Z  INFO my_crate: msg` (tracing-subscriber)\
+/// ` INFO  my_crate > msg` (pretty_env_logger), `INFO:my_crate: msg`, `[WARN] msg`..
+fn log_line(line: &str) -> Option<LogLine> {
+    let text = strip_ansi_escapes(line);
+    let (level_range, level) = words(&text).take(3).find_map(|(start, word)| {
+        let trimmed = word.trim_start_matches('[');
+        let start = start + word.len() - trimmed.len();
+        let level = trimmed.split([']', ':']).next()?;
+        Some((start..start + level.len(), Level::parse(level)?))
+    })?;
+    Some(LogLine {
+        text,
+        level,
+        level_range,
+    })
+}
+
+/// Whitespace separated words with their byte offset
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// Separate the log lines from the rest of stderr
+fn split_logs(stderr: &str) -> (Vec<LogLine>, String) {
+    let mut logs = vec![];
+    let mut rest = String::new();
+    for line in stderr.lines() {
+        match log_line(line) {
+            Some(log) => logs.push(log),
+            None => {
+                rest.push_str(line);
+                rest.push('\n');
+            }
+        }
+    }
+    (logs, rest)
+}
+
+impl IRust {
+    /// Send the log lines of `stderr` to `log_sink`, returns the rest of stderr and the logs to print inline
+    pub fn route_logs(&mut self, stderr: &str) -> Result<(String, PrintQueue)> {
+        let (logs, rest) = split_logs(stderr);
+        let mut inline = PrintQueue::default();
+        if logs.is_empty() {
+            return Ok((rest, inline));
+        }
+
+        match self.options.log_sink.as_str() {
+            "split_view" if self.options.split_view => {
+                // all of stderr goes to the pane, in order
+                for line in stderr.lines() {
+                    match log_line(line) {
+                        Some(log) => self
+                            .printer
+                            .push_colored_to_top_pane(&log.text, Some(log.level.color()))?,
+                        None => self.printer.push_to_top_pane(line)?,
+                    }
+                }
+                return Ok((String::new(), inline));
+            }
+            "" | "inline" | "split_view" => {
+                for log in logs {
+                    let color = log.level.color();
+                    let (prefix, rest) = log.text.split_at(log.level_range.start);
+                    let (level, message) = rest.split_at(log.level_range.len());
+                    inline.push(PrinterItem::String(prefix.to_string(), Color::DarkGrey));
+                    inline.push(PrinterItem::String(level.to_string(), color));
+                    inline.push(PrinterItem::String(
+                        message.to_string(),
+                        self.options.eval_color,
+                    ));
+                    inline.add_new_line(1);
+                }
+            }
+            path => {
+                let path = expand_home(path);
+                let written = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| {
+                        logs.iter()
+                            .try_for_each(|log| writeln!(file, "{}", log.text))
+                    });
+                if let Err(e) = written {
+                    inline.push(PrinterItem::String(
+                        format!("Failed to write the logs to {}: {}", path.display(), e),
+                        self.options.irust_warn_color,
+                    ));
+                    inline.add_new_line(1);
+                }
+            }
+        }
+        Ok((rest, inline))
+    }
+}
+
+#[test]
+fn log_line_test() {
+    let level = |line: &str| {
+        log_line(line).map(|log| {
+            assert_eq!(
+                Level::parse(&log.text[log.level_range.clone()]),
+                Some(log.level)
+            );
+            log.level
+        })
+    };
+    assert_eq!(
+        level("[2021-05-01T10:00:00Z INFO  my_crate] started"),
+        Some(Level::Info)
+    );
+    assert_eq!(
+        level("2021-05-01T10:00:00.000000Z  WARN my_crate: slow"),
+        Some(Level::Warn)
+    );
+    assert_eq!(
+        level("\x1b[2m2021-05-01T10:00:00Z\x1b[0m \x1b[31mERROR\x1b[0m my_crate: failed"),
+        Some(Level::Error)
+    );
+    assert_eq!(level(" DEBUG my_crate > value"), Some(Level::Debug));
+    assert_eq!(level("TRACE:my_crate: enter"), Some(Level::Trace));
+    assert_eq!(level("[WARN] deprecated"), Some(Level::Warn));
+    assert_eq!(level("some info about an ERROR"), None);
+    assert_eq!(level("thread 'main' panicked at 'INFO'"), None);
+
+    let (logs, rest) = split_logs("[INFO my_crate] a\nplain\n");
+    assert_eq!(logs.len(), 1);
+    assert_eq!(rest, "plain\n");
+}
@@ -0,0 +1,69 @@
+//! The `irust_meta` module of the session crate, it exposes the REPL state to the evaluated code
+use super::cargo_cmds::META_FILE;
+use super::global_variables::{GlobalVariables, LastEval};
+use super::{IRust, Result};
+
+/// Declared after main so the line numbers of the session code don't change
+pub const META_MOD: &str = "mod irust_meta;";
+
+fn meta_module(global_variables: &GlobalVariables, last_eval: &LastEval) -> String {
+    let last_eval_duration = match last_eval.duration {
+        Some(duration) => format!(
+            "Some(std::time::Duration::from_nanos({}))",
+            duration.as_nanos()
+        ),
+        None => "None".to_string(),
+    };
+    let last_output = match global_variables.get_last_output() {
+        Some(output) => format!("Some({:?})", output.trim_end()),
+        None => "None".to_string(),
+    };
+    format!(
+        r#"//! Generated by IRust before each evaluation
+#![allow(dead_code)]
+
+/// Number of the input being evaluated, `In [N]`
+pub const OPERATION_NUMBER: usize = {};
+/// How long the previous evaluation took
+pub const LAST_EVAL_DURATION: Option<std::time::Duration> = {};
+/// IRust current directory, changed with `:cd`
+pub const CWD: &str = {:?};
+/// Output of the last successful evaluation
+pub const LAST_OUTPUT: Option<&str> = {};
+"#,
+        global_variables.operation_number,
+        last_eval_duration,
+        global_variables.get_cwd().display().to_string(),
+        last_output
+    )
+}
+
+/// Write the module with the initial state, for sessions that don't update it
+pub fn write_default_meta() -> std::io::Result<()> {
+    std::fs::write(
+        &*META_FILE,
+        meta_module(&GlobalVariables::new(), &LastEval::default()),
+    )
+}
+
+impl IRust {
+    /// Write the current state to `irust_meta`, it's part of the next build
+    pub fn update_meta(&self) -> Result<()> {
+        std::fs::write(
+            &*META_FILE,
+            meta_module(&self.global_variables, &self.last_eval),
+        )?;
+        Ok(())
+    }
+}
+
+#[test]
+fn meta_module_test() {
+    let mut global_variables = GlobalVariables::new();
+    global_variables.operation_number = 4;
+    global_variables.set_last_output("\"a\\\"b\"\n".to_string());
+    let module = meta_module(&global_variables, &LastEval::default());
+    assert!(module.contains("pub const OPERATION_NUMBER: usize = 4;"));
+    assert!(module.contains("pub const LAST_EVAL_DURATION: Option<std::time::Duration> = None;"));
+    assert!(module.contains(r#"pub const LAST_OUTPUT: Option<&str> = Some("\"a\\\"b\"");"#));
+}
@@ -0,0 +1,222 @@
+//! `irust eval`: evaluate a single expression without the interactive interface
+use super::cargo_cmds::{cargo_build_output, EXE_PATH, SEPARATE_SESSION};
+use super::options::Options;
+use super::repl::{uses_await, Repl};
+use super::Result;
+use std::process::{Command, Stdio};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+/// Printed before the expression value, to separate it from the program output
+const RESULT_MARK: &str = "\u{1}IRUST_RESULT\u{1}";
+
+#[derive(Debug, PartialEq)]
+struct EvalArgs {
+    /// `cargo add` arguments
+    deps: Vec<String>,
+    expression: String,
+    json: bool,
+}
+
+struct Evaluation {
+    stdout: String,
+    stderr: String,
+    /// Debug representation of the expression value, `None` for statements or if it failed
+    result: Option<String>,
+    success: bool,
+    /// 1 if the expression doesn't compile, the program exit code otherwise
+    exit_code: i32,
+}
+
+/// Returns the process exit code
+pub fn eval(args: &[String], options: &Options) -> i32 {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    let start = Instant::now();
+    let evaluation = match evaluate(&args, options) {
+        Ok(evaluation) => evaluation,
+        Err(e) => Evaluation {
+            stdout: String::new(),
+            stderr: e.to_string(),
+            result: None,
+            success: false,
+            exit_code: 1,
+        },
+    };
+
+    if args.json {
+        println!(
+            "{{\"input\": {}, \"stdout\": {}, \"stderr\": {}, \"result\": {}, \"duration_ms\": {}, \"success\": {}}}",
+            json_string(&args.expression),
+            json_string(&evaluation.stdout),
+            json_string(&evaluation.stderr),
+            evaluation
+                .result
+                .as_deref()
+                .map(json_string)
+                .unwrap_or_else(|| "null".to_string()),
+            start.elapsed().as_millis(),
+            evaluation.success
+        );
+    } else {
+        print!("{}", evaluation.stdout);
+        eprint!("{}", evaluation.stderr);
+        if let Some(result) = evaluation.result {
+            println!("{}", result);
+        }
+    }
+    evaluation.exit_code
+}
+
+fn evaluate(args: &EvalArgs, options: &Options) -> Result<Evaluation> {
+    let failure = |stderr: String| Evaluation {
+        stdout: String::new(),
+        stderr,
+        result: None,
+        success: false,
+        exit_code: 1,
+    };
+
+    // don't disturb a running interactive session
+    SEPARATE_SESSION.store(true, Ordering::Relaxed);
+    options.apply_default_deps();
+    let mut repl = Repl::new();
+    repl.set_prelude(options.prelude.clone());
+    repl.prepare_ground(options.toolchain)?;
+
+    let mut deps_args = vec![];
+    if !args.deps.is_empty() {
+        deps_args.push(args.deps.clone());
+    }
+    if uses_await(&args.expression) {
+        repl.async_runtime = Some(options.async_runtime);
+        deps_args.push(options.async_runtime.dependency());
+    }
+    for deps in deps_args {
+        let output = repl.add_dep(&deps)?.wait_with_output()?;
+        if !output.status.success() {
+            return Ok(failure(String::from_utf8_lossy(&output.stderr).to_string()));
+        }
+    }
+
+    // statements are run as is, expressions are printed after the result mark
+    let is_statement = args.expression.trim_end().ends_with(';');
+    let input = if is_statement {
+        args.expression.clone()
+    } else {
+        format!(
+            "println!(\"{}{{:?}}\", {{\n{}\n}});",
+            RESULT_MARK, args.expression
+        )
+    };
+    repl.insert(input);
+    repl.write()?;
+
+    let (status, output) = cargo_build_output(false, false, options.toolchain)?;
+    if !status.success() {
+        return Ok(failure(output));
+    }
+
+    let output = Command::new(&*EXE_PATH).stdin(Stdio::inherit()).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let (stdout, result) = match stdout.rsplit_once(RESULT_MARK) {
+        Some((stdout, result)) => (
+            stdout.to_string(),
+            Some(result.strip_suffix('\n').unwrap_or(result).to_string()),
+        ),
+        None => (stdout, None),
+    };
+
+    Ok(Evaluation {
+        stdout,
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        result,
+        success: output.status.success(),
+        exit_code: output.status.code().unwrap_or(1),
+    })
+}
+
+/// `[--dep <name[=version]>].. [--output text|json] <expression>`
+fn parse_args(args: &[String]) -> Result<EvalArgs> {
+    let mut deps = vec![];
+    let mut expression = vec![];
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dep" => {
+                let dep = args.next().ok_or("--dep requires a crate name")?;
+                // cargo-edit syntax is name@version
+                deps.push(dep.replacen('=', "@", 1));
+            }
+            "--output" => {
+                json = match args.next().map(String::as_str) {
+                    Some("json") => true,
+                    Some("text") => false,
+                    _ => return Err("--output accepts `text` or `json`".into()),
+                };
+            }
+            _ => expression.push(arg.as_str()),
+        }
+    }
+    if expression.is_empty() {
+        return Err(
+            "No expression specified, usage: irust eval [--dep name=version].. [--output text|json] <expression>"
+                .into(),
+        );
+    }
+    Ok(EvalArgs {
+        deps,
+        expression: expression.join(" "),
+        json,
+    })
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[test]
+fn parse_args_test() {
+    let args: Vec<String> = [
+        "--dep", "serde=1", "--dep", "rand", "--output", "json", "1 +", "1",
+    ]
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+    assert_eq!(
+        parse_args(&args).unwrap(),
+        EvalArgs {
+            deps: vec!["serde@1".to_string(), "rand".to_string()],
+            expression: "1 + 1".to_string(),
+            json: true
+        }
+    );
+    assert!(parse_args(&args[..2]).is_err());
+    assert!(parse_args(&args[4..5]).is_err());
+}
+
+#[test]
+fn json_string_test() {
+    assert_eq!(json_string("a \"b\"\n\\\u{1}"), r#""a \"b\"\n\\\u0001""#);
+}
@@ -1,12 +1,22 @@
-use crate::irust::{cargo_cmds::ToolChain, IRust, Result};
+use crate::irust::{
+    cargo_cmds::ToolChain, highlight::HighlightEngine, repl::AsyncRuntime, terminal::Background,
+    IRust, Result,
+};
+use crate::utils::glob_match;
 use crossterm::style::Color;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Options {
     add_irust_cmd_to_history: bool,
     add_shell_cmd_to_history: bool,
+    pub history_max_entries: usize,
+    pub history_ignore: Vec<String>,
+    pub share_history: bool,
+    pub history_autosuggest: bool,
+    pub history_suggestion_color: Color,
     pub ok_color: Color,
     pub eval_color: Color,
     pub irust_color: Color,
@@ -18,20 +28,76 @@ pub struct Options {
     pub insert_color: Color,
     pub welcome_msg: String,
     pub welcome_color: Color,
+    /// Text file shown as is instead of `welcome_msg`, exp: a figlet art
+    pub welcome_file: String,
+    /// Set by `--no-banner` for this session only
+    #[serde(skip)]
+    pub no_banner: bool,
+    /// Files given on the command line, loaded before the first prompt
+    #[serde(skip)]
+    pub startup_files: Vec<std::path::PathBuf>,
+    /// Colors changed by `adapt_colors_to`: (index in `adaptable_colors`, configured color)
+    #[serde(skip)]
+    adapted_colors: Vec<(usize, Color)>,
     pub racer_inline_suggestion_color: Color,
     pub racer_suggestions_table_color: Color,
     pub racer_selected_suggestion_color: Color,
     pub racer_max_suggestions: usize,
+    pub completion_panel: bool,
+    pub completion_panel_rows: usize,
+    pub signature_help: bool,
+    pub signature_help_color: Color,
+    pub signature_help_param_color: Color,
+    pub check_as_you_type: bool,
+    pub check_as_you_type_delay: u64,
+    pub diagnostic_color: Color,
     pub first_irust_run: bool,
     pub enable_racer: bool,
     pub toolchain: ToolChain,
+    pub async_runtime: AsyncRuntime,
+    pub debugger: String,
+    pub eval_timeout: u64,
+    pub eval_memory_limit: u64,
+    pub eval_release: bool,
+    pub shared_target_dir: String,
+    pub sccache: bool,
+    pub default_deps: Vec<String>,
+    pub prelude: Vec<String>,
+    pub auto_add_deps: bool,
     pub check_statements: bool,
+    /// Show a re-evaluated input output as a diff against its previous output
+    pub diff_reruns: bool,
     pub auto_insert_semicolon: bool,
     pub replace_marker: String,
     pub replace_output_with_marker: bool,
     pub input_prompt: String,
     pub output_prompt: String,
+    pub prompt_color: Color,
+    pub prompt_error_color: Color,
     pub activate_scripting: bool,
+    pub highlight_engine: HighlightEngine,
+    pub semantic_highlighting: bool,
+    pub background: Background,
+    pub status_line: bool,
+    pub status_line_color: Color,
+    pub split_view: bool,
+    pub split_view_rows: u16,
+    pub split_view_color: Color,
+    /// Where the log lines of the evaluated code go: `inline`, `split_view` or a file path
+    pub log_sink: String,
+    pub alternate_screen: bool,
+    /// Where the session transcript goes when leaving the alternate screen: `none`, `screen` or a file path
+    pub exit_transcript: String,
+    pub auto_close_brackets: bool,
+    pub auto_indent: bool,
+    pub highlight_matching_bracket: bool,
+    pub matching_bracket_color: Color,
+    /// Target => command that runs the programs built for it, used by `:target`
+    ///
+    /// The tables are last so they're serialized after the other options
+    pub target_runners: BTreeMap<String, String>,
+    /// Word => text it expands to on Tab, `$0` marks the cursor position
+    pub snippets: BTreeMap<String, String>,
 }
 
 impl Default for Options {
@@ -40,6 +106,11 @@ impl Default for Options {
             // [Histroy]
             add_irust_cmd_to_history: true,
             add_shell_cmd_to_history: false,
+            history_max_entries: 0,
+            history_ignore: vec![],
+            share_history: true,
+            history_autosuggest: true,
+            history_suggestion_color: Color::DarkGrey,
 
             // [Colors]
             ok_color: Color::Blue,
@@ -55,6 +126,10 @@ impl Default for Options {
             // [Welcome]
             welcome_msg: String::new(),
             welcome_color: Color::DarkBlue,
+            welcome_file: String::new(),
+            no_banner: false,
+            startup_files: vec![],
+            adapted_colors: vec![],
 
             // [Racer]
             enable_racer: true,
@@ -62,32 +137,124 @@ impl Default for Options {
             racer_suggestions_table_color: Color::Green,
             racer_selected_suggestion_color: Color::DarkRed,
             racer_max_suggestions: 5,
+            completion_panel: false,
+            completion_panel_rows: 5,
+            signature_help: true,
+            signature_help_color: Color::DarkGrey,
+            signature_help_param_color: Color::Cyan,
+            check_as_you_type: false,
+            check_as_you_type_delay: 500,
+            diagnostic_color: Color::Red,
 
             //other
             first_irust_run: true,
             toolchain: ToolChain::Stable,
+            async_runtime: AsyncRuntime::Tokio,
+            debugger: "rust-gdb".to_string(),
+            eval_timeout: 0,
+            eval_memory_limit: 0,
+            eval_release: false,
+            shared_target_dir: String::new(),
+            sccache: false,
+            default_deps: vec![],
+            prelude: vec![],
+            auto_add_deps: false,
             check_statements: true,
+            diff_reruns: false,
             auto_insert_semicolon: true,
 
             // replace output
             replace_marker: "$out".into(),
             replace_output_with_marker: false,
 
-            input_prompt: "In: ".to_string(),
-            output_prompt: "Out: ".to_string(),
+            input_prompt: "In [{op}]: ".to_string(),
+            output_prompt: "Out[{op}]: ".to_string(),
+            prompt_color: Color::Yellow,
+            prompt_error_color: Color::DarkRed,
             activate_scripting: false,
+            highlight_engine: HighlightEngine::Classic,
+            semantic_highlighting: true,
+            background: Background::Auto,
+            status_line: false,
+            status_line_color: Color::DarkGrey,
+            split_view: false,
+            split_view_rows: 8,
+            split_view_color: Color::DarkYellow,
+            log_sink: "inline".to_string(),
+            alternate_screen: false,
+            exit_transcript: "none".to_string(),
+
+            // [Brackets]
+            auto_close_brackets: false,
+            auto_indent: true,
+            highlight_matching_bracket: true,
+            matching_bracket_color: Color::Magenta,
+
+            // [Target runners]
+            target_runners: [
+                ("wasm32-wasi", "wasmtime run --dir=."),
+                ("wasm32-wasip1", "wasmtime run --dir=."),
+            ]
+            .iter()
+            .map(|(target, runner)| (target.to_string(), runner.to_string()))
+            .collect(),
+
+            // [Snippets]
+            snippets: [("fori", "for i in 0..$0 {\n}\n")]
+                .iter()
+                .map(|(word, snippet)| (word.to_string(), snippet.to_string()))
+                .collect(),
         }
     }
 }
 
 impl Options {
+    /// Make the evaluation limits and compile mode visible to the code building and running the evaluated binary
+    pub fn apply_eval_limits(&self) {
+        use super::cargo_cmds::{EVAL_MEMORY_LIMIT, EVAL_RELEASE, EVAL_TIMEOUT};
+        use std::sync::atomic::Ordering;
+        EVAL_TIMEOUT.store(self.eval_timeout, Ordering::Relaxed);
+        EVAL_MEMORY_LIMIT.store(self.eval_memory_limit, Ordering::Relaxed);
+        EVAL_RELEASE.store(self.eval_release, Ordering::Relaxed);
+    }
+
+    /// Make the session crate use the shared target directory and sccache
+    ///
+    /// Needs to be called before the session crate is created, the target directory can't change afterwards
+    pub fn apply_build_cache(&self) {
+        use super::cargo_cmds::{SHARED_TARGET_DIR, USE_SCCACHE};
+        use std::sync::atomic::Ordering;
+        if !self.shared_target_dir.is_empty() {
+            let dir = match self.shared_target_dir.strip_prefix("~/") {
+                Some(path) => dirs_next::home_dir().unwrap_or_default().join(path),
+                None => std::path::PathBuf::from(&self.shared_target_dir),
+            };
+            let _ = SHARED_TARGET_DIR.set(dir);
+        }
+        USE_SCCACHE.store(self.sccache, Ordering::Relaxed);
+    }
+
+    /// Make `default_deps` part of the next sessions Cargo.toml
+    pub fn apply_default_deps(&self) {
+        *super::cargo_cmds::DEFAULT_DEPS
+            .lock()
+            .expect("poisoned lock") = self.default_deps.clone();
+    }
+
     pub fn save(&mut self) -> Result<()> {
         if let Some(path) = Self::config_path() {
-            Self::write_config_file(path, &self)?;
+            Self::write_config_file(path, &self.configured())?;
         }
         Ok(())
     }
 
+    /// The options without the colors adapted to the terminal, which are only for this terminal
+    pub fn configured(&self) -> Self {
+        let mut options = self.clone();
+        options.restore_adapted_colors();
+        options
+    }
+
     pub fn new() -> Result<Self> {
         if let Some(config_path) = Options::config_path() {
             let mut config_file = std::fs::File::open(&config_path)?;
@@ -104,6 +271,47 @@ impl Options {
         *self = Self::default();
     }
 
+    /// Swap the default colors that are hard to read on a light background (and back),
+    /// the colors set by the user are left untouched
+    pub fn adapt_colors_to(&mut self, background: Background) {
+        self.restore_adapted_colors();
+        if background != Background::Light {
+            return;
+        }
+        let defaults = Self::default().adaptable_colors().map(|color| *color);
+        let mut adapted = vec![];
+        for (idx, color) in self.adaptable_colors().iter_mut().enumerate() {
+            if let Some(light) = light_color(**color).filter(|_| **color == defaults[idx]) {
+                adapted.push((idx, **color));
+                **color = light;
+            }
+        }
+        self.adapted_colors = adapted;
+    }
+
+    /// Undo `adapt_colors_to`, the colors changed since then are kept
+    fn restore_adapted_colors(&mut self) {
+        let adapted = std::mem::take(&mut self.adapted_colors);
+        let colors = self.adaptable_colors();
+        for (idx, configured) in adapted {
+            if Some(*colors[idx]) == light_color(configured) {
+                *colors[idx] = configured;
+            }
+        }
+    }
+
+    fn adaptable_colors(&mut self) -> [&mut Color; 7] {
+        [
+            &mut self.eval_color,
+            &mut self.insert_color,
+            &mut self.input_color,
+            &mut self.prompt_color,
+            &mut self.irust_warn_color,
+            &mut self.racer_inline_suggestion_color,
+            &mut self.racer_suggestions_table_color,
+        ]
+    }
+
     pub fn config_path() -> Option<std::path::PathBuf> {
         let config_dir = match dirs_next::config_dir() {
             Some(dir) => dir.join("irust"),
@@ -127,8 +335,93 @@ impl Options {
     }
 }
 
+/// The light background counterpart of a default color
+fn light_color(color: Color) -> Option<Color> {
+    match color {
+        Color::White => Some(Color::Black),
+        Color::Yellow => Some(Color::DarkYellow),
+        Color::Cyan => Some(Color::DarkCyan),
+        Color::Green => Some(Color::DarkGreen),
+        _ => None,
+    }
+}
+
+/// Parse a `:set` value, strings don't need to be quoted
+pub fn parse_option_value(current: &toml::Value, value: &str) -> Result<toml::Value> {
+    if current.is_str() {
+        return Ok(toml::Value::String(
+            value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value)
+                .to_string(),
+        ));
+    }
+    let mut parsed: toml::value::Table = toml::from_str(&format!("value = {}", value))?;
+    // safe unwrap: we just parsed it
+    Ok(parsed.remove("value").unwrap())
+}
+
 impl IRust {
+    /// Make options changes at runtime take effect
+    pub fn apply_options(&mut self, old_options: &Options) {
+        if self.options.enable_racer != old_options.enable_racer {
+            self.racer = if self.options.enable_racer {
+                crate::irust::racer::Racer::start()
+            } else {
+                None
+            };
+        }
+        // `Auto` keeps the background detected at startup
+        if self.options.background != Background::Auto {
+            self.background = self.options.background;
+        }
+        // reloaded options come without their adapted colors
+        self.options.adapt_colors_to(self.background);
+        if self.options.background != old_options.background {
+            self.theme.adapt_to(self.background);
+        }
+        self.theme.engine = self.options.highlight_engine;
+        self.printer.underline_color = self.options.diagnostic_color;
+        self.printer.top_pane_color = self.options.split_view_color;
+        self.options.apply_eval_limits();
+        // only sccache can change at runtime
+        self.options.apply_build_cache();
+        // default dependencies are used by the next `:reset`
+        self.options.apply_default_deps();
+        if self.options.prelude != old_options.prelude {
+            self.repl.set_prelude(self.options.prelude.clone());
+        }
+        if self.options.alternate_screen != old_options.alternate_screen {
+            let _ = if self.options.alternate_screen {
+                self.printer.enter_alternate_screen()
+            } else {
+                // draw the status line and the top pane on the previous screen
+                self.printer
+                    .leave_alternate_screen()
+                    .and_then(|_| self.printer.resume())
+            };
+        }
+        if self.options.split_view != old_options.split_view
+            || self.options.split_view_rows != old_options.split_view_rows
+        {
+            let _ = self.update_split_view();
+        }
+        self.update_input_prompt();
+        // the status line is optional
+        let _ = self.update_status_line();
+    }
+
     pub fn should_push_to_history(&self, buffer: &str) -> bool {
+        if self
+            .options
+            .history_ignore
+            .iter()
+            .any(|pattern| glob_match(pattern, buffer.trim()))
+        {
+            return false;
+        }
+
         let buffer: Vec<char> = buffer.chars().collect();
 
         if buffer.is_empty() {
@@ -146,3 +439,38 @@ impl IRust {
             || (!irust_cmd && !shell_cmd)
     }
 }
+
+#[test]
+fn parse_option_value_test() {
+    let string = toml::Value::String("In: ".into());
+    assert_eq!(
+        parse_option_value(&string, ">> ").unwrap(),
+        toml::Value::String(">> ".into())
+    );
+    assert_eq!(
+        parse_option_value(&string, "\"In \"").unwrap(),
+        toml::Value::String("In ".into())
+    );
+    let boolean = toml::Value::Boolean(true);
+    assert_eq!(
+        parse_option_value(&boolean, "false").unwrap(),
+        toml::Value::Boolean(false)
+    );
+    assert!(parse_option_value(&boolean, "maybe").is_err());
+}
+
+#[test]
+fn adapt_colors_test() {
+    let mut options = Options {
+        eval_color: Color::DarkGreen,
+        ..Options::default()
+    };
+    options.adapt_colors_to(Background::Light);
+    assert_eq!(options.input_color, Color::DarkYellow);
+    assert_eq!(options.eval_color, Color::DarkGreen);
+
+    options.prompt_color = Color::Blue;
+    options.restore_adapted_colors();
+    assert_eq!(options.input_color, Color::Yellow);
+    assert_eq!(options.prompt_color, Color::Blue);
+}
@@ -0,0 +1,304 @@
+//! Full screen view for long outputs, exp: `:help`
+use super::{IRust, Result};
+use crossterm::{
+    cursor::MoveTo,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    queue,
+    style::{
+        Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+    },
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use printer::{
+    printer::{PrintQueue, PrinterItem},
+    width::char_width,
+};
+
+type Line = Vec<(char, Color)>;
+
+/// Split a print queue in lines of colored chars
+fn queue_lines(queue: PrintQueue) -> Vec<Line> {
+    let mut lines = vec![vec![]];
+    for item in queue {
+        let (text, color) = match item {
+            PrinterItem::NewLine => {
+                lines.push(vec![]);
+                continue;
+            }
+            PrinterItem::Char(c, color) => (c.to_string(), color),
+            PrinterItem::String(s, color) => (s, color),
+            PrinterItem::Str(s, color) => (s.to_string(), color),
+        };
+        for c in text.chars().filter(|c| *c != '\r') {
+            if c == '\n' {
+                lines.push(vec![]);
+            } else {
+                let line = lines.last_mut().expect("lines is never empty");
+                line.push((c, color));
+            }
+        }
+    }
+    lines
+}
+
+/// Split the lines wider than the terminal in rows, returns the rows and the first row of each line
+fn wrap(lines: &[Line], width: usize) -> (Vec<Line>, Vec<usize>) {
+    let mut rows = vec![];
+    let mut line_rows = vec![];
+    for line in lines {
+        line_rows.push(rows.len());
+        let mut row = vec![];
+        let mut row_width = 0;
+        for &(c, color) in line {
+            let c = if c == '\t' { ' ' } else { c };
+            if row_width + char_width(c) > width {
+                rows.push(std::mem::take(&mut row));
+                row_width = 0;
+            }
+            row_width += char_width(c);
+            row.push((c, color));
+        }
+        rows.push(row);
+    }
+    (rows, line_rows)
+}
+
+fn row_text(row: &[(char, Color)]) -> String {
+    row.iter().map(|(c, _)| c.to_ascii_lowercase()).collect()
+}
+
+/// Char ranges of the row that match the search
+fn matches(row: &[(char, Color)], search: &str) -> Vec<std::ops::Range<usize>> {
+    if search.is_empty() {
+        return vec![];
+    }
+    let text: Vec<char> = row_text(row).chars().collect();
+    let search: Vec<char> = search.to_lowercase().chars().collect();
+    (0..text.len())
+        .filter(|idx| text[*idx..].starts_with(&search))
+        .map(|idx| idx..idx + search.len())
+        .collect()
+}
+
+struct Pager {
+    title: String,
+    lines: Vec<Line>,
+    /// Lines jumped to with the digit keys
+    marks: Vec<usize>,
+    rows: Vec<Line>,
+    line_rows: Vec<usize>,
+    top: usize,
+    width: usize,
+    height: usize,
+    search: String,
+    last_match: Option<usize>,
+}
+
+impl Pager {
+    fn resize(&mut self, width: usize, height: usize) {
+        let top_line = self.line_rows.iter().rposition(|row| *row <= self.top);
+        self.width = width.max(1);
+        // the last row is the status line
+        self.height = height.saturating_sub(1).max(1);
+        let (rows, line_rows) = wrap(&self.lines, self.width);
+        self.rows = rows;
+        self.line_rows = line_rows;
+        self.last_match = None;
+        self.goto(top_line.map(|line| self.line_rows[line]).unwrap_or(0));
+    }
+
+    fn goto(&mut self, row: usize) {
+        self.top = row.min(self.rows.len().saturating_sub(self.height));
+    }
+
+    fn scroll(&mut self, rows: isize) {
+        self.goto((self.top as isize + rows).max(0) as usize);
+    }
+
+    /// Scroll to the row that matches the search after (or before) `row`, `row` included when going forward
+    fn find(&mut self, row: usize, forward: bool) -> bool {
+        let search = self.search.to_lowercase();
+        let is_match = |row: &Line| row_text(row).contains(&search);
+        let found = if forward {
+            (row..self.rows.len()).find(|idx| is_match(&self.rows[*idx]))
+        } else {
+            (0..row).rev().find(|idx| is_match(&self.rows[*idx]))
+        };
+        if let Some(row) = found {
+            self.goto(row);
+            self.last_match = Some(row);
+        }
+        found.is_some()
+    }
+
+    fn draw(&self, w: &mut impl std::io::Write, status: &str) -> Result<()> {
+        for y in 0..self.height {
+            queue!(w, MoveTo(0, y as u16), Clear(ClearType::CurrentLine))?;
+            let row = match self.rows.get(self.top + y) {
+                Some(row) => row,
+                None => continue,
+            };
+            let found = matches(row, &self.search);
+            let mut last_color = None;
+            for (idx, (c, color)) in row.iter().enumerate() {
+                let highlighted = found.iter().any(|range| range.contains(&idx));
+                if last_color != Some(*color) {
+                    queue!(w, SetForegroundColor(*color))?;
+                    last_color = Some(*color);
+                }
+                if highlighted {
+                    queue!(w, SetAttribute(Attribute::Reverse))?;
+                }
+                queue!(w, Print(c))?;
+                if highlighted {
+                    queue!(w, SetAttribute(Attribute::NoReverse))?;
+                }
+            }
+        }
+        queue!(
+            w,
+            MoveTo(0, self.height as u16),
+            Clear(ClearType::CurrentLine),
+            SetBackgroundColor(Color::DarkGrey),
+            SetForegroundColor(Color::White),
+            Print(status.chars().take(self.width).collect::<String>()),
+            ResetColor
+        )?;
+        w.flush()?;
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        let last_row = (self.top + self.height).min(self.rows.len());
+        let percent = if self.rows.is_empty() {
+            100
+        } else {
+            last_row * 100 / self.rows.len()
+        };
+        let sections = if self.marks.is_empty() {
+            ""
+        } else {
+            "  1-9 section"
+        };
+        format!(
+            " {} {}%  ↑↓ scroll  / search  n/N next/previous{}  q quit",
+            self.title, percent, sections
+        )
+    }
+
+    /// Read the search term on the status line, returns false if it was cancelled
+    fn read_search(&mut self, w: &mut impl std::io::Write) -> Result<bool> {
+        let mut search = String::new();
+        loop {
+            self.draw(w, &format!("/{}", search))?;
+            if let Event::Key(KeyEvent { code, modifiers }) = crossterm::event::read()? {
+                match code {
+                    KeyCode::Enter => break,
+                    KeyCode::Esc => return Ok(false),
+                    KeyCode::Char('c') if modifiers == KeyModifiers::CONTROL => return Ok(false),
+                    KeyCode::Backspace => {
+                        search.pop();
+                    }
+                    KeyCode::Char(c) => search.push(c),
+                    _ => (),
+                }
+            }
+        }
+        self.search = search;
+        Ok(true)
+    }
+}
+
+impl IRust {
+    /// Show the output from line `start` in the alternate screen, the input area is left untouched
+    ///
+    /// The digit keys jump to the lines in `marks`, exp: the sections of a document
+    pub fn page(
+        &mut self,
+        title: &str,
+        output: PrintQueue,
+        marks: Vec<usize>,
+        start: usize,
+    ) -> Result<()> {
+        let w = &mut self.printer.writer.raw;
+        let (width, height) = crossterm::terminal::size()?;
+        let mut pager = Pager {
+            title: title.to_string(),
+            lines: queue_lines(output),
+            marks,
+            rows: vec![],
+            line_rows: vec![],
+            top: 0,
+            width: 0,
+            height: 0,
+            search: String::new(),
+            last_match: None,
+        };
+        pager.resize(width as usize, height as usize);
+        if let Some(&row) = pager.line_rows.get(start) {
+            pager.goto(row);
+        }
+
+        queue!(w, EnterAlternateScreen, crossterm::cursor::Hide)?;
+        let mut message = None;
+        loop {
+            let status = message.take().unwrap_or_else(|| pager.status());
+            pager.draw(w, &status)?;
+            let page = pager.height as isize;
+            match crossterm::event::read()? {
+                Event::Resize(width, height) => pager.resize(width as usize, height as usize),
+                Event::Key(KeyEvent { code, modifiers }) => match code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char('c') if modifiers == KeyModifiers::CONTROL => break,
+                    KeyCode::Down | KeyCode::Char('j') | KeyCode::Enter => pager.scroll(1),
+                    KeyCode::Up | KeyCode::Char('k') => pager.scroll(-1),
+                    KeyCode::PageDown | KeyCode::Char(' ') | KeyCode::Char('f') => {
+                        pager.scroll(page)
+                    }
+                    KeyCode::PageUp | KeyCode::Char('b') => pager.scroll(-page),
+                    KeyCode::Home | KeyCode::Char('g') => pager.goto(0),
+                    KeyCode::End | KeyCode::Char('G') => pager.goto(usize::MAX),
+                    KeyCode::Char('/') => {
+                        let searched = pager.read_search(w)?;
+                        if searched && !pager.find(pager.top, true) {
+                            message = Some(format!(" `{}` not found", pager.search));
+                        }
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') if !pager.search.is_empty() => {
+                        let last_match = pager.last_match.unwrap_or(pager.top);
+                        let found = if code == KeyCode::Char('n') {
+                            pager.find(last_match + 1, true)
+                        } else {
+                            pager.find(last_match, false)
+                        };
+                        if !found {
+                            message = Some(" No more matches".to_string());
+                        }
+                    }
+                    KeyCode::Char(c) if c.is_ascii_digit() => {
+                        let mark = (c as usize - '0' as usize).checked_sub(1);
+                        if let Some(&line) = mark.and_then(|mark| pager.marks.get(mark)) {
+                            pager.goto(pager.line_rows[line]);
+                        }
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+        queue!(w, LeaveAlternateScreen, crossterm::cursor::Show)?;
+        self.restore_alternate_screen()?;
+        std::io::Write::flush(&mut self.printer.writer.raw)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn wrap_test() {
+    let line = |s: &str| s.chars().map(|c| (c, Color::White)).collect::<Line>();
+    let (rows, line_rows) = wrap(&[line("abcde"), line(""), line("中中中")], 4);
+    let rows: Vec<String> = rows.iter().map(|row| row_text(row)).collect();
+    assert_eq!(rows, ["abcd", "e", "", "中中", "中"]);
+    assert_eq!(line_rows, [0, 2, 3]);
+    assert_eq!(matches(&line("Ctrl-R ctrl-r"), "CTRL-r"), [0..6, 7..13]);
+}
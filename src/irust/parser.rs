@@ -1,11 +1,14 @@
 use crossterm::style::Color;
 
-use super::cargo_cmds::{cargo_asm, cargo_bench, ToolChain};
+use super::cargo_cmds::cargo_clean;
+use super::cargo_cmds::{cargo_asm, cargo_bench, cargo_build_output, dependencies, ToolChain};
 use super::cargo_cmds::{cargo_fmt, cargo_fmt_file, cargo_run, MAIN_FILE_EXTERN};
+use super::cargo_cmds::{dependency_spec, missing_crate};
 use super::highlight::highlight;
-use crate::irust::format::{format_check_output, format_err, format_eval_output};
+use crate::irust::format::{format_check_output, format_err, format_eval_diff, format_eval_output};
+use crate::irust::repl::{split_crate_attributes, uses_await};
 use crate::irust::{IRust, Result};
-use crate::utils::{remove_main, stdout_and_stderr};
+use crate::utils::remove_main;
 use printer::printer::{PrintQueue, PrinterItem};
 
 const SUCCESS: &str = "Ok!";
@@ -33,27 +36,50 @@ impl IRust {
     pub fn parse(&mut self) -> Result<PrintQueue> {
         // Order matters in this match
         match self.buffer.to_string().as_str() {
-            ":help" => self.help(),
+            cmd if cmd.starts_with(":help") => self.help(),
             ":reset" => self.reset(),
             ":show" => Ok(self.show()),
             ":pop" => self.pop(),
             ":irust" => self.irust(),
             ":sync" => self.sync(),
+            ":history" => Ok(self.show_history()),
+            ":checkpoints" => Ok(self.show_checkpoints()),
+            cmd if cmd.starts_with(":goto") => self.goto(),
             cmd if cmd.starts_with("::") => self.run_cmd(),
+            // valid `:edit-history` inputs are expanded before reaching here
+            cmd if cmd.starts_with(":edit-history") => {
+                Err("Usage: :edit-history [N], N is an input number".into())
+            }
             cmd if cmd.starts_with(":edit") => self.extern_edit(),
+            cmd if cmd.starts_with(":checkpoint") => self.checkpoint(),
+            cmd if cmd.starts_with(":rollback") => self.rollback(),
             cmd if cmd.starts_with(":add") => self.add_dep(),
+            cmd if cmd.starts_with(":search") => self.search_crates(),
             cmd if cmd.starts_with(":load") => self.load(),
             cmd if cmd.starts_with(":reload") => self.reload(),
+            cmd if cmd.starts_with(":watch") => self.watch(),
+            cmd if cmd.starts_with(":unwatch") => self.unwatch(),
             cmd if cmd.starts_with(":type") => self.show_type(),
             cmd if cmd.starts_with(":del") => self.del(),
             cmd if cmd.starts_with(":cd") => self.cd(),
             cmd if cmd.starts_with(":color") => self.color(),
+            cmd if cmd.starts_with(":theme") => self.set_theme(),
+            cmd if cmd.starts_with(":setenv") => self.setenv(),
+            cmd if cmd.starts_with(":unsetenv") => self.unsetenv(),
+            cmd if cmd.starts_with(":env") => self.env(),
+            cmd if cmd.starts_with(":set") => self.set_option(),
+            cmd if cmd.starts_with(":config") => self.edit_config(),
             cmd if cmd.starts_with(":toolchain") => self.toolchain(),
+            cmd if cmd.starts_with(":target") => self.target(),
             cmd if cmd.starts_with(":check_statements") => self.check_statements(),
             cmd if cmd.starts_with(":time_release") => self.time_release(),
             cmd if cmd.starts_with(":time") => self.time(),
             cmd if cmd.starts_with(":bench") => self.bench(),
             cmd if cmd.starts_with(":asm") => self.asm(),
+            cmd if cmd.starts_with(":debug") => self.debug(),
+            cmd if cmd.starts_with(":doctest") => self.doctest(),
+            ":clean" => self.clean(),
+            cmd if super::shell::shell_capture(cmd).is_some() => self.capture_cmd(),
             _ => self.parse_second_order(),
         }
     }
@@ -77,8 +103,17 @@ impl IRust {
     }
 
     fn del(&mut self) -> Result<PrintQueue> {
-        if let Some(line_num) = self.buffer.to_string().split_whitespace().last() {
-            self.repl.del(line_num)?;
+        // exp: `:del 3` deletes a line, `:del foo` deletes the definition of foo
+        if let Some(target) = self.buffer.to_string().split_whitespace().nth(1) {
+            if target.chars().all(|c| c.is_ascii_digit()) {
+                self.repl.del(target)?;
+            } else {
+                let kind = self.repl.del_item(target)?;
+                return print_queue!(
+                    format!("Removed {} `{}`", kind.as_str(), target),
+                    self.options.ok_color
+                );
+            }
         }
         success!()
     }
@@ -88,6 +123,147 @@ impl IRust {
         highlight(&code.into(), &self.theme)
     }
 
+    fn checkpoint(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        let name = match buffer.split_whitespace().nth(1) {
+            Some(name) => name.to_string(),
+            None => (self.checkpoints.len() + 1).to_string(),
+        };
+
+        let checkpoint = self.repl.checkpoint(name.clone())?;
+        // a checkpoint with the same name is replaced
+        self.checkpoints.retain(|c| c.name != name);
+        self.checkpoints.push(checkpoint);
+
+        print_queue!(
+            format!("Checkpoint `{}` created", name),
+            self.options.ok_color
+        )
+    }
+
+    fn rollback(&mut self) -> Result<PrintQueue> {
+        // exp: :rollback before_statics --deps
+        let buffer = self.buffer.to_string();
+        let args: Vec<&str> = buffer.split_whitespace().skip(1).collect();
+        let restore_deps = args.contains(&"--deps");
+        let target = args.iter().find(|arg| **arg != "--deps");
+
+        let checkpoints = &self.checkpoints;
+        let checkpoint = match target {
+            Some(target) => checkpoints.iter().find(|c| c.name == *target).or_else(|| {
+                // fallback to the checkpoint number as listed by `:checkpoints`
+                let n = target.parse::<usize>().ok()?;
+                checkpoints.get(n.checked_sub(1)?)
+            }),
+            None => checkpoints.last(),
+        }
+        .ok_or("No such checkpoint")?;
+
+        self.repl.rollback(checkpoint, restore_deps)?;
+        if restore_deps {
+            self.wait_add(self.repl.build(self.options.toolchain)?, "Build")?;
+        }
+
+        success!()
+    }
+
+    fn show_checkpoints(&mut self) -> PrintQueue {
+        let mut print_queue = PrintQueue::default();
+        for (idx, checkpoint) in self.checkpoints.iter().enumerate() {
+            print_queue.push(PrinterItem::String(
+                format!("{}: {}", idx + 1, checkpoint.name),
+                self.options.ok_color,
+            ));
+            print_queue.add_new_line(1);
+        }
+        print_queue
+    }
+
+    fn show_history(&mut self) -> PrintQueue {
+        let mut print_queue = PrintQueue::default();
+        for (operation_number, input) in self.history.session_inputs() {
+            print_queue.push(PrinterItem::String(
+                format!("[{}] ", operation_number),
+                self.options.irust_color,
+            ));
+            print_queue.append(&mut highlight(&input.as_str().into(), &self.theme));
+            print_queue.add_new_line(1);
+        }
+        print_queue
+    }
+
+    fn search_crates(&mut self) -> Result<PrintQueue> {
+        // Only 9 results so a crate can be picked with a single number key
+        const LIMIT: usize = 9;
+
+        let buffer = self.buffer.to_string();
+        let term = buffer
+            .split_once(' ')
+            .map(|(_, term)| term.trim())
+            .filter(|term| !term.is_empty())
+            .ok_or("No search term specified")?;
+
+        let (crates, offline) = match super::crates::search(term, LIMIT) {
+            Ok(crates) => (crates, false),
+            Err(_) => (super::crates::search_cached(term, LIMIT), true),
+        };
+        if crates.is_empty() {
+            return print_queue!("No crates found".to_string(), self.options.err_color);
+        }
+
+        let mut print_queue = PrintQueue::default();
+        if offline {
+            print_queue.push(PrinterItem::Str(
+                "crates.io is unreachable, showing cached results",
+                self.options.err_color,
+            ));
+            print_queue.add_new_line(1);
+        }
+        for (idx, krate) in crates.iter().enumerate() {
+            print_queue.push(PrinterItem::String(
+                format!("{}: ", idx + 1),
+                self.options.irust_color,
+            ));
+            print_queue.push(PrinterItem::String(
+                format!("{} = \"{}\"", krate.name, krate.version),
+                self.options.ok_color,
+            ));
+            if !krate.description.is_empty() {
+                print_queue.push(PrinterItem::String(
+                    format!("  # {}", krate.description),
+                    Color::DarkGrey,
+                ));
+            }
+            print_queue.add_new_line(1);
+        }
+        print_queue.push(PrinterItem::Str(
+            "Press a number to add the crate, any other key to cancel ",
+            Color::Grey,
+        ));
+        self.printer.print_output(print_queue)?;
+        std::io::Write::flush(&mut self.printer.writer.raw)?;
+
+        let picked = loop {
+            if let crossterm::event::Event::Key(key_event) = crossterm::event::read()? {
+                break match key_event.code {
+                    crossterm::event::KeyCode::Char(c) => c
+                        .to_digit(10)
+                        .and_then(|n| crates.get((n as usize).checked_sub(1)?)),
+                    _ => None,
+                };
+            }
+        };
+
+        let mut new_line = PrintQueue::default();
+        new_line.add_new_line(1);
+        self.printer.print_output(new_line)?;
+
+        match picked {
+            Some(krate) => self.add_dep_inner(vec![krate.name.clone()]),
+            None => Ok(PrintQueue::default()),
+        }
+    }
+
     fn toolchain(&mut self) -> Result<PrintQueue> {
         self.options.toolchain = ToolChain::from_str(
             self.buffer
@@ -102,7 +278,10 @@ impl IRust {
     fn add_dep(&mut self) -> Result<PrintQueue> {
         let mut dep: Vec<String> = crate::utils::split_args(self.buffer.to_string());
         dep.remove(0); //drop :add
+        self.add_dep_inner(dep)
+    }
 
+    fn add_dep_inner(&mut self, mut dep: Vec<String>) -> Result<PrintQueue> {
         // Try to canonicalize all arguments that corresponds to an existing path
         // This is necessary because `:add relative_path` doesn't work without it
         // Note this might be a bit too aggressive (an argument might be canonicalized, that the user didn't not intend for it to be considered as a path)
@@ -168,24 +347,131 @@ impl IRust {
                 return Err("Value is incorrect".into());
             }
 
+            let engine = self.theme.engine;
             self.theme = theme.try_into()?;
+            self.theme.engine = engine;
             Ok(())
         };
 
-        if let Err(e) = parse() {
-            return Err(e);
+        parse()?;
+
+        success!()
+    }
+
+    fn set_theme(&mut self) -> Result<PrintQueue> {
+        use super::highlight::theme::{backup_theme, builtin, BUILTIN_THEMES};
+
+        let buffer = self.buffer.to_string();
+        let name = match buffer.split_whitespace().nth(1) {
+            Some(name) => name,
+            // list available themes
+            None => {
+                return print_queue!(BUILTIN_THEMES.join(" "), self.options.ok_color);
+            }
+        };
+
+        let mut theme = builtin(name).ok_or_else(|| {
+            format!(
+                "Unknown theme `{}`, available themes: {}",
+                name,
+                BUILTIN_THEMES.join(" ")
+            )
+        })?;
+        theme.engine = self.theme.engine;
+        // a custom theme file is kept aside instead of being lost
+        let backup_path = backup_theme()?;
+        self.theme = theme;
+        self.theme.save()?;
+
+        match backup_path {
+            Some(backup_path) => print_queue!(
+                format!("Previous theme saved to {}", backup_path.display()),
+                self.options.ok_color
+            ),
+            None => success!(),
+        }
+    }
+
+    fn set_option(&mut self) -> Result<PrintQueue> {
+        // exp: :set input_prompt In [1]:
+        let buffer = self.buffer.to_string();
+        let (key, value) = buffer
+            .split_once(' ')
+            .map(|(_, rest)| rest.trim_start())
+            .and_then(|rest| rest.split_once(' '))
+            .ok_or("Usage: :set <key> <value>")?;
+
+        // the new options are adapted to the terminal again by `apply_options`
+        let mut options = toml::Value::try_from(self.options.configured())?;
+        let current = options.get_mut(key).ok_or("key doesn't exist")?;
+        *current = super::options::parse_option_value(current, value)?;
+        let options: super::options::Options =
+            options.try_into().map_err(|_| "Value is incorrect")?;
+        if options.eval_memory_limit != 0
+            && options.eval_memory_limit < super::cargo_cmds::MIN_EVAL_MEMORY_LIMIT
+        {
+            return Err(format!(
+                "eval_memory_limit limits the address space, it needs at least {} MiB",
+                super::cargo_cmds::MIN_EVAL_MEMORY_LIMIT
+            )
+            .into());
         }
 
+        let old_options = std::mem::replace(&mut self.options, options);
+        self.apply_options(&old_options);
+        success!()
+    }
+
+    fn edit_config(&mut self) -> Result<PrintQueue> {
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .map_err(|_| "Set $EDITOR to edit the configuration")?;
+        let config_path =
+            super::options::Options::config_path().ok_or("Error accessing config_dir")?;
+
+        // make sure the file reflects the current session
+        self.options.save()?;
+
+        self.printer.writer.raw.write_with_color(
+            format!("waiting for {}...", editor),
+            crossterm::style::Color::Magenta,
+        )?;
+
+        #[cfg(windows)]
+        std::process::Command::new("cmd")
+            .arg("/C")
+            .arg(editor)
+            .arg(&config_path)
+            .spawn()?
+            .wait()?;
+
+        #[cfg(not(windows))]
+        std::process::Command::new(editor)
+            .arg(&config_path)
+            .spawn()?
+            .wait()?;
+        self.restore_alternate_screen()?;
+
+        // on error the current options are kept
+        let options = super::options::Options::new()
+            .map_err(|e| format!("Invalid configuration, nothing changed: {}", e))?;
+        let old_options = std::mem::replace(&mut self.options, options);
+        self.apply_options(&old_options);
         success!()
     }
 
     fn load(&mut self) -> Result<PrintQueue> {
+        // exp: :load script.rs, :load --reset script.rs
         let buffer = self.buffer.to_string();
-        let path = if let Some(path) = buffer.split_whitespace().nth(1) {
-            std::path::Path::new(&path).to_path_buf()
-        } else {
-            return Err("No path specified").map_err(|e| e.into());
+        let args: Vec<&str> = buffer.split_whitespace().skip(1).collect();
+        let reset = args.contains(&"--reset");
+        let path = match args.iter().find(|arg| **arg != "--reset") {
+            Some(path) => std::path::Path::new(path).to_path_buf(),
+            None => return Err("No path specified").map_err(|e| e.into()),
         };
+        if reset {
+            self.repl.reset(self.options.toolchain)?;
+        }
         self.load_inner(path)
     }
 
@@ -198,34 +484,135 @@ impl IRust {
         self.load_inner(path)
     }
 
+    /// Inject the file items and main body into the session, replacing the code of its previous load,
+    /// then run the session
     pub fn load_inner(&mut self, path: std::path::PathBuf) -> Result<PrintQueue> {
+        let mut print_queue = PrintQueue::default();
+        if let Some(err) = self.load_code(path)? {
+            self.last_eval.success = Some(false);
+            print_queue.append(&mut format_err(&err));
+            return Ok(print_queue);
+        }
+
+        let (status, output) = cargo_run(true, false, self.options.toolchain)?;
+        self.last_eval.success = Some(status.success());
+        if !status.success() {
+            print_queue.append(&mut format_err(&output));
+        } else if !output.trim().is_empty() {
+            print_queue.push(PrinterItem::String(
+                output.trim_end().to_string(),
+                self.options.eval_color,
+            ));
+            print_queue.add_new_line(1);
+        } else {
+            print_queue.push(PrinterItem::Str(SUCCESS, Color::Blue));
+            print_queue.add_new_line(1);
+        }
+        Ok(print_queue)
+    }
+
+    /// Insert the files given on the command line in order then run their code, before the first prompt
+    ///
+    /// A file that doesn't compile is skipped
+    pub fn load_startup_files(&mut self) -> Result<()> {
+        let files = std::mem::take(&mut self.options.startup_files);
+        if files.is_empty() {
+            return Ok(());
+        }
+        let mut loaded = false;
+        for path in files {
+            let mut print_queue = PrintQueue::default();
+            print_queue.push(PrinterItem::String(
+                format!("Loading {}... ", path.display()),
+                self.options.irust_color,
+            ));
+            self.printer
+                .print_output(std::mem::take(&mut print_queue))?;
+            std::io::Write::flush(&mut self.printer.writer.raw)?;
+            match self.insert_file(path.clone()) {
+                Ok(None) => {
+                    loaded = true;
+                    print_queue.push(PrinterItem::Str(SUCCESS, Color::Blue));
+                    print_queue.add_new_line(1);
+                }
+                Ok(Some(errors)) => {
+                    print_queue.add_new_line(1);
+                    print_queue.append(&mut format_err(&errors));
+                }
+                Err(e) => {
+                    print_queue.push(PrinterItem::String(e.to_string(), self.options.err_color));
+                    print_queue.add_new_line(1);
+                }
+            }
+            self.printer.print_output(print_queue)?;
+        }
+        if !loaded {
+            return Ok(());
+        }
+
+        // the main bodies of the files
+        self.repl.write()?;
+        let (status, out) = cargo_run(true, false, self.options.toolchain)?;
+        if !out.trim().is_empty() {
+            let mut print_queue = PrintQueue::default();
+            let color = if status.success() {
+                self.options.eval_color
+            } else {
+                self.options.err_color
+            };
+            print_queue.push(PrinterItem::String(out.trim_end().to_string(), color));
+            print_queue.add_new_line(1);
+            self.printer.print_output(print_queue)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the build errors if the loaded code doesn't compile, the session is left untouched then
+    pub fn load_code(&mut self, path: std::path::PathBuf) -> Result<Option<String>> {
         // save path
         self.global_variables
             .set_last_loaded_coded_path(path.clone());
+        self.insert_file(path)
+    }
 
-        // reset repl
-        self.repl.reset(self.options.toolchain)?;
-
+    /// Insert the file code if it builds, the code of a previous load of the same file is replaced
+    pub fn insert_file(&mut self, path: std::path::PathBuf) -> Result<Option<String>> {
+        // the same file can be given with different paths
+        let path = std::fs::canonicalize(&path).unwrap_or(path);
         // read code
-        let path_code = std::fs::read(path)?;
+        let path_code = std::fs::read(&path)?;
         let code = if let Ok(code) = String::from_utf8(path_code) {
             code
         } else {
-            return Err("The specified file is not utf8 encoded").map_err(Into::into);
+            return Err("The specified file is not utf8 encoded".into());
         };
 
         // Format code to make `remove_main` function work correctly
         let code = cargo_fmt(&code)?;
         let code = remove_main(&code);
 
+        // crate attributes need to go outside of main
+        let (attributes, code): (Vec<&str>, Vec<&str>) = code
+            .lines()
+            .partition(|line| line.trim_start().starts_with("#!"));
+
+        let mut repl = self.repl.clone();
+        repl.insert_loaded(path, code.join("\n"));
+        for attribute in attributes {
+            repl.insert(attribute.to_string());
+        }
+
         // build the code
-        let (status, output) = self.repl.eval_build(code.clone(), self.options.toolchain)?;
+        repl.write()?;
+        let (status, output) = cargo_build_output(true, false, self.options.toolchain)?;
 
         if !status.success() {
-            Ok(format_err(&output))
+            // restore the session code on disk
+            self.repl.write()?;
+            Ok(Some(output))
         } else {
-            self.repl.insert(code);
-            success!()
+            self.repl = repl;
+            Ok(None)
         }
     }
 
@@ -275,20 +662,77 @@ impl IRust {
         print_queue!(var_type, self.options.ok_color)
     }
 
-    fn run_cmd(&mut self) -> Result<PrintQueue> {
-        // remove ::
-        let buffer = &self.buffer.to_string()[2..];
+    /// Run main with the configured async runtime, its crate is added on first use
+    fn enable_async_runtime(&mut self) -> Result<()> {
+        let runtime = self.options.async_runtime;
+        if !dependencies().iter().any(|dep| dep == runtime.crate_name()) {
+            self.wait_add(self.repl.add_dep(&runtime.dependency())?, "Add")?;
+            self.wait_add(self.repl.build(self.options.toolchain)?, "Build")?;
+        }
+        self.repl.async_runtime = Some(runtime);
+        Ok(())
+    }
 
-        let mut cmd = buffer.split_whitespace();
-        let output = stdout_and_stderr(
-            std::process::Command::new(cmd.next().unwrap_or_default())
-                .args(&cmd.collect::<Vec<&str>>())
-                .output()?,
-        )
-        .trim()
-        .to_owned();
+    /// If the build failed because of a crate that is not a dependency, add it (after asking unless
+    /// `auto_add_deps` is set) and evaluate the input again
+    ///
+    /// Returns `None` if the output doesn't mention such a crate or if the user declined
+    fn add_missing_crate(&mut self, output: &str) -> Result<Option<PrintQueue>> {
+        let name = match missing_crate(output) {
+            Some(name) if !dependencies().contains(&name) => name,
+            _ => return Ok(None),
+        };
+
+        if !self.options.auto_add_deps {
+            let mut question = PrintQueue::default();
+            question.push(PrinterItem::String(
+                format!("`{}` is not a dependency, add it? [Y/n] ", name),
+                self.options.irust_warn_color,
+            ));
+            self.printer.print_output(question)?;
+            std::io::Write::flush(&mut self.printer.writer.raw)?;
+
+            let confirmed = loop {
+                if let crossterm::event::Event::Key(key_event) = crossterm::event::read()? {
+                    match key_event.code {
+                        crossterm::event::KeyCode::Char('c')
+                            if key_event.modifiers == crossterm::event::KeyModifiers::CONTROL =>
+                        {
+                            break false
+                        }
+                        crossterm::event::KeyCode::Char('y')
+                        | crossterm::event::KeyCode::Char('Y')
+                        | crossterm::event::KeyCode::Enter => break true,
+                        crossterm::event::KeyCode::Char('n')
+                        | crossterm::event::KeyCode::Char('N')
+                        | crossterm::event::KeyCode::Esc => break false,
+                        _ => (),
+                    }
+                }
+            };
+
+            let mut new_line = PrintQueue::default();
+            new_line.add_new_line(1);
+            self.printer.print_output(new_line)?;
+            if !confirmed {
+                return Ok(None);
+            }
+        }
+
+        self.wait_add(self.repl.add_dep(std::slice::from_ref(&name))?, "Add")?;
+        self.wait_add(self.repl.build(self.options.toolchain)?, "Build")?;
 
-        print_queue!(output, self.options.shell_color)
+        let mut outputs = PrintQueue::default();
+        if let Some(spec) = dependency_spec(&name) {
+            outputs.push(PrinterItem::String(
+                format!("Added {} = {}", name, spec),
+                self.options.ok_color,
+            ));
+            outputs.add_new_line(1);
+        }
+        // the crate is a dependency now so this can't loop
+        outputs.append(&mut self.parse_second_order()?);
+        Ok(Some(outputs))
     }
 
     fn parse_second_order(&mut self) -> Result<PrintQueue> {
@@ -320,9 +764,35 @@ impl IRust {
             buffer
         };
 
+        // crate attributes are hoisted to the crate root, the rest of the input is handled as usual
+        let buffer = {
+            let (attributes, rest) = split_crate_attributes(&buffer);
+            if attributes.is_empty() {
+                buffer
+            } else {
+                if attributes.iter().any(|a| a.starts_with("#![feature"))
+                    && !matches!(self.options.toolchain, ToolChain::Nightly)
+                {
+                    return Err(
+                        "`#![feature]` requires the nightly toolchain, switch to it with `:toolchain nightly`"
+                            .into(),
+                    );
+                }
+                self.repl.insert(attributes.join("\n"));
+                if rest.trim().is_empty() {
+                    return success!();
+                }
+                rest
+            }
+        };
+
         // This trimmed buffer should not be inserted nor evaluated
         let buffer_trimmed = buffer.trim();
 
+        if self.repl.async_runtime.is_none() && uses_await(&buffer) {
+            self.enable_async_runtime()?;
+        }
+
         if buffer_trimmed.is_empty() {
             Ok(PrintQueue::default())
         } else if buffer_trimmed.ends_with(';')
@@ -343,12 +813,15 @@ impl IRust {
             let mut insert_flag = true;
 
             if self.options.check_statements {
-                if let Some(mut e) =
-                    format_check_output(self.repl.check(buffer.clone(), self.options.toolchain)?)
-                {
+                let output = self.repl.check(buffer.clone(), self.options.toolchain)?;
+                if let Some(mut e) = format_check_output(output.clone()) {
+                    if let Some(outputs) = self.add_missing_crate(&output)? {
+                        return Ok(outputs);
+                    }
                     print_queue.append(&mut e);
                     insert_flag = false;
                 }
+                self.last_eval.success = Some(insert_flag);
             }
 
             // if cargo_check is disabled or if cargo_check is enabled but returned no error
@@ -359,14 +832,42 @@ impl IRust {
             Ok(print_queue)
         } else {
             let mut outputs = PrintQueue::default();
-            let (status, out) = self.repl.eval(buffer, self.options.toolchain)?;
+            // inputs that only differ by their whitespace are re-runs
+            let rerun_key = buffer.split_whitespace().collect::<Vec<_>>().join(" ");
+            let now = std::time::Instant::now();
+            let (status, out, stderr) = self.repl.eval(buffer, self.options.toolchain)?;
+            let (stderr, mut logs) = self.route_logs(&stderr)?;
+            outputs.append(&mut logs);
+            let out = if self.options.split_view {
+                self.printer.push_to_top_pane(&stderr)?;
+                out
+            } else {
+                // the rest of stderr follows the output, exp: a panic message
+                out + &stderr
+            };
+            if !status.success() {
+                if let Some(outputs) = self.add_missing_crate(&out)? {
+                    return Ok(outputs);
+                }
+            }
+            self.last_eval.duration = Some(now.elapsed());
+            self.last_eval.success = Some(status.success());
             // Save output if it was a success
             if status.success() {
                 self.global_variables.set_last_output(out.clone());
             }
 
             let output_prompt = self.get_output_prompt();
-            if let Some(mut eval_output) = format_eval_output(status, out, output_prompt) {
+            let mut diff = None;
+            if self.options.diff_reruns && status.success() && out.trim() != "()" {
+                if let Some(previous) = self.previous_outputs.get(&rerun_key) {
+                    diff = format_eval_diff(previous, &out, output_prompt.clone());
+                }
+                self.previous_outputs.insert(rerun_key, out.clone());
+            }
+            if let Some(mut eval_output) =
+                diff.or_else(|| format_eval_output(status, out, output_prompt))
+            {
                 outputs.append(&mut eval_output);
             }
 
@@ -400,7 +901,7 @@ impl IRust {
         self.repl.write_to_extern()?;
 
         // beautify code
-        cargo_fmt_file(&*MAIN_FILE_EXTERN);
+        cargo_fmt_file(&MAIN_FILE_EXTERN);
 
         // some commands are not detected from path but still works  with cmd /C
         #[cfg(windows)]
@@ -416,15 +917,24 @@ impl IRust {
             .arg(&*MAIN_FILE_EXTERN)
             .spawn()?
             .wait()?;
+        self.restore_alternate_screen()?;
 
         self.sync()
     }
 
+    /// Editors and the pager leave the alternate screen when they exit, even if IRust was using it
+    pub fn restore_alternate_screen(&mut self) -> Result<()> {
+        if self.printer.is_in_alternate_screen() {
+            self.printer.enter_alternate_screen()?;
+        }
+        Ok(())
+    }
+
     fn irust(&mut self) -> Result<PrintQueue> {
         print_queue!(self.ferris(), Color::Red)
     }
 
-    fn cd(&mut self) -> Result<PrintQueue> {
+    pub fn cd(&mut self) -> Result<PrintQueue> {
         use std::env::*;
         let buffer = self.buffer.to_string();
         let buffer = buffer
@@ -444,7 +954,7 @@ impl IRust {
             }
             path => {
                 let mut dir = current_dir()?;
-                dir.push(&path);
+                dir.push(path);
                 set_current_dir(dir)?;
             }
         }
@@ -469,8 +979,8 @@ impl IRust {
     fn inner_time(&mut self, pattern: &str, release: bool) -> Result<PrintQueue> {
         let buffer = self.buffer.to_string();
         let fnn = buffer
-            .splitn(2, pattern)
-            .nth(1)
+            .split_once(pattern)
+            .map(|(_, fnn)| fnn)
             .ok_or("No function specified")?;
 
         if fnn.is_empty() {
@@ -503,6 +1013,14 @@ impl IRust {
             .ok_or("failed to bench function")?)
     }
 
+    fn clean(&mut self) -> Result<PrintQueue> {
+        let (status, out) = cargo_clean(self.options.toolchain)?;
+        if !status.success() {
+            return Ok(format_err(&out));
+        }
+        success!()
+    }
+
     fn bench(&mut self) -> Result<PrintQueue> {
         //make sure we have the latest changes in main.rs
         self.repl.write()?;
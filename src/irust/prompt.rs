@@ -0,0 +1,124 @@
+use super::cargo_cmds::ToolChain;
+use super::global_variables::{GlobalVariables, LastEval};
+use std::time::Duration;
+
+/// Render a prompt template
+///
+/// exp: "{op} [{cwd_short}] {toolchain}> "
+///
+/// Available variables: `op` `cwd` `cwd_short` `toolchain` `duration` `status`,
+/// `{{` and `}}` are literal braces, unknown variables are left as is
+pub fn render(
+    template: &str,
+    global_variables: &GlobalVariables,
+    last_eval: &LastEval,
+    toolchain: ToolChain,
+) -> String {
+    let mut prompt = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                prompt.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                prompt.push('}');
+            }
+            '{' => {
+                let mut var = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    var.push(c);
+                }
+                match variable(&var, global_variables, last_eval, toolchain) {
+                    Some(value) if closed => prompt.push_str(&value),
+                    _ => {
+                        prompt.push('{');
+                        prompt.push_str(&var);
+                        if closed {
+                            prompt.push('}');
+                        }
+                    }
+                }
+            }
+            c => prompt.push(c),
+        }
+    }
+    prompt
+}
+
+fn variable(
+    name: &str,
+    global_variables: &GlobalVariables,
+    last_eval: &LastEval,
+    toolchain: ToolChain,
+) -> Option<String> {
+    Some(match name {
+        "op" => global_variables.operation_number.to_string(),
+        "cwd" => global_variables.get_cwd().display().to_string(),
+        "cwd_short" => {
+            let cwd = global_variables.get_cwd();
+            if Some(&cwd) == dirs_next::home_dir().as_ref() {
+                "~".to_string()
+            } else {
+                cwd.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| cwd.display().to_string())
+            }
+        }
+        "toolchain" => toolchain.name().to_string(),
+        "duration" => last_eval.duration.map(format_duration).unwrap_or_default(),
+        "status" => match last_eval.success {
+            Some(true) => "✓".to_string(),
+            Some(false) => "✗".to_string(),
+            None => String::new(),
+        },
+        _ => return None,
+    })
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.as_secs() == 0 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", duration.as_secs_f32())
+    }
+}
+
+#[test]
+fn render_test() {
+    let mut global_variables = GlobalVariables::new();
+    global_variables.operation_number = 3;
+    let last_eval = LastEval {
+        duration: Some(Duration::from_millis(1500)),
+        success: Some(true),
+    };
+    assert_eq!(
+        render(
+            "{op} {toolchain} {duration}> ",
+            &global_variables,
+            &last_eval,
+            ToolChain::Beta
+        ),
+        "3 beta 1.5s> "
+    );
+    assert_eq!(
+        render(
+            "{{op}} {nope} {op",
+            &global_variables,
+            &last_eval,
+            ToolChain::Stable
+        ),
+        "{op} {nope} {op"
+    );
+    assert_eq!(
+        render("In: ", &global_variables, &last_eval, ToolChain::Stable),
+        "In: "
+    );
+}
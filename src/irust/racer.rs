@@ -1,10 +1,11 @@
 use super::{
     cargo_cmds::MAIN_FILE,
     highlight::{highlight, theme::Theme},
+    ranking::Ranking,
     Result,
 };
 use crate::utils::{read_until_bytes, StringTools};
-use crossterm::{style::Color, terminal::ClearType};
+use crossterm::style::Color;
 use printer::printer::{PrintQueue, Printer, PrinterItem};
 use std::io::Write;
 use std::process::{Child, Command, Stdio};
@@ -14,15 +15,53 @@ pub enum Cycle {
     Down,
 }
 
+/// IRust commands that can be auto completed
+const IRUST_COMMANDS: &[&str] = &[
+    "show",
+    "help",
+    "pop",
+    "del",
+    "add",
+    "search",
+    "reset",
+    "load",
+    "reload",
+    "watch",
+    "unwatch",
+    "type",
+    "cd",
+    "color",
+    "theme",
+    "set",
+    "config",
+    "toolchain",
+    "check_statements",
+    "time",
+    "time_release",
+    "bench",
+    "clean",
+    "asm",
+    "debug",
+    "edit",
+    "sync",
+    "irust",
+    "history",
+    "checkpoint",
+    "checkpoints",
+    "rollback",
+];
+
 pub struct Racer {
-    process: Child,
+    /// None if the racer daemon couldn't be started, IRust commands and paths can still be completed
+    process: Option<Child>,
     cursor: (usize, usize),
     // suggestions: (Name, definition)
     suggestions: Vec<(String, String)>,
     suggestion_idx: usize,
-    cmds: [String; 16],
     update_lock: bool,
     pub active_suggestion: Option<String>,
+    /// Accepted suggestions come first
+    ranking: Ranking,
 }
 
 impl Racer {
@@ -33,40 +72,25 @@ impl Racer {
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
             .spawn()
-            .ok()?;
-        // Disable Racer if unable to start it
-        //.map_err(|_| IRustError::RacerDisabled)?;
+            .ok();
         let cursor = (2, 0);
-        let cmds = [
-            "show".to_string(),
-            "help".to_string(),
-            "pop".to_string(),
-            "del".to_string(),
-            "add".to_string(),
-            "reset".to_string(),
-            "load".to_string(),
-            "reload".to_string(),
-            "type".to_string(),
-            "cd".to_string(),
-            "color".to_string(),
-            "toolchain".to_string(),
-            "check_statements".to_string(),
-            "time".to_string(),
-            "time_release".to_string(),
-            "bench".to_string(),
-        ];
 
         Some(Racer {
             process,
             cursor,
             suggestions: vec![],
             suggestion_idx: 0,
-            cmds,
             update_lock: false,
             active_suggestion: None,
+            ranking: Ranking::load(),
         })
     }
 
+    /// The racer daemon is available
+    pub fn is_running(&self) -> bool {
+        self.process.is_some()
+    }
+
     fn complete_code(&mut self) -> Result<()> {
         // check for lock
         if self.update_lock {
@@ -76,13 +100,16 @@ impl Racer {
         self.suggestions.clear();
         self.goto_first_suggestion();
 
-        let stdin = self
-            .process
+        let process = match self.process.as_mut() {
+            Some(process) => process,
+            // Racer is not available
+            None => return Ok(()),
+        };
+        let stdin = process
             .stdin
             .as_mut()
             .ok_or("failed to acess racer stdin")?;
-        let stdout = self
-            .process
+        let stdout = process
             .stdout
             .as_mut()
             .ok_or("faied to acess racer stdout")?;
@@ -136,22 +163,23 @@ impl Racer {
         // remove duplicates
         self.suggestions.sort();
         self.suggestions.dedup();
+        self.ranking.sort(&mut self.suggestions);
 
         Ok(())
     }
 
-    fn goto_next_suggestion(&mut self) {
+    pub fn goto_next_suggestion(&mut self) {
         if self.suggestion_idx >= self.suggestions.len() {
             self.suggestion_idx = 0
         }
         self.suggestion_idx += 1;
     }
 
-    fn goto_previous_suggestion(&mut self) {
+    pub fn goto_previous_suggestion(&mut self) {
         self.suggestion_idx = self
             .suggestion_idx
             .checked_sub(1)
-            .unwrap_or_else(|| self.suggestions.len());
+            .unwrap_or(self.suggestions.len());
         if self.suggestion_idx == 0 {
             self.suggestion_idx = self.suggestions.len();
         }
@@ -163,7 +191,7 @@ impl Racer {
                 .get(self.suggestion_idx - 1)
                 .map(ToOwned::to_owned)
         } else {
-            self.suggestions.get(0).map(ToOwned::to_owned)
+            self.suggestions.first().map(ToOwned::to_owned)
         }
     }
 
@@ -171,7 +199,36 @@ impl Racer {
         self.suggestion_idx = 0;
     }
 
-    fn full_suggestion(s: &(String, String)) -> String {
+    pub fn suggestions(&self) -> &[(String, String)] {
+        &self.suggestions
+    }
+
+    /// Keep only the suggestions matching `filter`, the selection is reset
+    pub fn retain_suggestions(&mut self, filter: impl Fn(&(String, String)) -> bool) {
+        self.suggestions.retain(filter);
+        self.goto_first_suggestion();
+    }
+
+    /// Index of the suggestion selected with `goto_next/previous_suggestion`
+    pub fn selected_suggestion_idx(&self) -> Option<usize> {
+        self.suggestion_idx.checked_sub(1)
+    }
+
+    /// Take the inline suggestion to insert it, the suggestion acceptance is recorded
+    pub fn accept_active_suggestion(&mut self) -> Option<String> {
+        let suggestion = self.active_suggestion.take()?;
+        if let Some((name, _)) = self.current_suggestion() {
+            self.ranking.record(&name);
+        }
+        Some(suggestion)
+    }
+
+    /// The suggestion was picked from the completion panel
+    pub fn record_acceptance(&mut self, name: &str) {
+        self.ranking.record(name);
+    }
+
+    pub fn full_suggestion(s: &(String, String)) -> String {
         if !s.1.is_empty() {
             s.0.to_owned() + ": " + &s.1
         } else {
@@ -186,36 +243,70 @@ impl Racer {
         buffer: &super::Buffer,
         repl: &mut crate::irust::repl::Repl,
     ) -> Result<()> {
+        // check for lock
+        if self.update_lock {
+            return Ok(());
+        }
+
         // get the buffer as string
         let buffer: String = buffer.iter().take(buffer.buffer_pos).collect();
 
-        // don't autocomplete shell commands
-        if buffer.starts_with("::") {
+        self.show_suggestions_inner(buffer, repl, true)?;
+
+        Ok(())
+    }
+
+    /// Like `update_suggestions` but crate names only come from the cache, it runs on every key
+    pub fn update_live_suggestions(
+        &mut self,
+        buffer: &super::Buffer,
+        repl: &mut crate::irust::repl::Repl,
+    ) -> Result<()> {
+        if self.update_lock {
             return Ok(());
         }
+        let buffer: String = buffer.iter().take(buffer.buffer_pos).collect();
+        self.show_suggestions_inner(buffer, repl, false)
+    }
 
-        self.show_suggestions_inner(buffer, repl)?;
+    /// Definition of `name`, completed at the end of `input`, exp: `fn foo(a: u8) -> u8`
+    ///
+    /// The current suggestions are cleared
+    pub fn definition_of(
+        &mut self,
+        input: String,
+        name: &str,
+        repl: &mut crate::irust::repl::Repl,
+    ) -> Result<Option<String>> {
+        let update_lock = std::mem::replace(&mut self.update_lock, false);
+        let result = self.show_suggestions_inner(input, repl, false);
+        self.update_lock = update_lock;
+        result?;
 
-        Ok(())
+        let definition = self
+            .suggestions
+            .iter()
+            .find(|(suggestion, _)| suggestion == name)
+            .map(|(_, definition)| definition.clone());
+        self.suggestions.clear();
+        self.goto_first_suggestion();
+        Ok(definition)
     }
 
     fn show_suggestions_inner(
         &mut self,
         buffer: String,
         repl: &mut crate::irust::repl::Repl,
+        search_crates: bool,
     ) -> Result<()> {
         if buffer.starts_with(':') {
-            // Auto complete IRust commands
-            self.suggestions = self
-                .cmds
-                .iter()
-                .filter(|c| c.starts_with(&buffer[1..]))
-                // place holder for IRust command definitions
-                .map(|c| (c.to_owned(), String::new()))
-                .collect();
+            // Auto complete IRust commands and their arguments
+            self.suggestions = complete_irust_input(&buffer, search_crates);
+            self.ranking.sort(&mut self.suggestions);
+            self.goto_first_suggestion();
         } else {
             // Auto complete rust code
-            let mut racer = self;
+            let racer = self;
 
             racer.cursor.0 = repl.body.len() + StringTools::new_lines_count(&buffer);
 
@@ -228,9 +319,7 @@ impl Racer {
                 }
             }
 
-            repl.eval_in_tmp_repl(buffer, move || -> Result<()> {
-                racer.complete_code().map_err(From::from)
-            })?;
+            repl.eval_in_tmp_repl(buffer, move || -> Result<()> { racer.complete_code() })?;
         }
 
         Ok(())
@@ -317,7 +406,7 @@ impl Racer {
         let suggestions_num = std::cmp::min(self.suggestions.len(), options.racer_max_suggestions);
 
         // if The total input + suggestion >  screen height don't draw the suggestions
-        if printer.cursor.buffer_pos_to_cursor_pos(&buffer).1 + suggestions_num
+        if printer.cursor.buffer_pos_to_cursor_pos(buffer).1 + suggestions_num
             >= printer.cursor.height() - 1
         {
             return Ok(());
@@ -356,13 +445,13 @@ impl Racer {
         }
 
         printer.cursor.save_position();
-        printer.cursor.move_to_input_last_row(&buffer);
+        printer.cursor.move_to_input_last_row(buffer);
 
         let max_width = printer.cursor.width() - 1;
         printer.cursor.current_pos().0 = 0;
         printer.cursor.goto_internal_pos();
         printer.cursor.raw.move_down(1)?;
-        printer.writer.raw.clear(ClearType::FromCursorDown)?;
+        printer.clear_from_cursor_down()?;
         printer.cursor.raw.move_up(1)?;
 
         printer
@@ -408,7 +497,7 @@ impl Racer {
         printer.writer.raw.reset_color()?;
         printer.cursor.restore_position();
         printer.cursor.goto_internal_pos();
-        printer.recalculate_bounds(highlight(&buffer, theme))?;
+        printer.recalculate_bounds(highlight(buffer, theme))?;
 
         Ok(())
     }
@@ -423,3 +512,81 @@ impl Racer {
         Ok(())
     }
 }
+
+/// `search_crates` allows `cargo search` (a network request) when no cached crate matches
+fn complete_irust_input(buffer: &str, search_crates: bool) -> Vec<(String, String)> {
+    let (cmd, arg) = match buffer.split_once(' ') {
+        Some((cmd, arg)) => (cmd, Some(arg)),
+        None => (buffer, None),
+    };
+
+    match (cmd, arg) {
+        // don't autocomplete shell commands
+        (cmd, None) if cmd.starts_with("::") => vec![],
+        (cmd, None) => IRUST_COMMANDS
+            .iter()
+            .filter(|c| c.starts_with(&cmd[1..]))
+            // place holder for IRust command definitions
+            .map(|c| (c.to_string(), String::new()))
+            .collect(),
+        (":add", Some(arg)) => {
+            let krate = arg.rsplit(' ').next().unwrap_or_default();
+            if krate.is_empty() || krate.starts_with('-') {
+                return vec![];
+            }
+            complete_crate(krate, search_crates)
+        }
+        (":load", Some(path))
+        | (":watch", Some(path))
+        | (":cd", Some(path))
+        | ("::cd", Some(path)) => complete_path(path.trim_start()),
+        _ => vec![],
+    }
+}
+
+/// Complete crate names from the offline cache, fallback to `cargo search` if nothing is cached and `search` is set
+fn complete_crate(prefix: &str, search: bool) -> Vec<(String, String)> {
+    let matches = |crates: Vec<super::crates::Crate>| -> Vec<(String, String)> {
+        crates
+            .into_iter()
+            .filter(|c| c.name.starts_with(prefix))
+            .map(|c| (c.name, format!("{} {}", c.version, c.description)))
+            .collect()
+    };
+
+    let suggestions = matches(super::crates::cached());
+    if !suggestions.is_empty() || !search {
+        return suggestions;
+    }
+    matches(super::crates::search(prefix, 20).unwrap_or_default())
+}
+
+fn complete_path(path: &str) -> Vec<(String, String)> {
+    let (dir, file_prefix) = match path.rfind(std::path::is_separator) {
+        Some(idx) => (&path[..=idx], &path[idx + 1..]),
+        None => ("", path),
+    };
+    let read_dir = match std::fs::read_dir(if dir.is_empty() { "." } else { dir }) {
+        Ok(read_dir) => read_dir,
+        Err(_) => return vec![],
+    };
+
+    let mut suggestions: Vec<(String, String)> = read_dir
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            // hidden files are only completed if explicitly asked for
+            if !name.starts_with(file_prefix) || (name.starts_with('.') && file_prefix.is_empty()) {
+                return None;
+            }
+            let separator = if entry.file_type().ok()?.is_dir() {
+                std::path::MAIN_SEPARATOR.to_string()
+            } else {
+                String::new()
+            };
+            Some((format!("{}{}{}", dir, name, separator), String::new()))
+        })
+        .collect();
+    suggestions.sort();
+    suggestions
+}
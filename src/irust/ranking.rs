@@ -0,0 +1,71 @@
+use super::cargo_cmds::IRUST_DIR;
+use super::Result;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// How many times each suggestion was accepted, saved next to the history file
+static RANKING_FILE: Lazy<PathBuf> = Lazy::new(|| IRUST_DIR.join("suggestions_ranking"));
+
+/// Orders suggestions by how often they were accepted on this machine
+#[derive(Default)]
+pub struct Ranking {
+    accepted: HashMap<String, usize>,
+}
+
+impl Ranking {
+    pub fn load() -> Self {
+        std::fs::read_to_string(&*RANKING_FILE)
+            .map(|ranking| parse_ranking(&ranking))
+            .unwrap_or_default()
+    }
+
+    /// The suggestion was accepted, the ranking is saved right away so other IRust instances see it
+    pub fn record(&mut self, name: &str) {
+        *self.accepted.entry(name.to_string()).or_insert(0) += 1;
+        // The ranking is optional
+        let _ = self.save();
+    }
+
+    /// Most accepted suggestions first, the order of the others is kept
+    pub fn sort(&self, suggestions: &mut [(String, String)]) {
+        suggestions.sort_by_key(|(name, _)| {
+            std::cmp::Reverse(self.accepted.get(name).copied().unwrap_or(0))
+        });
+    }
+
+    fn save(&self) -> Result<()> {
+        let mut ranking: Vec<String> = self
+            .accepted
+            .iter()
+            .map(|(name, count)| format!("{}\t{}", count, name))
+            .collect();
+        ranking.sort();
+        std::fs::write(&*RANKING_FILE, ranking.join("\n"))?;
+        Ok(())
+    }
+}
+
+/// One `count\tname` per line
+fn parse_ranking(ranking: &str) -> Ranking {
+    let accepted = ranking
+        .lines()
+        .filter_map(|line| {
+            let (count, name) = line.split_once('\t')?;
+            Some((name.to_string(), count.parse().ok()?))
+        })
+        .collect();
+    Ranking { accepted }
+}
+
+#[test]
+fn ranking_test() {
+    let ranking = parse_ranking("3\tpush\n1\tlen\nbad line\n");
+    let mut suggestions: Vec<(String, String)> = ["iter", "len", "push"]
+        .iter()
+        .map(|name| (name.to_string(), String::new()))
+        .collect();
+    ranking.sort(&mut suggestions);
+    let names: Vec<&str> = suggestions.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["push", "len", "iter"]);
+}
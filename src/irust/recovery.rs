@@ -0,0 +1,71 @@
+//! Leave the terminal usable and save the session when IRust is stopped by a panic or a signal
+//!
+//! A panic of the main thread unwinds to `main`, dropping `IRust` saves the history and `Printer` restores the terminal,
+//! the panic message is printed after that since it would be garbled in raw mode
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How often the main loop checks for a termination request while waiting for input
+pub const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+static TERMINATION_REQUESTED: AtomicBool = AtomicBool::new(false);
+static PANIC_MESSAGE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Keep the panic message of the main thread for `take_panic_message`, other threads use the default hook
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() != Some("main") {
+            return default_hook(info);
+        }
+        let backtrace = std::backtrace::Backtrace::capture();
+        // exp: `panicked at src/irust.rs:10:5:\nmessage`
+        let mut message = info.to_string();
+        if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+            message += &format!("\nstack backtrace:\n{}", backtrace);
+        }
+        if let Ok(mut panic_message) = PANIC_MESSAGE.lock() {
+            *panic_message = Some(message);
+        }
+    }));
+}
+
+pub fn take_panic_message() -> Option<String> {
+    PANIC_MESSAGE.lock().ok()?.take()
+}
+
+#[cfg(unix)]
+extern "C" fn request_termination(signal: nix::libc::c_int) {
+    use std::convert::TryFrom;
+    TERMINATION_REQUESTED.store(true, Ordering::SeqCst);
+    // a second signal stops IRust right away in case the exit hangs
+    if let Ok(signal) = nix::sys::signal::Signal::try_from(signal) {
+        let _ = unsafe { nix::sys::signal::signal(signal, nix::sys::signal::SigHandler::SigDfl) };
+    }
+}
+
+/// SIGTERM and SIGHUP make the main loop exit normally instead of killing IRust with the terminal in raw mode
+pub fn install_signal_handlers() {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{signal, SigHandler, Signal};
+        for sig in [Signal::SIGTERM, Signal::SIGHUP] {
+            // best effort, the default behavior is kept on failure
+            let _ = unsafe { signal(sig, SigHandler::Handler(request_termination)) };
+        }
+    }
+}
+
+pub fn termination_requested() -> bool {
+    TERMINATION_REQUESTED.load(Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+#[test]
+fn termination_request_test() {
+    install_signal_handlers();
+    nix::sys::signal::raise(nix::sys::signal::Signal::SIGHUP).unwrap();
+    assert!(termination_requested());
+}
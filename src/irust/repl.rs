@@ -1,5 +1,7 @@
 use super::cargo_cmds::*;
+use super::meta::META_MOD;
 use super::Result;
+use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
 use std::process::ExitStatus;
 
@@ -9,6 +11,151 @@ const FN_MAIN: &str = "fn main() {";
 pub struct Repl {
     pub body: Vec<String>,
     cursor: usize,
+    items: Vec<TrackedItem>,
+    /// Lines inserted by `:load` by canonical file path, replaced when the same file is loaded again
+    loaded: Vec<(std::path::PathBuf, Vec<String>)>,
+    /// Set once the session uses `.await`, main is then run by this runtime
+    pub async_runtime: Option<AsyncRuntime>,
+    /// Lines put before main in every session, see `Options::prelude`
+    prelude: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AsyncRuntime {
+    Tokio,
+    AsyncStd,
+    Pollster,
+}
+
+impl AsyncRuntime {
+    pub fn crate_name(self) -> &'static str {
+        use AsyncRuntime::*;
+        match self {
+            Tokio => "tokio",
+            AsyncStd => "async-std",
+            Pollster => "pollster",
+        }
+    }
+
+    /// `cargo add` arguments, the features providing the main attribute are required
+    pub fn dependency(self) -> Vec<String> {
+        use AsyncRuntime::*;
+        let features = match self {
+            Tokio => "full",
+            AsyncStd => "attributes",
+            Pollster => "macro",
+        };
+        vec![
+            self.crate_name().to_string(),
+            "--features".to_string(),
+            features.to_string(),
+        ]
+    }
+
+    fn main_attribute(self) -> String {
+        format!("#[{}::main]", self.crate_name().replace('-', "_"))
+    }
+
+    /// Kept on one line so the body lines numbers don't change
+    fn main_line(self) -> String {
+        format!("{} async {}", self.main_attribute(), FN_MAIN)
+    }
+}
+
+/// Split the leading crate attributes (`#![...]`) from the rest of the input
+pub fn split_crate_attributes(input: &str) -> (Vec<&str>, String) {
+    let mut lines = input.lines().peekable();
+    let mut attributes = vec![];
+    while let Some(line) = lines.peek() {
+        let line = line.trim();
+        if line.starts_with("#!") {
+            attributes.push(line);
+        } else if !line.is_empty() {
+            break;
+        }
+        lines.next();
+    }
+    (attributes, lines.collect::<Vec<_>>().join("\n"))
+}
+
+/// The code awaits a future, exp: `foo().await`
+pub fn uses_await(code: &str) -> bool {
+    code.match_indices(".await").any(|(idx, m)| {
+        !code[idx + m.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// An input that defines a named item (or let binding), tracked so it can be found in the body later
+#[derive(Clone)]
+struct TrackedItem {
+    name: String,
+    kind: ItemKind,
+    namespaces: &'static [Namespace],
+    lines: Vec<String>,
+}
+
+/// Items only conflict with the items of the same namespace, exp: `struct foo {..}` and `fn foo()` can coexist
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Namespace {
+    Type,
+    Value,
+    Macro,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ItemKind {
+    Function,
+    Struct,
+    Enum,
+    Union,
+    Trait,
+    TypeAlias,
+    Const,
+    Static,
+    Module,
+    Macro,
+    Let,
+}
+
+impl ItemKind {
+    /// The namespaces of the item name, a tuple or unit struct also defines a constructor in the value namespace
+    ///
+    /// Let bindings are left out since shadowing them is valid
+    fn namespaces(self, braced_struct: bool) -> &'static [Namespace] {
+        use ItemKind::*;
+        match self {
+            Struct if braced_struct => &[Namespace::Type],
+            Struct => &[Namespace::Type, Namespace::Value],
+            Enum | Union | Trait | TypeAlias | Module => &[Namespace::Type],
+            Function | Const | Static => &[Namespace::Value],
+            Macro => &[Namespace::Macro],
+            Let => &[],
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        use ItemKind::*;
+        match self {
+            Function => "function",
+            Struct => "struct",
+            Enum => "enum",
+            Union => "union",
+            Trait => "trait",
+            TypeAlias => "type alias",
+            Const => "const",
+            Static => "static",
+            Module => "module",
+            Macro => "macro",
+            Let => "let binding",
+        }
+    }
+}
+
+/// A snapshot of the repl code and its dependencies that can be restored later
+pub struct Checkpoint {
+    pub name: String,
+    repl: Repl,
+    cargo_toml: String,
 }
 
 impl Repl {
@@ -19,40 +166,113 @@ impl Repl {
                 "} // Do not write past this line (it will corrupt the repl)".to_string(),
             ],
             cursor: 1,
+            items: Vec::new(),
+            loaded: Vec::new(),
+            async_runtime: None,
+            prelude: Vec::new(),
         }
     }
 
+    /// Replace the prelude lines before main, they are kept on reset
+    pub fn set_prelude(&mut self, prelude: Vec<String>) {
+        for line in std::mem::take(&mut self.prelude) {
+            if let Some(idx) = self.body[..self.main_idx()].iter().position(|l| *l == line) {
+                self.body.remove(idx);
+                self.cursor -= 1;
+            }
+        }
+        for line in &prelude {
+            self.body.insert(self.main_idx(), line.clone());
+            self.cursor += 1;
+        }
+        self.prelude = prelude;
+    }
+
     pub fn update_from_extern_main_file(&mut self) -> Result<()> {
         let main_file = std::fs::read_to_string(&*MAIN_FILE_EXTERN)?;
-        let lines_num = main_file.lines().count();
-        if lines_num < 2 {
+        let body = self.body_from_main_file(&main_file);
+        if body.len() < 2 || !body.iter().any(|line| line == FN_MAIN) {
             return Err("main.rs file corrupted, resetting irust..".into());
         }
-        let cursor_pos = lines_num - 1;
 
-        *self = Self {
-            body: main_file.lines().map(ToOwned::to_owned).collect(),
-            cursor: cursor_pos,
-        };
+        // keep tracked items, they are looked up by content so they still work if they weren't edited
+        self.cursor = body.len() - 1;
+        self.body = body;
         Ok(())
     }
 
+    /// The body lines of a main.rs written by `write_to_extern`, the async main is turned back into `FN_MAIN`
+    ///
+    /// rustfmt puts the main attribute on its own line, both forms are recognized
+    fn body_from_main_file(&self, main_file: &str) -> Vec<String> {
+        let runtime = match self.async_runtime {
+            Some(runtime) => runtime,
+            None => return main_file.lines().map(ToOwned::to_owned).collect(),
+        };
+        let (main_line, attribute) = (runtime.main_line(), runtime.main_attribute());
+        let async_main = format!("async {}", FN_MAIN);
+
+        let mut body = vec![];
+        let mut lines = main_file.lines().peekable();
+        while let Some(line) = lines.next() {
+            if line.trim() == main_line {
+                body.push(FN_MAIN.to_string());
+            } else if line.trim() == attribute
+                && lines.peek().map(|next| next.trim()) == Some(async_main.as_str())
+            {
+                lines.next();
+                body.push(FN_MAIN.to_string());
+            } else {
+                body.push(line.to_owned());
+            }
+        }
+        body
+    }
+
     // Note: Insert must be followed by write_to_extern if persistance is needed
     // Or else it will be overwritten by the main_extern thread
     // Fix this
     pub fn insert(&mut self, input: String) {
-        // CRATE_ATTRIBUTE are special in the sense that they should be inserted outside of the main function
+        // crate attributes are special in the sense that they should be inserted outside of the main function
         // #![feature(unboxed_closures)]
         // fn main() {}
-        const CRATE_ATTRIBUTE: &str = "#!";
-
-        let outside_main = input.trim_start().starts_with(CRATE_ATTRIBUTE);
-        if outside_main {
-            for line in input.lines() {
-                self.body.insert(0, line.to_owned());
+        let (attributes, input) = split_crate_attributes(&input);
+        for attribute in attributes {
+            // keep them in order before the prelude and main, each one only once
+            let main_idx = self.main_idx();
+            if !self.body[..main_idx].iter().any(|l| l.trim() == attribute) {
+                let idx = self.body[..main_idx]
+                    .iter()
+                    .position(|l| !l.trim_start().starts_with("#!"))
+                    .unwrap_or(main_idx);
+                self.body.insert(idx, attribute.to_owned());
                 self.cursor += 1;
             }
-        } else {
+        }
+
+        if !input.trim().is_empty() {
+            if let Some((kind, name, rest)) = item_declaration(&input) {
+                let name = name.to_string();
+                let namespaces = kind.namespaces(is_braced_struct(rest));
+                // redefining an item replaces its previous definition instead of causing a duplicate definition error
+                // let bindings are left alone since shadowing them is valid
+                if kind != ItemKind::Let {
+                    while let Some(idx) = self.items.iter().rposition(|item| {
+                        item.name == name
+                            && item.namespaces.iter().any(|ns| namespaces.contains(ns))
+                    }) {
+                        let item = self.items.remove(idx);
+                        // if the previous definition can't be found, let the compiler report the conflict
+                        let _ = self.remove_lines(&item.lines);
+                    }
+                }
+                self.items.push(TrackedItem {
+                    name,
+                    kind,
+                    namespaces,
+                    lines: input.lines().map(ToOwned::to_owned).collect(),
+                });
+            }
             for line in input.lines() {
                 self.body.insert(self.cursor, line.to_owned());
                 self.cursor += 1;
@@ -60,14 +280,68 @@ impl Repl {
         }
     }
 
+    fn main_idx(&self) -> usize {
+        // safe unwrap: main is never removed from the body, a synced file without it is rejected
+        self.body.iter().position(|l| l == FN_MAIN).unwrap()
+    }
+
+    /// Index of the body line where the next input is inserted
+    pub fn input_line(&self) -> usize {
+        self.cursor
+    }
+
+    /// Insert code loaded from a file, the code inserted by the previous load of the same file is removed
+    pub fn insert_loaded(&mut self, path: std::path::PathBuf, code: String) {
+        if let Some(idx) = self.loaded.iter().position(|(loaded, _)| *loaded == path) {
+            let (_, lines) = self.loaded.remove(idx);
+            // the loaded code might have been edited with `:edit`, keep it then
+            let _ = self.remove_lines(&lines);
+        }
+        let lines = self.insert_file_code(code);
+        self.loaded.push((path, lines));
+    }
+
+    /// Insert the code of a file as is, returns its lines
+    pub fn insert_file_code(&mut self, code: String) -> Vec<String> {
+        // the file code is not tracked as a single item, its first item could be redefined later
+        let lines: Vec<String> = code.lines().map(ToOwned::to_owned).collect();
+        for line in &lines {
+            self.body.insert(self.cursor, line.clone());
+            self.cursor += 1;
+        }
+        lines
+    }
+
     pub fn reset(&mut self, toolchain: ToolChain) -> Result<()> {
         self.prepare_ground(toolchain)?;
+        let prelude = std::mem::take(&mut self.prelude);
         *self = Self::new();
+        self.set_prelude(prelude);
+        Ok(())
+    }
+
+    pub fn checkpoint(&self, name: String) -> Result<Checkpoint> {
+        Ok(Checkpoint {
+            name,
+            repl: self.clone(),
+            cargo_toml: std::fs::read_to_string(&*CARGO_TOML_FILE)?,
+        })
+    }
+
+    /// Restore the repl code to the checkpoint, and optionally its Cargo.toml
+    ///
+    /// Restoring the dependencies requires a rebuild afterwards
+    pub fn rollback(&mut self, checkpoint: &Checkpoint, restore_deps: bool) -> Result<()> {
+        *self = checkpoint.repl.clone();
+        self.write()?;
+        if restore_deps {
+            std::fs::write(&*CARGO_TOML_FILE, &checkpoint.cargo_toml)?;
+        }
         Ok(())
     }
 
     pub fn show(&self) -> String {
-        let mut current_code = self.body.join("\n");
+        let mut current_code = self.code();
         // If cargo fmt is present foramt output else ignore
         if let Ok(fmt_code) = cargo_fmt(&current_code) {
             current_code = fmt_code;
@@ -81,38 +355,27 @@ impl Repl {
         Ok(())
     }
 
-    pub fn eval(&mut self, input: String, toolchain: ToolChain) -> Result<(ExitStatus, String)> {
-        // `\n{}\n` to avoid print appearing in error messages
-        let eval_statement = format!("println!(\"{{:?}}\", {{\n{}\n}});", input);
-        let mut eval_result = String::new();
-        let mut status = None;
-
-        self.eval_in_tmp_repl(eval_statement, || -> Result<()> {
-            let (s, result) = cargo_run(true, false, toolchain)?;
-            eval_result = result;
-            status = Some(s);
-            Ok(())
-        })?;
-        // status is guarenteed to be some
-
-        Ok((status.unwrap(), eval_result))
+    /// Like `prepare_ground` but the session crate is built while the user types the first input
+    pub fn prepare_ground_in_background(&self, toolchain: ToolChain) -> Result<()> {
+        cargo_new_in_background(toolchain)?;
+        Ok(())
     }
 
-    pub fn eval_build(
+    /// Evaluate the input, the program stderr is returned separately
+    pub fn eval(
         &mut self,
         input: String,
         toolchain: ToolChain,
-    ) -> Result<(ExitStatus, String)> {
-        let orig_body = self.body.clone();
-        let orig_cursor = self.cursor;
-
-        self.insert(input);
-        self.write()?;
-        let (status, output) = cargo_build_output(true, false, toolchain)?;
+    ) -> Result<(ExitStatus, String, String)> {
+        let eval_statement = format!("println!(\"{{:?}}\", {{\n{}\n}});", input);
+        let mut eval_result = None;
 
-        self.body = orig_body;
-        self.cursor = orig_cursor;
-        Ok((status, output))
+        self.eval_in_tmp_repl(eval_statement, || -> Result<()> {
+            eval_result = Some(cargo_run_separate_stderr(true, false, toolchain)?);
+            Ok(())
+        })?;
+        // eval_result is guarenteed to be some
+        Ok(eval_result.unwrap())
     }
 
     pub fn eval_in_tmp_repl(
@@ -120,17 +383,15 @@ impl Repl {
         input: String,
         mut f: impl FnMut() -> Result<()>,
     ) -> Result<()> {
-        let orig_body = self.body.clone();
-        let orig_cursor = self.cursor;
+        let orig_repl = self.clone();
 
         self.insert(input);
-        self.write()?;
-        f()?;
+        let result = self.write().map_err(Into::into).and_then(|_| f());
 
-        self.body = orig_body;
-        self.cursor = orig_cursor;
+        // restore the repl even if the evaluation failed or was interrupted
+        *self = orig_repl;
 
-        Ok(())
+        result
     }
 
     pub fn add_dep(&self, dep: &[String]) -> std::io::Result<std::process::Child> {
@@ -150,9 +411,28 @@ impl Repl {
         Ok(result)
     }
 
+    /// The main.rs content, with main run by the async runtime if needed
+    fn code(&self) -> String {
+        match self.async_runtime {
+            Some(runtime) => self
+                .body
+                .iter()
+                .map(|line| {
+                    if line == FN_MAIN {
+                        runtime.main_line()
+                    } else {
+                        line.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => self.body.join("\n"),
+        }
+    }
+
     pub fn write(&self) -> io::Result<()> {
         let mut main_file = std::fs::File::create(&*MAIN_FILE)?;
-        write!(main_file, "{}", self.body.join("\n"))?;
+        write!(main_file, "{}\n{}", self.code(), META_MOD)?;
 
         Ok(())
     }
@@ -160,7 +440,7 @@ impl Repl {
     // Used for external editors
     pub fn write_to_extern(&self) -> io::Result<()> {
         let mut main_file = std::fs::File::create(&*MAIN_FILE_EXTERN)?;
-        write!(main_file, "{}", self.body.join("\n"))?;
+        write!(main_file, "{}", self.code())?;
 
         Ok(())
     }
@@ -170,26 +450,30 @@ impl Repl {
         let mut body = self.body.clone();
 
         // safe unwrap
-        let main_idx = body.iter().position(|l| l == FN_MAIN).unwrap();
+        let main_idx = self.main_idx();
         body.remove(main_idx); // remove fn main
         body.pop(); // remove last }
 
-        write!(lib_file, "{}", body.join("\n"))?;
+        write!(lib_file, "{}\n{}", body.join("\n"), META_MOD)?;
 
         Ok(())
     }
 
     pub fn pop(&mut self) {
-        if self.body.len() > 2 {
+        // don't pop main or the crate attributes before it
+        let main_idx = self.body.iter().position(|l| l == FN_MAIN);
+        if main_idx.is_some_and(|main_idx| self.cursor > main_idx + 1) {
             self.body.remove(self.cursor - 1);
             self.cursor -= 1;
         }
     }
 
+    /// Remove a line of the session code, line 1 is the first line after `fn main() {`
     pub fn del(&mut self, line_num: &str) -> Result<()> {
         if let Ok(line_num) = line_num.parse::<usize>() {
-            if line_num != 0 && line_num + 1 < self.body.len() {
-                self.body.remove(line_num);
+            let idx = self.main_idx() + line_num;
+            if line_num != 0 && idx < self.cursor {
+                self.body.remove(idx);
                 self.cursor -= 1;
                 return Ok(());
             }
@@ -197,4 +481,379 @@ impl Repl {
 
         Err("Incorrect line number".into())
     }
+
+    /// Kind of the last item or let binding defined with this name
+    pub fn item_kind(&self, name: &str) -> Option<ItemKind> {
+        self.items
+            .iter()
+            .rev()
+            .find(|item| item.name == name)
+            .map(|item| item.kind)
+    }
+
+    /// Delete the last input that defined an item or a let binding with this name
+    pub fn del_item(&mut self, name: &str) -> Result<ItemKind> {
+        let idx = self
+            .items
+            .iter()
+            .rposition(|item| item.name == name)
+            .ok_or_else(|| format!("No item named `{}` in the repl", name))?;
+        let item = self.items.remove(idx);
+        self.remove_lines(&item.lines)?;
+        Ok(item.kind)
+    }
+
+    /// Remove the last occurrence of these lines inside main
+    fn remove_lines(&mut self, lines: &[String]) -> Result<()> {
+        let len = lines.len();
+        // the body can be shorter than when the lines were inserted, exp: after `:edit`
+        let start = (self.main_idx() + 1..=self.cursor.min(self.body.len()).saturating_sub(len))
+            .rev()
+            .find(|idx| self.body[*idx..*idx + len] == *lines)
+            .ok_or("The item code was not found in the repl (was it edited?)")?;
+        self.body.drain(start..start + len);
+        self.cursor -= len;
+        Ok(())
+    }
+}
+
+/// Extract the kind and the name of the item (or let binding) defined at the start of the input,
+/// followed by the code after the name
+///
+/// exp: `#[derive(Debug)] pub struct Point {..}` => (Struct, "Point", " {..}")
+fn item_declaration(input: &str) -> Option<(ItemKind, &str, &str)> {
+    fn ident(s: &str) -> &str {
+        let end = s
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(s.len());
+        &s[..end]
+    }
+    // skip a delimited group that starts at the beginning of s, exp: `(crate)` `[derive(Debug)]`
+    fn skip_group(s: &str, open: char, close: char) -> &str {
+        let mut depth = 0;
+        for (idx, c) in s.char_indices() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return &s[idx + 1..];
+                }
+            }
+        }
+        ""
+    }
+
+    let mut code = input.trim_start();
+    let kind = loop {
+        // skip attributes and comments
+        if let Some(rest) = code.strip_prefix('#') {
+            code = skip_group(rest, '[', ']').trim_start();
+            continue;
+        }
+        if code.starts_with("//") {
+            code = code
+                .find('\n')
+                .map(|idx| &code[idx..])
+                .unwrap_or("")
+                .trim_start();
+            continue;
+        }
+
+        let word = ident(code);
+        let rest = code[word.len()..].trim_start();
+        let kind = match word {
+            "pub" if rest.starts_with('(') => {
+                code = skip_group(rest, '(', ')').trim_start();
+                continue;
+            }
+            "extern" if rest.starts_with('"') => {
+                code = rest[1..]
+                    .find('"')
+                    .map(|idx| &rest[idx + 2..])
+                    .unwrap_or("")
+                    .trim_start();
+                continue;
+            }
+            // const fn
+            "const" if matches!(ident(rest), "fn" | "unsafe" | "async" | "extern") => {
+                code = rest;
+                continue;
+            }
+            "pub" | "async" | "unsafe" | "extern" | "default" => {
+                code = rest;
+                continue;
+            }
+            "fn" => ItemKind::Function,
+            "struct" => ItemKind::Struct,
+            "enum" => ItemKind::Enum,
+            "union" => ItemKind::Union,
+            "trait" => ItemKind::Trait,
+            "type" => ItemKind::TypeAlias,
+            "const" => ItemKind::Const,
+            "static" => ItemKind::Static,
+            "mod" => ItemKind::Module,
+            "let" => ItemKind::Let,
+            "macro_rules" if rest.starts_with('!') => {
+                code = rest[1..].trim_start();
+                break ItemKind::Macro;
+            }
+            _ => return None,
+        };
+        code = rest;
+        break kind;
+    };
+
+    if matches!(kind, ItemKind::Let | ItemKind::Static) && ident(code) == "mut" {
+        code = code[3..].trim_start();
+    }
+
+    let name = ident(code);
+    if name.is_empty() {
+        // exp: let (a, b) = ..
+        None
+    } else {
+        Some((kind, name, &code[name.len()..]))
+    }
+}
+
+/// The code after a struct name declares its fields with braces, exp: `<T> where T: Fn(u8) { f: T }`
+fn is_braced_struct(rest: &str) -> bool {
+    let mut rest = rest.trim_start();
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        let mut previous = ' ';
+        for (idx, c) in rest.char_indices() {
+            match c {
+                '<' => depth += 1,
+                // the `>` of `->` in a `Fn() -> T` bound doesn't close anything
+                '>' if previous != '-' => depth -= 1,
+                _ => (),
+            }
+            previous = c;
+            if depth == 0 {
+                rest = rest[idx + 1..].trim_start();
+                break;
+            }
+        }
+    }
+    // a tuple struct `where` clause comes after its fields
+    rest.starts_with('{') || rest.starts_with("where")
+}
+
+#[test]
+fn item_name_test() {
+    let cases = [
+        ("fn foo() {}", Some((ItemKind::Function, "foo"))),
+        (
+            "#[derive(Debug, Clone)]\npub(crate) struct Point<T> { x: T }",
+            Some((ItemKind::Struct, "Point")),
+        ),
+        (
+            "pub const fn bar() -> u8 { 1 }",
+            Some((ItemKind::Function, "bar")),
+        ),
+        (
+            "pub unsafe extern \"C\" fn ffi() {}",
+            Some((ItemKind::Function, "ffi")),
+        ),
+        ("const MAX: usize = 5;", Some((ItemKind::Const, "MAX"))),
+        (
+            "static mut COUNTER: u8 = 0;",
+            Some((ItemKind::Static, "COUNTER")),
+        ),
+        ("let mut v = vec![1];", Some((ItemKind::Let, "v"))),
+        ("macro_rules! m { () => {} }", Some((ItemKind::Macro, "m"))),
+        ("// comment\nenum E { A }", Some((ItemKind::Enum, "E"))),
+        ("let (a, b) = (1, 2);", None),
+        ("impl Point {}", None),
+        ("println!(\"hello\");", None),
+    ];
+    for (input, expected) in cases.iter() {
+        assert_eq!(
+            item_declaration(input).map(|(kind, name, _)| (kind, name)),
+            *expected,
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn redefined_item_is_replaced() {
+    let mut repl = Repl::new();
+    repl.insert("fn foo() -> u8 {\n    1\n}".to_string());
+    repl.insert("let a = foo();".to_string());
+    repl.insert("fn foo() -> u8 {\n    2\n}".to_string());
+    repl.insert("let a = foo();".to_string());
+
+    assert_eq!(
+        repl.body[1..repl.body.len() - 1],
+        [
+            "let a = foo();",
+            "fn foo() -> u8 {",
+            "    2",
+            "}",
+            "let a = foo();"
+        ]
+    );
+}
+
+#[test]
+fn items_of_different_namespaces_coexist() {
+    let mut repl = Repl::new();
+    repl.insert("struct foo { a: u8 }".to_string());
+    repl.insert("fn foo() {}".to_string());
+    repl.insert("struct Bar<T> where T: Fn(u8) { f: T }".to_string());
+    repl.insert("const Bar: u8 = 1;".to_string());
+    assert_eq!(repl.body.len(), 6);
+
+    // a tuple struct constructor is a function
+    repl.insert("struct foo(u8);".to_string());
+    assert_eq!(
+        repl.body[1..repl.body.len() - 1],
+        [
+            "struct Bar<T> where T: Fn(u8) { f: T }",
+            "const Bar: u8 = 1;",
+            "struct foo(u8);"
+        ]
+    );
+}
+
+#[test]
+fn braced_struct_with_fn_bound() {
+    assert!(is_braced_struct("<F: Fn() -> u8> { f: F }"));
+    assert!(!is_braced_struct("<F: Fn() -> u8>(F);"));
+}
+
+#[test]
+fn reloaded_code_replaces_previous_load() {
+    let mut repl = Repl::new();
+    repl.insert("let a = 1;".to_string());
+    repl.insert_loaded("a.rs".into(), "fn foo() {}\nfn bar() {}".to_string());
+    repl.insert_loaded("b.rs".into(), "fn baz() {}".to_string());
+    repl.insert_loaded("a.rs".into(), "fn foo() {}".to_string());
+
+    assert_eq!(
+        repl.body[1..repl.body.len() - 1],
+        ["let a = 1;", "fn baz() {}", "fn foo() {}"]
+    );
+}
+
+#[test]
+fn uses_await_test() {
+    assert!(uses_await("let a = foo().await;"));
+    assert!(uses_await("bar(foo().await)"));
+    assert!(!uses_await("a.awaited"));
+    assert!(!uses_await("async fn foo() {}"));
+}
+
+#[test]
+fn crate_attributes_are_hoisted_in_order() {
+    let mut repl = Repl::new();
+    repl.insert("#![feature(never_type)]\n#![allow(unused)]\nlet a = 1;".to_string());
+    repl.insert("#![allow(unused)]".to_string());
+
+    assert_eq!(
+        repl.body[..repl.body.len() - 1],
+        [
+            "#![feature(never_type)]",
+            "#![allow(unused)]",
+            FN_MAIN,
+            "let a = 1;"
+        ]
+    );
+    repl.pop();
+    repl.pop();
+    assert_eq!(repl.body.len(), 4);
+}
+
+#[test]
+fn prelude_is_replaced() {
+    let mut repl = Repl::new();
+    repl.insert("#![allow(unused)]".to_string());
+    repl.insert("let a = 1;".to_string());
+    repl.set_prelude(vec!["use std::fmt;".to_string()]);
+    repl.set_prelude(vec!["use std::io;".to_string(), "use std::fs;".to_string()]);
+    repl.insert("let b = 2;".to_string());
+    assert_eq!(
+        repl.body,
+        [
+            "#![allow(unused)]",
+            "use std::io;",
+            "use std::fs;",
+            FN_MAIN,
+            "let a = 1;",
+            "let b = 2;",
+            "} // Do not write past this line (it will corrupt the repl)"
+        ]
+    );
+}
+
+#[test]
+fn async_main_survives_rustfmt() {
+    let mut repl = Repl::new();
+    repl.async_runtime = Some(AsyncRuntime::Tokio);
+    repl.insert("let a = foo().await;".to_string());
+    let code = repl.code();
+    assert!(code.starts_with("#[tokio::main] async fn main() {"));
+
+    // what `:edit` gets back after rustfmt
+    let formatted = code.replacen("#[tokio::main] async", "#[tokio::main]\nasync", 1);
+    assert_eq!(repl.body_from_main_file(&formatted), repl.body);
+    assert_eq!(repl.body_from_main_file(&code), repl.body);
+}
+
+#[test]
+fn pop_without_main_does_not_panic() {
+    let mut repl = Repl::new();
+    repl.insert("let a = 1;".to_string());
+    repl.body[0] = "async fn main() {".to_string();
+    repl.pop();
+    assert_eq!(repl.body.len(), 3);
+}
+
+#[test]
+fn del_counts_lines_from_main() {
+    let mut repl = Repl::new();
+    repl.set_prelude(vec!["use std::fmt;".to_string()]);
+    repl.insert("#![allow(unused)]\nlet a = 1;".to_string());
+    repl.insert("let b = 2;".to_string());
+
+    repl.del("1").unwrap();
+    assert_eq!(
+        repl.body,
+        [
+            "#![allow(unused)]",
+            "use std::fmt;",
+            FN_MAIN,
+            "let b = 2;",
+            "} // Do not write past this line (it will corrupt the repl)"
+        ]
+    );
+    // the closing line isn't part of the session code
+    assert!(repl.del("2").is_err());
+    assert!(repl.del("0").is_err());
+}
+
+#[test]
+fn del_item_after_the_body_is_edited() {
+    let mut repl = Repl::new();
+    repl.insert("fn foo() {\n    1;\n}".to_string());
+    repl.insert("let a = 1;".to_string());
+    // what `:sync` does after the lines were removed in the editor
+    repl.body = vec![
+        FN_MAIN.to_string(),
+        "} // Do not write past this line (it will corrupt the repl)".to_string(),
+    ];
+    repl.cursor = 1;
+    assert!(repl.del_item("foo").is_err());
+
+    // lines before main aren't session code
+    let mut repl = Repl::new();
+    repl.set_prelude(vec!["let a = 1;".to_string()]);
+    repl.insert("let a = 1;".to_string());
+    repl.del_item("a").unwrap();
+    assert_eq!(repl.body[0], "let a = 1;");
+    assert_eq!(repl.body.len(), 3);
 }
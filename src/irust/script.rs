@@ -68,7 +68,7 @@ impl ScriptManager {
         let compilation = (|| {
             Command::new("cargo")
                 .arg("build")
-                .args(&["--target-dir", &script_target_dir.display().to_string()])
+                .args(["--target-dir", &script_target_dir.display().to_string()])
                 .current_dir(script_path)
                 .spawn()
                 .ok()?
@@ -113,6 +113,18 @@ impl ScriptManager {
         }
     }
 
+    pub fn welcome(&self, global_variables: &GlobalVariables) -> Option<String> {
+        unsafe {
+            let script: PromptFn = self.lib.get(b"welcome").ok()?;
+            Some(
+                CString::from_raw(script(global_variables))
+                    .to_str()
+                    .ok()?
+                    .to_string(),
+            )
+        }
+    }
+
     pub fn get_output_prompt(&self, global_variables: &GlobalVariables) -> Option<String> {
         unsafe {
             let script: PromptFn = self.lib.get(b"output_prompt").ok()?;
@@ -129,7 +141,7 @@ impl ScriptManager {
 type PromptFn<'lib> = Symbol<'lib, unsafe extern "C" fn(&GlobalVariables) -> &mut c_char>;
 
 fn create_script_dir_with_src(script_path: &Path) -> Option<()> {
-    let _ = std::fs::create_dir_all(&script_path.join("src"));
+    let _ = std::fs::create_dir_all(script_path.join("src"));
 
     let cargo_toml_file = script_path.join("Cargo.toml");
 
@@ -0,0 +1,354 @@
+use super::cargo_cmds::cargo_run;
+use super::env::{set_env_vars, unset_env_vars};
+use super::{IRust, Result};
+use printer::printer::{PrintQueue, PrinterItem};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+
+/// Printed before the interpolated values, to separate them from the output of the repl statements
+const INTERPOLATION_MARK: &str = "\u{1}IRUST_INTERPOLATION\u{1}";
+
+#[derive(Debug, PartialEq)]
+enum Part {
+    Text(String),
+    /// A rust expression, printed with Display or Debug (`#{expr:?}`)
+    Expr {
+        expr: String,
+        debug: bool,
+    },
+}
+
+enum ShellOutput {
+    Stdout(String),
+    Stderr(String),
+}
+
+impl IRust {
+    /// Run a `::` shell command
+    pub fn run_cmd(&mut self) -> Result<PrintQueue> {
+        // remove ::
+        let buffer = self.buffer.to_string()[2..].trim().to_string();
+        // these need to change IRust own process, the values are used as they are
+        let builtin = matches!(
+            buffer.split_whitespace().next(),
+            Some("cd") | Some("export") | Some("unset")
+        );
+        // exp: ::echo #{my_var} #{v:?}
+        let buffer = self.interpolate(&buffer, !builtin)?;
+
+        match buffer.split_whitespace().next() {
+            // these need to change IRust own process
+            Some("cd") => {
+                self.buffer = format!("::{}", buffer).as_str().into();
+                self.cd()
+            }
+            // exp: ::export RUST_BACKTRACE=1 RUSTFLAGS="-C target-cpu=native"
+            Some("export") => set_env_vars(&buffer["export".len()..]),
+            Some("unset") => unset_env_vars(&buffer["unset".len()..]),
+            _ => self.run_in_shell(&buffer),
+        }
+    }
+
+    /// Run a shell command and bind its stdout to a `String` in the session
+    ///
+    /// exp: let files = ::ls -la
+    pub fn capture_cmd(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        let (binding, cmd) = shell_capture(&buffer).ok_or("Expected `let <name> = ::<command>`")?;
+        let cmd = self.interpolate(cmd, true)?;
+
+        let output = shell_command(&cmd)
+            .current_dir(self.global_variables.get_cwd())
+            .stdin(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr)
+                .trim()
+                .to_string()
+                .into());
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // Debug formatting gives a valid rust string literal
+        self.repl.insert(format!(
+            "let {}: String = {:?}.to_string();",
+            binding, stdout
+        ));
+        Ok(PrintQueue::default())
+    }
+
+    /// Replace `#{expr}` with the value of the rust expression evaluated in the repl,
+    /// `quote` makes each value a single shell word
+    fn interpolate(&mut self, cmd: &str, quote: bool) -> Result<String> {
+        let parts = interpolation_parts(cmd);
+        if !parts.iter().any(|part| matches!(part, Part::Expr { .. })) {
+            return Ok(cmd.to_string());
+        }
+
+        // evaluate all the expressions at once, their values are separated with \0
+        let mut eval = format!("print!(\"{}\");", INTERPOLATION_MARK);
+        for part in &parts {
+            if let Part::Expr { expr, debug } = part {
+                let format = if *debug { "{:?}\\0" } else { "{}\\0" };
+                eval.push_str(&format!("print!(\"{}\", {{\n{}\n}});", format, expr));
+            }
+        }
+
+        let toolchain = self.options.toolchain;
+        let mut result = None;
+        self.repl.eval_in_tmp_repl(eval, || {
+            result = Some(cargo_run(false, false, toolchain)?);
+            Ok(())
+        })?;
+        // safe unwrap: eval_in_tmp_repl runs the closure or returns an error
+        let (status, output) = result.unwrap();
+        if !status.success() {
+            return Err(output.into());
+        }
+        let mut values = output
+            .split_once(INTERPOLATION_MARK)
+            .map(|(_, values)| values)
+            .ok_or("failed to evaluate the interpolated values")?
+            .split('\0');
+
+        let mut cmd = String::new();
+        for part in parts {
+            match part {
+                Part::Text(text) => cmd.push_str(&text),
+                Part::Expr { .. } => {
+                    let value = values.next().unwrap_or_default();
+                    if quote {
+                        cmd.push_str(&shell_quote(value));
+                    } else {
+                        cmd.push_str(value);
+                    }
+                }
+            }
+        }
+        Ok(cmd)
+    }
+
+    /// Delegate to the user shell so pipes, redirections, globs etc.. work,
+    /// the output is printed as it comes
+    ///
+    /// The command gets no input, the terminal stays in raw mode so ctrl-c can be read meanwhile
+    fn run_in_shell(&mut self, cmd: &str) -> Result<PrintQueue> {
+        let mut child = shell_command(cmd)
+            .current_dir(self.global_variables.get_cwd())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let (sender, receiver) = mpsc::channel();
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or("failed to read the command stdout")?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or("failed to read the command stderr")?;
+        let stdout_thread = forward_lines(stdout, sender.clone(), ShellOutput::Stdout);
+        let stderr_thread = forward_lines(stderr, sender, ShellOutput::Stderr);
+
+        let mut first_line = true;
+        // ends when both outputs are closed
+        loop {
+            let output = match receiver.recv_timeout(std::time::Duration::from_millis(20)) {
+                Ok(output) => output,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if crate::utils::ctrl_c_pressed(std::time::Duration::from_millis(0)) {
+                        child.kill()?;
+                        child.wait()?;
+                        return Err(crate::utils::INTERRUPTED.into());
+                    }
+                    continue;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            let mut print_queue = PrintQueue::default();
+            if !first_line {
+                print_queue.add_new_line(1);
+            }
+            first_line = false;
+            match output {
+                ShellOutput::Stdout(line) => {
+                    print_queue.push(PrinterItem::String(line, self.options.shell_color))
+                }
+                ShellOutput::Stderr(line) => {
+                    print_queue.push(PrinterItem::String(line, self.options.err_color))
+                }
+            }
+            self.printer.print_output(print_queue)?;
+            std::io::Write::flush(&mut self.printer.writer.raw)?;
+        }
+        let _ = stdout_thread.join();
+        let _ = stderr_thread.join();
+        child.wait()?;
+
+        Ok(PrintQueue::default())
+    }
+}
+
+/// Returns the binding and the shell command of a `let <binding> = ::<command>` input
+pub fn shell_capture(input: &str) -> Option<(&str, &str)> {
+    let (binding, cmd) = input.trim().strip_prefix("let ")?.split_once('=')?;
+    let cmd = cmd.trim_start().strip_prefix("::")?.trim();
+    // an absolute rust path, exp: let pi = ::std::f64::consts::PI;
+    if cmd.split_whitespace().next()?.contains("::") || cmd.ends_with(';') {
+        return None;
+    }
+    let binding = binding.trim();
+    let name = binding.strip_prefix("mut ").unwrap_or(binding).trim();
+    if cmd.is_empty() || name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((binding, cmd))
+}
+
+/// Split a shell command into text and `#{expr}` parts
+///
+/// The rest is left to the shell, exp: `{a,b}` or `awk '{print $1}'`, `##{` is a literal `#{`
+fn interpolation_parts(cmd: &str) -> Vec<Part> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut rest = cmd;
+
+    while let Some(idx) = rest.find("#{") {
+        if rest[..idx].ends_with('#') {
+            text.push_str(&rest[..idx + 2]);
+            // `##{` -> `#{`
+            text.remove(text.len() - 3);
+            rest = &rest[idx + 2..];
+            continue;
+        }
+        text.push_str(&rest[..idx]);
+        let mut expr = String::new();
+        let mut depth = 1;
+        let mut end = rest.len();
+        for (i, c) in rest[idx + 2..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => (),
+            }
+            if depth == 0 {
+                end = idx + 2 + i + 1;
+                break;
+            }
+            expr.push(c);
+        }
+        rest = &rest[end..];
+
+        if !text.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut text)));
+        }
+        let debug = expr.ends_with(":?");
+        if debug {
+            expr.truncate(expr.len() - 2);
+        }
+        parts.push(Part::Expr { expr, debug });
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    parts
+}
+
+/// Make `value` a single word of the shell command, whatever it contains
+fn shell_quote(value: &str) -> String {
+    #[cfg(windows)]
+    {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+    #[cfg(not(windows))]
+    {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn shell_command(cmd: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(cmd);
+        command
+    }
+    #[cfg(not(windows))]
+    {
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+        let mut command = Command::new(shell);
+        command.arg("-c").arg(cmd);
+        command
+    }
+}
+
+fn forward_lines(
+    output: impl Read + Send + 'static,
+    sender: mpsc::Sender<ShellOutput>,
+    wrap: fn(String) -> ShellOutput,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for line in BufReader::new(output).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if sender.send(wrap(line)).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+#[test]
+fn interpolation_parts_test() {
+    assert_eq!(
+        interpolation_parts("echo #{a} ${HOME} #{v:?} {} {a,b} ##{x}"),
+        vec![
+            Part::Text("echo ".into()),
+            Part::Expr {
+                expr: "a".into(),
+                debug: false
+            },
+            Part::Text(" ${HOME} ".into()),
+            Part::Expr {
+                expr: "v".into(),
+                debug: true
+            },
+            Part::Text(" {} {a,b} #{x}".into()),
+        ]
+    );
+    assert_eq!(
+        interpolation_parts("awk '{print $1}' #{ { 1 } }"),
+        vec![
+            Part::Text("awk '{print $1}' ".into()),
+            Part::Expr {
+                expr: " { 1 } ".into(),
+                debug: false
+            },
+        ]
+    );
+}
+
+#[cfg(not(windows))]
+#[test]
+fn shell_quote_test() {
+    assert_eq!(shell_quote("a b"), "'a b'");
+    assert_eq!(shell_quote("it's; rm x"), "'it'\\''s; rm x'");
+}
+
+#[test]
+fn shell_capture_test() {
+    assert_eq!(shell_capture("let out = ::ls -la"), Some(("out", "ls -la")));
+    assert_eq!(
+        shell_capture("let mut out = :: cat a | wc -l"),
+        Some(("mut out", "cat a | wc -l"))
+    );
+    assert_eq!(shell_capture("let a = b::c()"), None);
+    assert_eq!(shell_capture("let (a, b) = ::ls"), None);
+    assert_eq!(shell_capture("let pi = ::std::f64::consts::PI;"), None);
+}
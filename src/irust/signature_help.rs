@@ -0,0 +1,218 @@
+use super::{BelowInputRow, IRust, Result};
+
+const KEYWORDS: &[&str] = &["if", "while", "match", "for", "in", "return", "let", "fn"];
+
+/// The function call the input ends in
+#[derive(Debug, PartialEq)]
+struct Call<'a> {
+    name: &'a str,
+    /// Byte index of the opening parenthesis
+    paren: usize,
+    /// Index of the argument being written
+    arg: usize,
+    /// `a.foo(`, the `self` parameter is skipped then
+    method: bool,
+}
+
+fn call_at_end(input: &str) -> Option<Call<'_>> {
+    let mut depth = 0;
+    let mut arg = 0;
+    let mut in_str = false;
+
+    for (idx, c) in input.char_indices().rev() {
+        if in_str {
+            in_str = c != '"';
+            continue;
+        }
+        match c {
+            '"' => in_str = true,
+            ')' | ']' | '}' => depth += 1,
+            // inside an array or a block
+            '[' | '{' if depth == 0 => return None,
+            '(' | '[' | '{' if depth != 0 => depth -= 1,
+            ',' if depth == 0 => arg += 1,
+            '(' => {
+                let before = &input[..idx];
+                let name_start = before
+                    .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .map(|idx| idx + before[idx..].chars().next().unwrap().len_utf8())
+                    .unwrap_or(0);
+                let name = &before[name_start..];
+                let preceding = &before[..name_start];
+
+                let is_definition = preceding.split_whitespace().last() == Some("fn");
+                if name.is_empty()
+                    || name.starts_with(|c: char| c.is_ascii_digit())
+                    || KEYWORDS.contains(&name)
+                    || is_definition
+                {
+                    return None;
+                }
+                return Some(Call {
+                    name,
+                    paren: idx,
+                    arg,
+                    method: preceding.ends_with('.'),
+                });
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Split a definition into its head up to `(`, its parameters and its tail from `)`
+fn split_signature(definition: &str) -> Option<(&str, Vec<&str>, &str)> {
+    let open = definition.find('(')?;
+    let mut params = vec![];
+    let mut param_start = open + 1;
+    let mut depth = 0;
+    let mut previous = ' ';
+
+    for (idx, c) in definition.char_indices().skip_while(|(idx, _)| *idx < open) {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' => depth -= 1,
+            // `->` in `impl Fn(u8) -> u8` is not a closing bracket
+            '>' if previous != '-' => depth -= 1,
+            ',' if depth == 1 => {
+                params.push(definition[param_start..idx].trim());
+                param_start = idx + 1;
+            }
+            _ => (),
+        }
+        if depth == 0 {
+            let last = definition[param_start..idx].trim();
+            if !last.is_empty() {
+                params.push(last);
+            }
+            return Some((&definition[..=open], params, &definition[idx..]));
+        }
+        previous = c;
+    }
+    None
+}
+
+fn is_self_param(param: &str) -> bool {
+    let param = param.trim_start_matches('&');
+    // skip the lifetime, exp: `&'a self`
+    let param = match param.strip_prefix('\'') {
+        Some(param) => param.split_once(' ').map_or("", |(_, param)| param),
+        None => param,
+    };
+    let param = param.strip_prefix("mut ").unwrap_or(param);
+    param == "self" || param.starts_with("self:")
+}
+
+impl IRust {
+    /// Look up the signature of the function call the cursor is in
+    pub fn update_signature_help(&mut self) -> Result<()> {
+        if !self.options.signature_help {
+            return Ok(());
+        }
+        let racer = match self.racer.as_mut() {
+            Some(racer) => racer,
+            None => return Ok(()),
+        };
+
+        let input: String = self.buffer.iter().take(self.buffer.buffer_pos).collect();
+        let call = match call_at_end(&input) {
+            Some(call) if !input.starts_with(':') => call,
+            _ => {
+                self.signature_help = None;
+                return Ok(());
+            }
+        };
+        // still in the same call
+        if matches!(&self.signature_help, Some((name, _)) if name == call.name) {
+            return Ok(());
+        }
+
+        let definition =
+            racer.definition_of(input[..call.paren].to_string(), call.name, &mut self.repl)?;
+        self.signature_help = definition.map(|definition| (call.name.to_string(), definition));
+        Ok(())
+    }
+
+    /// The signature drawn under the input, with the current parameter highlighted
+    pub fn signature_help_row(&self) -> Option<BelowInputRow> {
+        let (name, definition) = self.signature_help.as_ref()?;
+        let input: String = self.buffer.iter().take(self.buffer.buffer_pos).collect();
+        let call = call_at_end(&input).filter(|call| call.name == name)?;
+
+        let color = self.options.signature_help_color;
+        let definition = definition.trim_end().trim_end_matches('{').trim_end();
+        let (head, params, tail) = match split_signature(definition) {
+            Some(signature) => signature,
+            // multi-line signatures are cut by racer
+            None => return Some(vec![(definition.to_string(), color, None)]),
+        };
+
+        let skip_self = call.method && params.first().map(|p| is_self_param(p)) == Some(true);
+        let current_param = call.arg + skip_self as usize;
+
+        let mut row = vec![(head.to_string(), color, None)];
+        for (idx, param) in params.iter().enumerate() {
+            if idx != 0 {
+                row.push((", ".to_string(), color, None));
+            }
+            let param_color = if idx == current_param {
+                self.options.signature_help_param_color
+            } else {
+                color
+            };
+            row.push((param.to_string(), param_color, None));
+        }
+        row.push((tail.to_string(), color, None));
+        Some(row)
+    }
+
+    pub fn hide_signature_help(&mut self) -> Result<()> {
+        let shown = self.signature_help_row().is_some();
+        self.signature_help = None;
+        if shown {
+            self.print_input()?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn call_at_end_test() {
+    assert_eq!(
+        call_at_end("let a = foo(1, bar(2), \"a,b\", "),
+        Some(Call {
+            name: "foo",
+            paren: 11,
+            arg: 3,
+            method: false
+        })
+    );
+    assert_eq!(
+        call_at_end("v.push("),
+        Some(Call {
+            name: "push",
+            paren: 6,
+            arg: 0,
+            method: true
+        })
+    );
+    assert_eq!(call_at_end("foo(1)"), None);
+    assert_eq!(call_at_end("fn foo(a"), None);
+    assert_eq!(call_at_end("if (a"), None);
+    assert_eq!(call_at_end("println!(\"{}\", "), None);
+}
+
+#[test]
+fn split_signature_test() {
+    assert_eq!(
+        split_signature("pub fn foo<T>(a: HashMap<u8, T>, f: impl Fn(u8) -> u8) -> u8"),
+        Some((
+            "pub fn foo<T>(",
+            vec!["a: HashMap<u8, T>", "f: impl Fn(u8) -> u8"],
+            ") -> u8"
+        ))
+    );
+    assert_eq!(split_signature("fn bar()"), Some(("fn bar(", vec![], ")")));
+    assert_eq!(split_signature("pub fn baz("), None);
+}
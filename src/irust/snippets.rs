@@ -0,0 +1,81 @@
+use super::{IRust, Result};
+
+/// Marks where the cursor goes after the expansion
+const CURSOR_MARK: &str = "$0";
+
+/// The snippet text indented like the line it's expanded in, and the cursor offset in chars
+fn expand(snippet: &str, indent: &str) -> (String, usize) {
+    let text = snippet.replace('\n', &format!("\n{}", indent));
+    match text.find(CURSOR_MARK) {
+        Some(idx) => (
+            text.replacen(CURSOR_MARK, "", 1),
+            text[..idx].chars().count(),
+        ),
+        None => {
+            let len = text.chars().count();
+            (text, len)
+        }
+    }
+}
+
+/// The word before the cursor and the leading whitespace of its line
+fn word_and_indent(before_cursor: &[char]) -> (String, String) {
+    let word_start = before_cursor
+        .iter()
+        .rposition(|c| !(c.is_alphanumeric() || *c == '_'))
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    let line_start = before_cursor
+        .iter()
+        .rposition(|c| *c == '\n')
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+
+    let word = before_cursor[word_start..].iter().collect();
+    let indent = before_cursor[line_start..]
+        .iter()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    (word, indent)
+}
+
+impl IRust {
+    /// Replace the word before the cursor with its snippet, returns false if there is none
+    pub fn expand_snippet(&mut self) -> Result<bool> {
+        let before_cursor = &self.buffer.buffer[..self.buffer.buffer_pos];
+        let (word, indent) = word_and_indent(before_cursor);
+        let snippet = match self.options.snippets.get(&word) {
+            Some(snippet) if !word.is_empty() => snippet,
+            _ => return Ok(false),
+        };
+        let (text, cursor_offset) = expand(snippet, &indent);
+
+        let word_start = self.buffer.buffer_pos - word.chars().count();
+        self.buffer.set_buffer_pos(word_start);
+        for _ in word.chars() {
+            self.buffer.remove_current_char();
+        }
+        self.buffer.insert_str(&text);
+        self.buffer.set_buffer_pos(word_start + cursor_offset);
+        self.print_input()?;
+
+        self.printer.cursor.goto_buffer_pos(&self.buffer);
+        Ok(true)
+    }
+}
+
+#[test]
+fn expand_test() {
+    assert_eq!(
+        expand("for i in 0..$0 {\n}\n", "    "),
+        ("for i in 0.. {\n    }\n    ".to_string(), 12)
+    );
+    assert_eq!(
+        expand("println!(\"é\")", ""),
+        ("println!(\"é\")".to_string(), 13)
+    );
+    assert_eq!(
+        word_and_indent(&"  a\n    let x = fori".chars().collect::<Vec<_>>()),
+        ("fori".to_string(), "    ".to_string())
+    );
+}
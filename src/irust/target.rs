@@ -0,0 +1,80 @@
+//! `:target` builds the session for another target and runs it with a runner, exp: `wasm32-wasi` with wasmtime
+use super::cargo_cmds::{Target, ToolChain, TARGET};
+use super::{IRust, Result};
+use crate::utils::split_args;
+use printer::printer::{PrintQueue, PrinterItem};
+use std::process::Command;
+
+/// The installed targets of the toolchain, None if rustup isn't available
+fn installed_targets(toolchain: ToolChain) -> Option<Vec<String>> {
+    let output = Command::new("rustup")
+        .args([
+            "target",
+            "list",
+            "--installed",
+            "--toolchain",
+            toolchain.name(),
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect(),
+    )
+}
+
+impl IRust {
+    /// `:target [triple] [runner..]` builds the session for the target and runs it with the runner (`target_runners` by default),
+    /// `host` goes back to the host target, without arguments it shows the current target
+    pub fn target(&mut self) -> Result<PrintQueue> {
+        let mut args = split_args(self.buffer.to_string());
+        args.remove(0); // drop :target
+
+        if let Some(triple) = args.first().cloned() {
+            self.set_target(triple, &args[1..])?;
+        }
+
+        // the selected target
+        let current = match &*TARGET.lock().expect("poisoned lock") {
+            Some(target) if target.runner.is_empty() => target.triple.clone(),
+            Some(target) => format!("{} (runner: {})", target.triple, target.runner.join(" ")),
+            None => "host".to_string(),
+        };
+        let mut print_queue = PrintQueue::default();
+        print_queue.push(PrinterItem::String(current, self.options.ok_color));
+        print_queue.add_new_line(1);
+        Ok(print_queue)
+    }
+
+    fn set_target(&mut self, triple: String, runner: &[String]) -> Result<()> {
+        if triple == "host" {
+            *TARGET.lock().expect("poisoned lock") = None;
+        } else {
+            if let Some(installed) = installed_targets(self.options.toolchain) {
+                if !installed.contains(&triple) {
+                    return Err(format!(
+                        "The target `{}` isn't installed, install it with `::rustup target add {}`",
+                        triple, triple
+                    )
+                    .into());
+                }
+            }
+            let runner = if runner.is_empty() {
+                self.options
+                    .target_runners
+                    .get(&triple)
+                    .map(|runner| split_args(runner.clone()))
+                    .unwrap_or_default()
+            } else {
+                runner.to_vec()
+            };
+            *TARGET.lock().expect("poisoned lock") = Some(Target { triple, runner });
+        }
+        self.update_status_line()
+    }
+}
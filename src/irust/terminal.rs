@@ -0,0 +1,143 @@
+//! Queries answered by the terminal itself
+//!
+//! The answers arrive as input events, so these need raw mode. The other events read while waiting
+//! for an answer (exp: keys typed during startup) are returned so they can be handled later.
+use crossterm::event::Event;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// How long to wait for the terminal to answer, terminals that don't support a query never answer
+#[cfg(unix)]
+const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Background {
+    /// Ask the terminal
+    Auto,
+    Light,
+    Dark,
+}
+
+/// Detect the terminal background, default to dark if the terminal doesn't tell us
+///
+/// Returns the events that were read and are not part of the answer
+pub fn detect_background(out: &mut impl Write) -> (Background, Vec<Event>) {
+    let mut other_events = Vec::new();
+    let background = match query_background_color(out, &mut other_events) {
+        Some((r, g, b)) if is_light(r, g, b) => Background::Light,
+        _ => Background::Dark,
+    };
+    (background, other_events)
+}
+
+fn is_light(r: u8, g: u8, b: u8) -> bool {
+    // relative luminance
+    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32 > 128.0
+}
+
+/// Ask the terminal for its background color with OSC 11
+#[cfg(unix)]
+fn query_background_color(
+    out: &mut impl Write,
+    other_events: &mut Vec<Event>,
+) -> Option<(u8, u8, u8)> {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    write!(out, "\x1b]11;?\x07").ok()?;
+    out.flush().ok()?;
+
+    // exp: \x1b]11;rgb:ffff/ffff/ffff\x07
+    // the event reader sees ESC ] as alt-], BEL as ctrl-g and ST (ESC \) as alt-\
+    let deadline = std::time::Instant::now() + QUERY_TIMEOUT;
+    let mut answer: Option<String> = None;
+    loop {
+        let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+        if !crossterm::event::poll(timeout).ok()? {
+            // timeout, a partial answer is dropped
+            return None;
+        }
+        let event = crossterm::event::read().ok()?;
+        let key_event = match event {
+            Event::Key(key_event) => key_event,
+            event => {
+                other_events.push(event);
+                continue;
+            }
+        };
+        let answer = match answer.as_mut() {
+            Some(answer) => answer,
+            None => {
+                if key_event.code == KeyCode::Char(']') && key_event.modifiers == KeyModifiers::ALT
+                {
+                    answer = Some(String::new());
+                } else {
+                    other_events.push(Event::Key(key_event));
+                }
+                continue;
+            }
+        };
+        match (key_event.code, key_event.modifiers) {
+            (KeyCode::Char('g'), KeyModifiers::CONTROL)
+            | (KeyCode::Char('\\'), KeyModifiers::ALT) => return parse_osc11_answer(answer),
+            // ST split in two events
+            (KeyCode::Char('\\'), _) if answer.ends_with('\x1b') => {
+                return parse_osc11_answer(answer)
+            }
+            (KeyCode::Esc, _) => answer.push('\x1b'),
+            (KeyCode::Char(c), _) => answer.push(c),
+            // not an answer after all
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn query_background_color(
+    _out: &mut impl Write,
+    _other_events: &mut Vec<Event>,
+) -> Option<(u8, u8, u8)> {
+    None
+}
+
+/// Parse the answer to OSC 11, with or without its leading ESC ] and its terminator
+#[cfg(unix)]
+fn parse_osc11_answer(answer: &str) -> Option<(u8, u8, u8)> {
+    let rgb = answer.split("rgb:").nth(1)?;
+    let rgb = rgb.trim_end_matches(['\x07', '\\', '\x1b']);
+
+    // each component has 1 to 4 hex digits
+    let mut components = rgb.split('/').map(|c| {
+        if c.is_empty() || c.len() > 4 {
+            return None;
+        }
+        let value = u64::from_str_radix(c, 16).ok()?;
+        let max = 16u64.pow(c.len() as u32) - 1;
+        Some((value * 255 / max) as u8)
+    });
+    Some((
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    ))
+}
+
+#[cfg(unix)]
+#[test]
+fn parse_osc11_answer_test() {
+    assert_eq!(
+        parse_osc11_answer("\x1b]11;rgb:ffff/ffff/ffff\x07"),
+        Some((255, 255, 255))
+    );
+    assert_eq!(
+        parse_osc11_answer("\x1b]11;rgb:28/2c/34\x1b\\"),
+        Some((0x28, 0x2c, 0x34))
+    );
+    assert_eq!(
+        parse_osc11_answer("11;rgb:ffff/ffff/0000"),
+        Some((255, 255, 0))
+    );
+    assert_eq!(parse_osc11_answer("rgb:fffffff/0/0"), None);
+    assert_eq!(parse_osc11_answer("garbage"), None);
+    assert!(!is_light(0x28, 0x2c, 0x34));
+    assert!(is_light(0xfd, 0xf6, 0xe3));
+}
@@ -0,0 +1,168 @@
+//! The last inputs and outputs of the session, shown by `:goto N` and optionally kept on exit (`exit_transcript`)
+use super::highlight::highlight;
+use super::{prompt, IRust, Result};
+use crate::utils::{expand_home, strip_ansi_escapes};
+use printer::printer::{PrintQueue, PrinterItem};
+use std::collections::VecDeque;
+use std::io::Write;
+
+/// Older exchanges are dropped
+const MAX_EXCHANGES: usize = 500;
+
+struct Exchange {
+    operation_number: usize,
+    input: String,
+    output: PrintQueue,
+}
+
+#[derive(Default)]
+pub struct Transcript {
+    exchanges: VecDeque<Exchange>,
+}
+
+impl Transcript {
+    pub fn record(&mut self, operation_number: usize, input: String, output: PrintQueue) {
+        if self.exchanges.len() == MAX_EXCHANGES {
+            self.exchanges.pop_front();
+        }
+        self.exchanges.push_back(Exchange {
+            operation_number,
+            input,
+            output,
+        });
+    }
+
+    fn position(&self, operation_number: usize) -> Option<usize> {
+        self.exchanges
+            .iter()
+            .position(|exchange| exchange.operation_number == operation_number)
+    }
+}
+
+impl IRust {
+    /// `:goto [N]` shows the transcript from the exchange N, the last one by default
+    pub fn goto(&mut self) -> Result<PrintQueue> {
+        let buffer = self.buffer.to_string();
+        let operation_number = match buffer.split_whitespace().nth(1) {
+            Some(n) => n
+                .parse()
+                .map_err(|_| format!("Invalid input/output number: `{}`", n))?,
+            None => self
+                .transcript
+                .exchanges
+                .back()
+                .map(|exchange| exchange.operation_number)
+                .ok_or("Nothing was evaluated yet")?,
+        };
+        if operation_number >= self.global_variables.operation_number {
+            return Err(format!("Input/output {} doesn't exist yet", operation_number).into());
+        }
+        let position = self.transcript.position(operation_number).ok_or_else(|| {
+            format!(
+                "Input/output {} is not in the transcript, only the last {} are kept",
+                operation_number, MAX_EXCHANGES
+            )
+        })?;
+
+        let (transcript, starts) = self.render_transcript();
+        self.page("IRust transcript", transcript, vec![], starts[position])?;
+        Ok(PrintQueue::default())
+    }
+
+    /// The exchanges with their prompts, and the line where each exchange starts
+    fn render_transcript(&mut self) -> (PrintQueue, Vec<usize>) {
+        let mut transcript = PrintQueue::default();
+        let mut starts = vec![];
+        let current_operation_number = self.global_variables.operation_number;
+        for exchange in &self.transcript.exchanges {
+            starts.push(
+                transcript
+                    .clone()
+                    .filter(|item| matches!(item, PrinterItem::NewLine))
+                    .count(),
+            );
+            // the prompts are rendered with the number of the exchange
+            self.global_variables.operation_number = exchange.operation_number;
+            let input_prompt = prompt::render(
+                &self.options.input_prompt,
+                &self.global_variables,
+                &self.last_eval,
+                self.options.toolchain,
+            );
+            transcript.push(PrinterItem::String(input_prompt, self.options.prompt_color));
+            transcript.append(&mut highlight(&exchange.input.as_str().into(), &self.theme));
+            transcript.add_new_line(1);
+            transcript.append(&mut exchange.output.clone());
+            transcript.add_new_line(1);
+        }
+        self.global_variables.operation_number = current_operation_number;
+        (transcript, starts)
+    }
+
+    /// Send the transcript to `exit_transcript`, called after leaving the alternate screen
+    pub fn dump_transcript(&mut self) -> Result<()> {
+        if self.transcript.exchanges.is_empty() {
+            return Ok(());
+        }
+        match self.options.exit_transcript.as_str() {
+            "" | "none" => Ok(()),
+            "screen" => {
+                let (transcript, _) = self.render_transcript();
+                self.printer.print_output(transcript)
+            }
+            path => {
+                let path = expand_home(path);
+                let (transcript, _) = self.render_transcript();
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| file.write_all(plain_text(transcript).as_bytes()))
+                    .map_err(|e| {
+                        format!(
+                            "Failed to write the transcript to {}: {}",
+                            path.display(),
+                            e
+                        )
+                    })?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The text without colors, the outputs can have escape codes (exp: cargo errors)
+fn plain_text(queue: PrintQueue) -> String {
+    let text: String = queue
+        .map(|item| match item {
+            PrinterItem::Char(c, _) => c.to_string(),
+            PrinterItem::String(s, _) => s,
+            PrinterItem::Str(s, _) => s.to_string(),
+            PrinterItem::NewLine => "\n".to_string(),
+        })
+        .collect();
+    strip_ansi_escapes(&text)
+}
+
+#[test]
+fn transcript_test() {
+    let mut transcript = Transcript::default();
+    for operation_number in 1..=MAX_EXCHANGES + 2 {
+        transcript.record(operation_number, String::new(), PrintQueue::default());
+    }
+    assert_eq!(transcript.exchanges.len(), MAX_EXCHANGES);
+    assert_eq!(transcript.position(2), None);
+    assert_eq!(transcript.position(3), Some(0));
+}
+
+#[test]
+fn plain_text_test() {
+    let mut queue = PrintQueue::default();
+    queue.push(PrinterItem::Str("In: ", crossterm::style::Color::Yellow));
+    queue.push(PrinterItem::String(
+        "\x1b[1m\x1b[91merror\x1b[0m".to_string(),
+        crossterm::style::Color::Red,
+    ));
+    queue.add_new_line(1);
+    assert_eq!(plain_text(queue), "In: error\n");
+}
@@ -0,0 +1,147 @@
+use super::{IRust, Result};
+use printer::printer::{PrintQueue, PrinterItem};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often the watched file modification time is checked
+pub const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+        Self { path, modified }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if the file was modified since the last call
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        // a missing file (editors can remove it while saving) is not a change
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl IRust {
+    pub fn watch(&mut self) -> Result<PrintQueue> {
+        // exp: :watch scratch.rs
+        let buffer = self.buffer.to_string();
+        let path = buffer
+            .split_once(' ')
+            .map(|(_, path)| path.trim())
+            .filter(|path| !path.is_empty())
+            .ok_or("No path specified")?;
+        let path = Path::new(path).to_path_buf();
+        if !path.is_file() {
+            return Err(format!("{} is not a file", path.display()).into());
+        }
+
+        self.watcher = Some(Watcher::new(path.clone()));
+        let mut print_queue = PrintQueue::default();
+        print_queue.push(PrinterItem::String(
+            format!("Watching {}, `:unwatch` to stop", path.display()),
+            self.options.irust_color,
+        ));
+        print_queue.add_new_line(1);
+        print_queue.append(&mut self.load_inner(path)?);
+        Ok(print_queue)
+    }
+
+    pub fn unwatch(&mut self) -> Result<PrintQueue> {
+        let watcher = self.watcher.take().ok_or("No file is being watched")?;
+        let mut print_queue = PrintQueue::default();
+        print_queue.push(PrinterItem::String(
+            format!("Stopped watching {}", watcher.path().display()),
+            self.options.ok_color,
+        ));
+        print_queue.add_new_line(1);
+        Ok(print_queue)
+    }
+
+    /// Reload and re-evaluate the watched file if it changed, the current input is kept
+    pub fn check_watched_file(&mut self) -> Result<()> {
+        let path = match self.watcher.as_mut() {
+            Some(watcher) => {
+                if !watcher.changed() {
+                    return Ok(());
+                }
+                watcher.path().to_path_buf()
+            }
+            None => return Ok(()),
+        };
+
+        self.remove_history_suggestion_and_reprint()?;
+        let buffer = self.buffer.take();
+
+        // the output takes the place of the current input, which is printed again after it
+        self.printer.cursor.hide();
+        self.printer.cursor.goto_start();
+        self.printer.clear_from_cursor_down()?;
+
+        let mut output = PrintQueue::default();
+        output.push(PrinterItem::String(
+            format!("[watch] {} changed", path.display()),
+            self.options.irust_color,
+        ));
+        output.add_new_line(1);
+        match self.load_inner(path) {
+            Ok(mut eval_output) => output.append(&mut eval_output),
+            Err(e) => {
+                output.push(PrinterItem::String(e.to_string(), self.options.err_color));
+                output.add_new_line(1);
+            }
+        }
+        self.printer.print_output(output)?;
+        self.update_status_line()?;
+
+        self.printer.print_prompt_if_set()?;
+        self.buffer.buffer = buffer;
+        self.print_input()?;
+        let last_input_pos = self.printer.cursor.input_last_pos(&self.buffer);
+        self.buffer.goto_end();
+        self.printer.cursor.goto(last_input_pos.0, last_input_pos.1);
+        self.printer.cursor.show();
+
+        std::io::Write::flush(&mut self.printer.writer.raw)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn watcher_detects_modifications() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static TEST_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+    // unique so concurrent test runs don't share the file
+    let path = std::env::temp_dir().join(format!(
+        "irust_watcher_test_{}_{}.rs",
+        std::process::id(),
+        TEST_FILE_COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::write(&path, "fn main() {}").unwrap();
+
+    let mut watcher = Watcher::new(path.clone());
+    assert!(!watcher.changed());
+
+    // as if the file was saved after the watcher started
+    watcher.modified = Some(SystemTime::UNIX_EPOCH);
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(!watcher.changed());
+}
@@ -5,8 +5,8 @@ mod irust;
 mod dependencies;
 mod utils;
 use crate::irust::options::Options;
-use crate::irust::IRust;
-use dependencies::{check_required_deps, warn_about_opt_deps};
+use crate::irust::{recovery, IRust};
+use dependencies::{check_required_deps, check_sccache, warn_about_opt_deps};
 
 use crate::args::handle_args;
 use crossterm::style::Colorize;
@@ -24,16 +24,27 @@ fn main() {
         exit(1);
     }
     warn_about_opt_deps(&mut options);
+    check_sccache(&mut options);
 
-    let mut irust = IRust::new(options);
-    let err = if let Err(e) = irust.run() {
-        Some(e)
-    } else {
-        None
-    };
+    recovery::install_panic_hook();
+    recovery::install_signal_handlers();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+        let mut irust = IRust::new(options);
+        irust.run()
+    }));
 
     // now IRust has been dropped we can safely print to stderr
-    if let Some(err) = err {
-        eprintln!("{}", format!("\r\nIRust exited with error: {}", err).red());
+    match result {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => {
+            eprintln!("{}", format!("\r\nIRust exited with error: {}", err).red());
+        }
+        Err(_) => {
+            // the panic can come before the printer took over the terminal
+            let _ = crossterm::terminal::disable_raw_mode();
+            let message = recovery::take_panic_message().unwrap_or_else(|| "panicked".to_string());
+            eprintln!("{}", format!("\r\nIRust {}", message).red());
+            exit(101);
+        }
     }
 }
@@ -7,11 +7,10 @@ pub fn split_args(s: String) -> Vec<String> {
 
     for c in s.chars() {
         match c {
+            ' ' if quote => tmp.push(' '),
             ' ' => {
-                if !quote && !tmp.is_empty() {
-                    args.push(tmp.drain(..).collect());
-                } else {
-                    tmp.push(' ');
+                if !tmp.is_empty() {
+                    args.push(std::mem::take(&mut tmp));
                 }
             }
             '"' => {
@@ -38,6 +37,64 @@ fn split_args_test() {
     );
 }
 
+/// Simple shell style pattern matching, `*` matches any sequence of characters and `?` matches one character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` in the pattern and the text position it matched up to
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[test]
+fn glob_match_test() {
+    assert!(glob_match(":exit", ":exit"));
+    assert!(glob_match(":*", ":reset"));
+    assert!(glob_match("::l?", "::ls"));
+    assert!(glob_match("*secret*", "let my_secret = 4;"));
+    assert!(!glob_match(":exit", ":exit2"));
+    assert!(!glob_match("::l?", "::l"));
+}
+
+pub fn strip_ansi_escapes(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end with a letter
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// `~/a` => `$HOME/a`
+pub fn expand_home(path: &str) -> std::path::PathBuf {
+    match path.strip_prefix("~/") {
+        Some(path) => dirs_next::home_dir().unwrap_or_default().join(path),
+        None => std::path::PathBuf::from(path),
+    }
+}
+
 pub fn stdout_and_stderr(out: std::process::Output) -> String {
     let out = if !out.stdout.is_empty() {
         out.stdout
@@ -126,10 +183,8 @@ impl StringTools {
     pub fn strings_unique(s1: &str, s2: &mut String) {
         let mut idx = s2.len();
         loop {
-            if !s2[..idx].is_empty() && s1.ends_with(&s2[..idx]) {
-                for _ in 0..idx {
-                    s2.remove(0);
-                }
+            if s2.is_char_boundary(idx) && !s2[..idx].is_empty() && s1.ends_with(&s2[..idx]) {
+                s2.drain(..idx);
                 break;
             }
             if idx == 0 {
@@ -158,45 +213,29 @@ impl StringTools {
         for character in s.chars() {
             // safe unwraps ahead
             match character {
-                '(' => {
-                    if !quote && !double_quote {
-                        *braces.get_mut(&'(').unwrap() += 1;
-                    }
+                '(' if !quote && !double_quote => {
+                    *braces.get_mut(&'(').unwrap() += 1;
                 }
-                ')' => {
-                    if !quote && !double_quote {
-                        *braces.get_mut(&'(').unwrap() -= 1;
-                    }
+                ')' if !quote && !double_quote => {
+                    *braces.get_mut(&'(').unwrap() -= 1;
                 }
-                '[' => {
-                    if !quote && !double_quote {
-                        *braces.get_mut(&'[').unwrap() += 1;
-                    }
+                '[' if !quote && !double_quote => {
+                    *braces.get_mut(&'[').unwrap() += 1;
                 }
-                ']' => {
-                    if !quote && !double_quote {
-                        *braces.get_mut(&'[').unwrap() -= 1;
-                    }
+                ']' if !quote && !double_quote => {
+                    *braces.get_mut(&'[').unwrap() -= 1;
                 }
-                '{' => {
-                    if !quote && !double_quote {
-                        *braces.get_mut(&'{').unwrap() += 1;
-                    }
+                '{' if !quote && !double_quote => {
+                    *braces.get_mut(&'{').unwrap() += 1;
                 }
-                '}' => {
-                    if !quote && !double_quote {
-                        *braces.get_mut(&'{').unwrap() -= 1;
-                    }
+                '}' if !quote && !double_quote => {
+                    *braces.get_mut(&'{').unwrap() -= 1;
                 }
-                '"' => {
-                    if previous_char != '\\' {
-                        double_quote = !double_quote;
-                    }
+                '"' if previous_char != '\\' => {
+                    double_quote = !double_quote;
                 }
-                '\'' => {
-                    if previous_char != '\\' {
-                        quote = !quote;
-                    }
+                '\'' if previous_char != '\\' => {
+                    quote = !quote;
                 }
                 _ => (),
             }
@@ -282,31 +321,81 @@ fn remove_comments(s: &str) -> String {
 }
 
 fn balanced_quotes(s: &str) -> bool {
-    s.match_indices(|p| p == '"' || p == '\'').count() % 2 == 0
+    s.match_indices(['"', '\'']).count() % 2 == 0
+}
+
+/// Printed instead of the output of an interrupted process
+pub const INTERRUPTED: &str = "^C";
+
+/// Wait up to `timeout` for a key press, returns true if it was ctrl-c
+///
+/// Other keys are dropped, they were pressed while a process was running
+pub fn ctrl_c_pressed(timeout: std::time::Duration) -> bool {
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+    matches!(crossterm::event::poll(timeout), Ok(true))
+        && matches!(
+            crossterm::event::read(),
+            Ok(Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            }))
+        )
+}
+
+/// Read a process output in the background, so the process never blocks on a full pipe
+fn read_in_background<R: std::io::Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut output = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        output
+    })
 }
 
 pub trait ProcessUtils {
-    fn output_with_ctrlc_cancel(self) -> Result<std::process::Output>;
+    /// Wait for the output, the process is killed on ctrl-c or once `timeout` elapses
+    fn output_with_ctrlc_cancel(
+        self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<std::process::Output>;
 }
 
 impl ProcessUtils for std::process::Child {
-    fn output_with_ctrlc_cancel(mut self) -> Result<std::process::Output> {
-        use crossterm::event::{Event, KeyCode, KeyEvent};
-
+    fn output_with_ctrlc_cancel(
+        mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<std::process::Output> {
+        let stdout = read_in_background(self.stdout.take());
+        let stderr = read_in_background(self.stderr.take());
+
+        let start = std::time::Instant::now();
         while self.try_wait()?.is_none() {
-            if let Ok(event) = crossterm::event::poll(std::time::Duration::from_millis(100)) {
-                if event {
-                    if let Ok(Event::Key(KeyEvent {
-                        code: KeyCode::Char('c'),
-                        modifiers: crossterm::event::KeyModifiers::CONTROL,
-                    })) = crossterm::event::read()
-                    {
-                        self.kill()?;
-                        return Err("Cancelled!".into());
-                    }
+            if let Some(timeout) = timeout {
+                if start.elapsed() >= timeout {
+                    self.kill()?;
+                    self.wait()?;
+                    return Err(format!("Evaluation timed out after {:?}", timeout).into());
                 }
             }
+            if ctrl_c_pressed(std::time::Duration::from_millis(100)) {
+                self.kill()?;
+                self.wait()?;
+                return Err(INTERRUPTED.into());
+            }
         }
-        self.wait_with_output().map_err(Into::into)
+
+        Ok(std::process::Output {
+            status: self.wait()?,
+            stdout: stdout
+                .join()
+                .map_err(|_| "failed to read the process stdout")?,
+            stderr: stderr
+                .join()
+                .map_err(|_| "failed to read the process stderr")?,
+        })
     }
 }